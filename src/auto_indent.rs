@@ -0,0 +1,171 @@
+//! The indentation given to lines as they are typed.
+//!
+//! A new line copies the indentation of the line it is broken off, one level
+//! more after what opens a block, and a line is moved back a level as soon
+//! as what closes a block is typed on it alone.
+use crate::indent::Indent;
+use crate::language::Language;
+
+/// When lines are indented differently from the ones above them:
+/// `indent_rules = <language>: after <tokens> dedent <tokens>`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Rules {
+    /// What ends a line after which the next one is a level deeper.
+    pub indent_after: Vec<String>,
+    /// What makes a line holding nothing else a level shallower.
+    pub dedent_on: Vec<String>,
+}
+
+impl Rules {
+    /// The rules built into a language.
+    pub fn of(language: &Language) -> Self {
+        let owned = |tokens: &[&str]| {
+            tokens.iter().map(|token| (*token).to_owned()).collect()
+        };
+
+        Self {
+            indent_after: owned(language.indent_after),
+            dedent_on: owned(language.dedent_on),
+        }
+    }
+
+    /// Parses `after <tokens> dedent <tokens>`, either part being optional.
+    pub fn from_key(value: &str) -> Option<Self> {
+        let mut rules = Self::default();
+        let mut list = None;
+
+        // Tokens before either word belong nowhere
+        for word in value.split_whitespace() {
+            match word {
+                "after" => list = Some(&mut rules.indent_after),
+                "dedent" => list = Some(&mut rules.dedent_on),
+                _ => list.as_mut()?.push(word.to_owned()),
+            }
+        }
+
+        let given = list.is_some();
+        given.then_some(rules)
+    }
+
+    /// Returns the indentation of the line broken off at the byte column.
+    pub fn line_break(
+        &self,
+        line: &str,
+        column: usize,
+        indent: Indent,
+    ) -> String {
+        let (before, after) = line.split_at(column);
+        let base = leading(before);
+
+        // An opening right before a closing, like `{}`, keeps the level
+        let opens = self
+            .indent_after
+            .iter()
+            .any(|token| ends_with_token(before.trim_end(), token));
+        let closes = self
+            .dedent_on
+            .iter()
+            .any(|token| starts_with_token(after.trim_start(), token));
+
+        if opens && !closes {
+            format!("{base}{}", indent.unit())
+        } else {
+            base.to_owned()
+        }
+    }
+
+    /// Returns the indentation of the inner and the closing line when the
+    /// byte column is between a bracket and the one closing it, like `{|}`,
+    /// if the language indents after the bracket and dedents on its match.
+    pub fn bracket_break(
+        &self,
+        line: &str,
+        column: usize,
+        indent: Indent,
+    ) -> Option<(String, String)> {
+        let (before, after) = line.split_at(column);
+        let open = before.trim_end().chars().next_back()?;
+        let close = match open {
+            '{' => '}',
+            '(' => ')',
+            '[' => ']',
+            _ => return None,
+        };
+
+        let listed = |tokens: &[String], bracket: char| {
+            tokens.iter().any(|token| token.chars().eq([bracket]))
+        };
+
+        if !listed(&self.indent_after, open)
+            || !listed(&self.dedent_on, close)
+            || !after.trim_start().starts_with(close)
+        {
+            return None;
+        }
+
+        let base = leading(before);
+
+        Some((format!("{base}{}", indent.unit()), base.to_owned()))
+    }
+
+    /// Returns the line a level shallower if it holds nothing but a closing
+    /// and is not already shallower than the line above, whose indentation
+    /// is `previous`.
+    pub fn dedent(
+        &self,
+        line: &str,
+        previous: &str,
+        indent: Indent,
+    ) -> Option<String> {
+        let indentation = leading(line);
+        let trimmed = &line[indentation.len()..];
+
+        if indentation.is_empty()
+            || columns(indentation, indent) < columns(previous, indent)
+            || !self.dedent_on.iter().any(|token| token == trimmed)
+        {
+            return None;
+        }
+
+        let unit = indent.unit();
+        let dedented =
+            indentation.strip_suffix(unit.as_str()).unwrap_or_else(|| {
+                indentation
+                    .char_indices()
+                    .last()
+                    .map_or("", |(offset, _)| &indentation[..offset])
+            });
+
+        Some(format!("{dedented}{trimmed}"))
+    }
+}
+
+/// Returns the leading whitespace of a line.
+pub fn leading(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// How many columns indentation spans, counting a tab as a level.
+fn columns(whitespace: &str, indent: Indent) -> usize {
+    whitespace
+        .chars()
+        .map(|c| if c == '\t' { indent.width } else { 1 })
+        .sum()
+}
+
+// Words only count on their own, so `undo` does not end with `do`
+fn ends_with_token(text: &str, token: &str) -> bool {
+    text.strip_suffix(token).is_some_and(|rest| {
+        !is_word(token.chars().next()) || !is_word(rest.chars().next_back())
+    })
+}
+
+fn starts_with_token(text: &str, token: &str) -> bool {
+    text.strip_prefix(token).is_some_and(|rest| {
+        !is_word(token.chars().next_back()) || !is_word(rest.chars().next())
+    })
+}
+
+fn is_word(c: Option<char>) -> bool {
+    c.is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
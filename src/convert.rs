@@ -0,0 +1,263 @@
+//! Conversions of configuration text between JSON, YAML and TOML.
+//!
+//! Every format is read into a JSON value, which is written out in the other
+//! format. Comments are lost on the way, and keys come out sorted.
+use std::fmt;
+
+use serde_json::{Map, Number, Value};
+use toml_edit::{Array, ArrayOfTables, Document, InlineTable, Item, Table};
+use yaml_rust::{Yaml, YamlEmitter, YamlLoader};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    /// The formats, in the order text of an unknown one is tried in. YAML
+    /// comes last, as it reads nearly anything.
+    pub const ALL: [Self; 3] = [Self::Json, Self::Toml, Self::Yaml];
+
+    /// The format of files of the language, if it is one.
+    pub fn of_language(name: &str) -> Option<Self> {
+        match name {
+            "JSON" => Some(Self::Json),
+            "YAML" => Some(Self::Yaml),
+            "TOML" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+
+    /// Reads the text as the format.
+    pub fn parse(self, text: &str) -> Result<Value, String> {
+        match self {
+            Self::Json => {
+                serde_json::from_str(text).map_err(|error| error.to_string())
+            }
+            Self::Yaml => {
+                let documents = YamlLoader::load_from_str(text)
+                    .map_err(|error| error.to_string())?;
+
+                documents.first().map_or(Ok(Value::Null), from_yaml)
+            }
+            Self::Toml => {
+                let document = text
+                    .parse::<Document>()
+                    .map_err(|error| error.to_string())?;
+
+                from_toml_table(document.as_table())
+            }
+        }
+    }
+
+    /// Writes the value out in the format.
+    pub fn write(self, value: &Value) -> Result<String, String> {
+        match self {
+            Self::Json => serde_json::to_string_pretty(value)
+                .map_err(|error| error.to_string()),
+            Self::Yaml => {
+                let mut text = String::new();
+
+                YamlEmitter::new(&mut text)
+                    .dump(&to_yaml(value))
+                    .map_err(|error| format!("{error:?}"))?;
+
+                // The emitter starts every document with a `---` line
+                Ok(text.strip_prefix("---\n").unwrap_or(&text).to_owned())
+            }
+            Self::Toml => {
+                let Value::Object(map) = value else {
+                    return Err(String::from(
+                        "TOML can only hold a table at the top",
+                    ));
+                };
+
+                let mut document = Document::new();
+                *document.as_table_mut() = to_toml_table(map)?;
+
+                Ok(document.to_string())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Json => "JSON",
+            Self::Yaml => "YAML",
+            Self::Toml => "TOML",
+        })
+    }
+}
+
+fn from_yaml(yaml: &Yaml) -> Result<Value, String> {
+    Ok(match yaml {
+        Yaml::Null => Value::Null,
+        Yaml::Boolean(boolean) => Value::Bool(*boolean),
+        Yaml::Integer(integer) => Value::from(*integer),
+        Yaml::Real(real) => number(
+            real.parse()
+                .map_err(|_| format!("{real} is not a number"))?,
+        )?,
+        Yaml::String(string) => Value::String(string.clone()),
+        Yaml::Array(items) => {
+            Value::Array(items.iter().map(from_yaml).collect::<Result<_, _>>()?)
+        }
+        Yaml::Hash(hash) => {
+            let mut map = Map::new();
+
+            for (key, value) in hash {
+                let key = match key {
+                    Yaml::String(key) | Yaml::Real(key) => key.clone(),
+                    Yaml::Integer(key) => key.to_string(),
+                    Yaml::Boolean(key) => key.to_string(),
+                    _ => return Err(String::from("A key is not a string")),
+                };
+
+                map.insert(key, from_yaml(value)?);
+            }
+
+            Value::Object(map)
+        }
+        Yaml::Alias(_) | Yaml::BadValue => {
+            return Err(String::from("The YAML has an invalid value"));
+        }
+    })
+}
+
+fn to_yaml(value: &Value) -> Yaml {
+    match value {
+        Value::Null => Yaml::Null,
+        Value::Bool(boolean) => Yaml::Boolean(*boolean),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => Yaml::Integer(integer),
+            None => Yaml::Real(number.to_string()),
+        },
+        Value::String(string) => Yaml::String(string.clone()),
+        Value::Array(items) => Yaml::Array(items.iter().map(to_yaml).collect()),
+        Value::Object(map) => Yaml::Hash(
+            map.iter()
+                .map(|(key, value)| (Yaml::String(key.clone()), to_yaml(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn from_toml_table<'a>(
+    entries: impl IntoIterator<Item = (&'a str, &'a Item)>,
+) -> Result<Value, String> {
+    let mut map = Map::new();
+
+    for (key, item) in entries {
+        let value = match item {
+            Item::None => continue,
+            Item::Value(value) => from_toml(value)?,
+            Item::Table(table) => from_toml_table(table)?,
+            Item::ArrayOfTables(tables) => Value::Array(
+                tables
+                    .iter()
+                    .map(from_toml_table)
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+
+        map.insert(key.to_owned(), value);
+    }
+
+    Ok(Value::Object(map))
+}
+
+fn from_toml(value: &toml_edit::Value) -> Result<Value, String> {
+    Ok(match value {
+        toml_edit::Value::String(string) => {
+            Value::String(string.value().clone())
+        }
+        toml_edit::Value::Integer(integer) => Value::from(*integer.value()),
+        toml_edit::Value::Float(float) => number(*float.value())?,
+        toml_edit::Value::Boolean(boolean) => Value::Bool(*boolean.value()),
+        toml_edit::Value::Datetime(datetime) => {
+            Value::String(datetime.value().to_string())
+        }
+        toml_edit::Value::Array(items) => {
+            Value::Array(items.iter().map(from_toml).collect::<Result<_, _>>()?)
+        }
+        toml_edit::Value::InlineTable(table) => {
+            let mut map = Map::new();
+
+            for (key, value) in table {
+                map.insert(key.to_owned(), from_toml(value)?);
+            }
+
+            Value::Object(map)
+        }
+    })
+}
+
+/// Turns an object into a table, writing nested objects as sections and
+/// arrays of objects as arrays of tables.
+fn to_toml_table(map: &Map<String, Value>) -> Result<Table, String> {
+    let mut table = Table::new();
+
+    for (key, value) in map {
+        let item = match value {
+            Value::Object(map) => Item::Table(to_toml_table(map)?),
+            Value::Array(items)
+                if !items.is_empty() && items.iter().all(Value::is_object) =>
+            {
+                let mut tables = ArrayOfTables::new();
+
+                for item in items {
+                    if let Value::Object(map) = item {
+                        tables.push(to_toml_table(map)?);
+                    }
+                }
+
+                Item::ArrayOfTables(tables)
+            }
+            value => Item::Value(to_toml(value)?),
+        };
+
+        table.insert(key, item);
+    }
+
+    Ok(table)
+}
+
+fn to_toml(value: &Value) -> Result<toml_edit::Value, String> {
+    Ok(match value {
+        Value::Null => return Err(String::from("TOML has no null values")),
+        Value::Bool(boolean) => toml_edit::Value::from(*boolean),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => toml_edit::Value::from(integer),
+            None => toml_edit::Value::from(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(string) => toml_edit::Value::from(string.as_str()),
+        Value::Array(items) => {
+            let mut array = Array::new();
+
+            for item in items {
+                array.push(to_toml(item)?);
+            }
+
+            toml_edit::Value::Array(array)
+        }
+        Value::Object(map) => {
+            let mut table = InlineTable::new();
+
+            for (key, value) in map {
+                table.insert(key, to_toml(value)?);
+            }
+
+            toml_edit::Value::InlineTable(table)
+        }
+    })
+}
+
+fn number(float: f64) -> Result<Value, String> {
+    Number::from_f64(float)
+        .map(Value::Number)
+        .ok_or_else(|| format!("{float} cannot be written as JSON"))
+}
@@ -0,0 +1,243 @@
+//! Line diffs between the buffer and the file as it was last loaded or saved.
+use std::ops::{Deref, Range};
+
+/// Above this many line pairs, the changed middle of two texts is reported as
+/// a single hunk instead of being diffed line by line.
+const MAX_CELLS: usize = 4_000_000;
+
+/// A run of lines that differ between the original and the current text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// The lines of the original text that were replaced.
+    pub original: Range<usize>,
+    /// The lines of the current text that replace them.
+    pub current: Range<usize>,
+}
+
+impl Hunk {
+    pub fn kind(&self) -> Kind {
+        if self.original.is_empty() {
+            Kind::Added
+        } else if self.current.is_empty() {
+            Kind::Removed
+        } else {
+            Kind::Modified
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Added,
+    Modified,
+    Removed,
+}
+
+/// Returns the hunks turning the `original` lines into the `current` ones.
+pub fn hunks<A, B>(original: &[A], current: &[B]) -> Vec<Hunk>
+where
+    A: Deref<Target = str>,
+    B: Deref<Target = str>,
+{
+    let same = |i: usize, j: usize| *original[i] == *current[j];
+
+    let prefix = (0..original.len().min(current.len()))
+        .take_while(|&i| same(i, i))
+        .count();
+    let suffix = (0..original.len().min(current.len()) - prefix)
+        .take_while(|&i| same(original.len() - 1 - i, current.len() - 1 - i))
+        .count();
+
+    let original_end = original.len() - suffix;
+    let current_end = current.len() - suffix;
+    let rows = original_end - prefix;
+    let columns = current_end - prefix;
+
+    if rows == 0 && columns == 0 {
+        return Vec::new();
+    }
+
+    if rows * columns > MAX_CELLS {
+        return vec![Hunk {
+            original: prefix..original_end,
+            current: prefix..current_end,
+        }];
+    }
+
+    // Longest common subsequence of the changed middle, from the end
+    let width = columns + 1;
+    let mut lengths = vec![0u32; (rows + 1) * width];
+
+    for i in (0..rows).rev() {
+        for j in (0..columns).rev() {
+            lengths[i * width + j] = if same(prefix + i, prefix + j) {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut hunks = Vec::new();
+    let mut pending: Option<Hunk> = None;
+    let (mut i, mut j) = (0, 0);
+
+    while i < rows || j < columns {
+        let (next_i, next_j) =
+            if i < rows && j < columns && same(prefix + i, prefix + j) {
+                hunks.extend(pending.take());
+
+                (i + 1, j + 1)
+            } else {
+                let hunk = pending.get_or_insert(Hunk {
+                    original: prefix + i..prefix + i,
+                    current: prefix + j..prefix + j,
+                });
+
+                if j == columns
+                    || (i < rows
+                        && lengths[(i + 1) * width + j]
+                            >= lengths[i * width + j + 1])
+                {
+                    hunk.original.end += 1;
+
+                    (i + 1, j)
+                } else {
+                    hunk.current.end += 1;
+
+                    (i, j + 1)
+                }
+            };
+
+        i = next_i;
+        j = next_j;
+    }
+
+    hunks.extend(pending);
+    hunks
+}
+
+/// Writes the hunks turning the `original` lines into the `current` ones as
+/// a unified diff, with up to `context` unchanged lines around each change.
+pub fn unified<A, B>(
+    original: &[A],
+    current: &[B],
+    hunks: &[Hunk],
+    context: usize,
+) -> String
+where
+    A: Deref<Target = str>,
+    B: Deref<Target = str>,
+{
+    let mut diff = String::new();
+    let mut rest = hunks;
+
+    while let Some(first) = rest.first() {
+        // Changes whose context would touch are shown together
+        let count = 1 + rest
+            .windows(2)
+            .take_while(|pair| {
+                pair[1].original.start - pair[0].original.end <= 2 * context
+            })
+            .count();
+        let (group, next) = rest.split_at(count);
+        let last = &group[count - 1];
+
+        let before = first.original.start.min(context);
+        let after = (original.len() - last.original.end).min(context);
+        let start = first.original.start - before;
+        let end = last.original.end + after;
+
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            header(start, end - start),
+            header(
+                first.current.start - before,
+                last.current.end + after - (first.current.start - before)
+            ),
+        ));
+
+        let mut unchanged = start;
+
+        for hunk in group {
+            for line in &original[unchanged..hunk.original.start] {
+                diff.push_str(&format!(" {}\n", &**line));
+            }
+            for line in &original[hunk.original.clone()] {
+                diff.push_str(&format!("-{}\n", &**line));
+            }
+            for line in &current[hunk.current.clone()] {
+                diff.push_str(&format!("+{}\n", &**line));
+            }
+
+            unchanged = hunk.original.end;
+        }
+
+        for line in &original[unchanged..end] {
+            diff.push_str(&format!(" {}\n", &**line));
+        }
+
+        rest = next;
+    }
+
+    diff
+}
+
+/// Makes the whitespace of the changed lines of a unified diff visible, with
+/// `·` for spaces and `→` for tabs, and marks the lines of changes that only
+/// touch whitespace with `!`, like context diffs mark changed lines.
+pub fn reveal_whitespace(diff: &str) -> String {
+    let lines: Vec<&str> = diff.lines().collect();
+    let mut revealed = String::with_capacity(diff.len());
+    let mut i = 0;
+
+    while i < lines.len() {
+        let removed = lines[i..]
+            .iter()
+            .take_while(|line| line.starts_with('-'))
+            .count();
+        let added = lines[i + removed..]
+            .iter()
+            .take_while(|line| line.starts_with('+'))
+            .count();
+
+        if removed + added == 0 {
+            revealed.push_str(lines[i]);
+            revealed.push('\n');
+            i += 1;
+            continue;
+        }
+
+        let (before, after) = lines[i..i + removed + added].split_at(removed);
+        let whitespace_only = removed == added
+            && before.iter().zip(after).all(|(a, b)| {
+                a[1..].split_whitespace().eq(b[1..].split_whitespace())
+            });
+
+        for line in before.iter().chain(after) {
+            let (marker, text) = line.split_at(1);
+
+            revealed.push_str(if whitespace_only { "!" } else { marker });
+            revealed.extend(text.chars().map(|c| match c {
+                ' ' => '·',
+                '\t' => '→',
+                c => c,
+            }));
+            revealed.push('\n');
+        }
+
+        i += removed + added;
+    }
+
+    revealed
+}
+
+/// Formats the line range of one side of a unified diff hunk header, which
+/// counts from 1 except for empty ranges.
+fn header(start: usize, length: usize) -> String {
+    match length {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{length}", start + 1),
+    }
+}
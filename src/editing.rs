@@ -0,0 +1,1126 @@
+//! Helpers for reading and positioning the cursor of a `text_editor::Content`.
+//!
+//! The editor only exposes the caret position and the selected text, and can
+//! only be driven through [`Action`]s, so everything here is built on top of
+//! those two primitives.
+//!
+//! Vertical motions need the lines they cross to be laid out, which edited
+//! lines are not until the next layout. Positioning the cursor right after an
+//! edit must therefore wait until the editor has been laid out again.
+use iced::widget::text_editor::{Action, Content, Edit, Motion};
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use unicode_normalization::UnicodeNormalization;
+
+/// A line and a byte column within that line.
+pub type Position = (usize, usize);
+
+/// Returns the anchor and the caret of the current selection, if any.
+///
+/// The anchor is not exposed by the editor, so it is inferred by looking for
+/// the selected text right before or right after the caret.
+pub fn selection(content: &Content) -> Option<(Position, Position)> {
+    let selected = content.selection()?;
+    let text = content.text();
+    let cursor = content.cursor_position();
+    let offset = to_offset(&text, cursor);
+
+    let anchor = if text[..offset].ends_with(selected.as_str()) {
+        offset - selected.len()
+    } else if text[offset..].starts_with(selected.as_str()) {
+        offset + selected.len()
+    } else {
+        return None;
+    };
+
+    Some((to_position(&text, anchor), cursor))
+}
+
+/// Whether the selection covers the whole text, which is not empty.
+pub fn selects_all(content: &Content) -> bool {
+    content.selection().is_some_and(|selected| {
+        !selected.is_empty() && selected == text(content)
+    })
+}
+
+/// Moves the caret to the given [`Position`], clearing any selection.
+pub fn move_to(content: &mut Content, position: Position) {
+    content.perform(Action::Move(Motion::DocumentStart));
+
+    step_to(content, position, Action::Move);
+}
+
+/// Selects the text between the `anchor` and the `cursor`.
+pub fn select(content: &mut Content, anchor: Position, cursor: Position) {
+    let current = if anchor == cursor {
+        content.cursor_position() == cursor && content.selection().is_none()
+    } else {
+        selection(content) == Some((anchor, cursor))
+    };
+
+    // Moving there would cross lines that may not be laid out for nothing
+    if current {
+        return;
+    }
+
+    move_to(content, anchor);
+
+    if anchor != cursor {
+        step_to(content, cursor, Action::Select);
+    }
+}
+
+/// Converts a [`Position`] into a byte offset of the given text.
+pub fn to_offset(text: &str, (line, column): Position) -> usize {
+    let start: usize =
+        text.split('\n').take(line).map(|line| line.len() + 1).sum();
+
+    (start + column).min(text.len())
+}
+
+/// Converts a byte offset of the given text into a [`Position`].
+pub fn to_position(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    let column = before.rfind('\n').map_or(offset, |i| offset - i - 1);
+
+    (line, column)
+}
+
+fn step_to(
+    content: &mut Content,
+    target: Position,
+    action: fn(Motion) -> Action,
+) {
+    // Vertical motions work on visual lines, so they are only used to get to
+    // the right line quickly; the column is reached one grapheme at a time.
+    loop {
+        let current = content.cursor_position();
+
+        let motion = match current.0.cmp(&target.0) {
+            Ordering::Less => Motion::Down,
+            Ordering::Greater => Motion::Up,
+            Ordering::Equal => break,
+        };
+
+        content.perform(action(motion));
+
+        if content.cursor_position() == current {
+            break;
+        }
+    }
+
+    loop {
+        let current = content.cursor_position();
+
+        let motion = match current.cmp(&target) {
+            Ordering::Less => Motion::Right,
+            Ordering::Greater => Motion::Left,
+            Ordering::Equal => break,
+        };
+
+        content.perform(action(motion));
+
+        let next = content.cursor_position();
+
+        if next == current || next.cmp(&target) != current.cmp(&target) {
+            break;
+        }
+    }
+}
+
+/// A vertical direction in the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+/// Copies the lines spanned by the cursor or selection in the given
+/// [`Direction`], leaving the original lines in place.
+///
+/// Returns the anchor and caret that put the selection onto the copy.
+pub fn copy_lines(
+    content: &mut Content,
+    direction: Direction,
+) -> (Position, Position) {
+    let cursor = content.cursor_position();
+    let (anchor, cursor) = selection(content).unwrap_or((cursor, cursor));
+    let (start, end) = (anchor.min(cursor), anchor.max(cursor));
+
+    // A selection ending at the very start of a line does not include it
+    let last = if end.0 > start.0 && end.1 == 0 {
+        end.0 - 1
+    } else {
+        end.0
+    };
+
+    let block = (start.0..=last)
+        .filter_map(|line| content.line(line).map(|line| line.to_string()))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let length = last - start.0 + 1;
+
+    match direction {
+        Direction::Down => {
+            let end_of_last = content.line(last).map_or(0, |line| line.len());
+
+            move_to(content, (last, end_of_last));
+            content.perform(Action::Edit(Edit::Paste(Arc::new(format!(
+                "\n{block}"
+            )))));
+
+            ((anchor.0 + length, anchor.1), (cursor.0 + length, cursor.1))
+        }
+        Direction::Up => {
+            move_to(content, (start.0, 0));
+            content.perform(Action::Edit(Edit::Paste(Arc::new(format!(
+                "{block}\n"
+            )))));
+
+            (anchor, cursor)
+        }
+    }
+}
+
+/// Returns the text of the [`Content`] exactly as it is displayed.
+///
+/// Unlike [`Content::text`], no newline is appended at the end.
+pub fn text(content: &Content) -> String {
+    content
+        .lines()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the path-like word around the given byte column of a line, with
+/// the line and column it points at, if any.
+///
+/// Locations follow the path as `path:line:column`, like rustc, gcc and clang
+/// print them, or as `path(line,column)`, like MSVC does. Both are counted
+/// from 1, and the column may be left out.
+pub fn location_at(
+    line: &str,
+    column: usize,
+) -> Option<(&str, Option<(usize, usize)>)> {
+    let is_path = |c: char| {
+        c.is_alphanumeric() || matches!(c, '/' | '\\' | '.' | '_' | '-' | '~')
+    };
+
+    let column = column.min(line.len());
+    let start = line[..column]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_path(*c))
+        .last()
+        .map_or(column, |(i, _)| i);
+    let end = line[column..]
+        .char_indices()
+        .find(|(_, c)| !is_path(*c))
+        .map_or(line.len(), |(i, _)| column + i);
+
+    let path = line[start..end].trim_end_matches('.');
+
+    if path.is_empty() {
+        return None;
+    }
+
+    Some((path, location_suffix(&line[start + path.len()..])))
+}
+
+fn location_suffix(rest: &str) -> Option<(usize, usize)> {
+    let (line, rest, separator) = if let Some(rest) = rest.strip_prefix(':') {
+        let (line, rest) = number(rest)?;
+
+        (line, rest, ':')
+    } else {
+        let (line, rest) = number(rest.strip_prefix('(')?)?;
+
+        (line, rest, ',')
+    };
+
+    let column = rest
+        .strip_prefix(separator)
+        .and_then(number)
+        .map_or(1, |(column, _)| column);
+
+    Some((line, column))
+}
+
+fn number(text: &str) -> Option<(usize, &str)> {
+    let digits = text.len()
+        - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+    Some((text[..digits].parse().ok()?, &text[digits..]))
+}
+
+/// Converts a line and a column counted from 1 into a [`Position`] within the
+/// content.
+pub fn clamp(content: &Content, (line, column): (usize, usize)) -> Position {
+    let line = line
+        .saturating_sub(1)
+        .min(content.line_count().saturating_sub(1));
+    let length = content.line(line).map_or(0, |line| line.len());
+
+    (line, column.saturating_sub(1).min(length))
+}
+
+/// Replaces the text between two positions, returning the range now covered
+/// by the replacement.
+pub fn replace(
+    content: &mut Content,
+    start: Position,
+    end: Position,
+    replacement: &str,
+) -> (Position, Position) {
+    select(content, start, end);
+
+    content.perform(Action::Edit(Edit::Paste(Arc::new(
+        replacement.to_owned(),
+    ))));
+
+    let end = match replacement.rfind('\n') {
+        Some(i) => (
+            start.0 + replacement.matches('\n').count(),
+            replacement.len() - i - 1,
+        ),
+        None => (start.0, start.1 + replacement.len()),
+    };
+
+    (start, end)
+}
+
+/// Reads the strings to surround a selection with from what the user typed:
+/// an opening and a closing string separated by a space, an HTML tag that
+/// gets its closing tag, or a single string used on both sides.
+pub fn surround_pair(input: &str) -> Option<(String, String)> {
+    let input = input.trim();
+
+    if let Some((open, close)) = input.split_once(' ') {
+        return Some((open.to_owned(), close.trim().to_owned()));
+    }
+
+    if input.is_empty() {
+        return None;
+    }
+
+    let tag = input
+        .strip_prefix('<')
+        .and_then(|tag| tag.strip_suffix('>'))
+        .filter(|tag| !tag.starts_with('/'))
+        .and_then(|tag| tag.split_whitespace().next());
+
+    Some(match tag {
+        Some(name) => (input.to_owned(), format!("</{name}>")),
+        None => (input.to_owned(), input.to_owned()),
+    })
+}
+
+/// Wraps the text in the given block comment delimiters, or unwraps it when
+/// it is already commented.
+pub fn toggle_block_comment(text: &str, open: &str, close: &str) -> String {
+    let trimmed = text.trim();
+
+    if let Some(inner) = trimmed
+        .strip_prefix(open)
+        .and_then(|rest| rest.strip_suffix(close))
+    {
+        let start = text.find(open).unwrap_or(0);
+        let end = text.rfind(close).map_or(text.len(), |i| i + close.len());
+
+        let inner = inner.strip_prefix(' ').unwrap_or(inner);
+        let inner = inner.strip_suffix(' ').unwrap_or(inner);
+
+        format!("{}{inner}{}", &text[..start], &text[end..])
+    } else {
+        let leading = text.len() - text.trim_start().len();
+        let trailing = leading + trimmed.len();
+
+        format!(
+            "{}{open} {trimmed} {close}{}",
+            &text[..leading],
+            &text[trailing..]
+        )
+    }
+}
+
+/// Returns the current selection shrunk so that it neither starts nor ends
+/// with whitespace, keeping its direction.
+pub fn trimmed_selection(content: &Content) -> Option<(Position, Position)> {
+    let (anchor, cursor) = selection(content)?;
+    let (start, end) = (anchor.min(cursor), anchor.max(cursor));
+
+    let text = content.text();
+    let start = to_offset(&text, start);
+    let end = to_offset(&text, end);
+    let selected = &text[start..end];
+
+    let trimmed_start = start + (selected.len() - selected.trim_start().len());
+    let trimmed_end = (start + selected.trim_end().len()).max(trimmed_start);
+
+    let start = to_position(&text, trimmed_start);
+    let end = to_position(&text, trimmed_end);
+
+    Some(if anchor <= cursor {
+        (start, end)
+    } else {
+        (end, start)
+    })
+}
+
+/// Returns the current selection, or the caret, grown to span whole lines,
+/// keeping its direction.
+pub fn line_selection(content: &Content) -> (Position, Position) {
+    let cursor = content.cursor_position();
+    let (anchor, cursor) = selection(content).unwrap_or((cursor, cursor));
+    let (start, end) = (anchor.min(cursor), anchor.max(cursor));
+
+    let start = (start.0, 0);
+    let end = (end.0, content.line(end.0).map_or(0, |line| line.len()));
+
+    if anchor <= cursor {
+        (start, end)
+    } else {
+        (end, start)
+    }
+}
+
+/// Returns the selection of the whole lines from the anchor line to the
+/// given one, which the cursor ends up on.
+pub fn lines_selection(
+    content: &Content,
+    anchor: usize,
+    line: usize,
+) -> (Position, Position) {
+    let last = content.line_count().saturating_sub(1);
+    let (anchor, line) = (anchor.min(last), line.min(last));
+    let end = |line: usize| (line, content.line(line).map_or(0, |l| l.len()));
+
+    if anchor <= line {
+        ((anchor, 0), end(line))
+    } else {
+        (end(anchor), (line, 0))
+    }
+}
+
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Returns the byte offsets of the bracket right after or right before the
+/// given offset and of its match, if it is balanced.
+pub fn matching_bracket(text: &str, offset: usize) -> Option<(usize, usize)> {
+    let after = text[offset..].chars().next().map(|c| (offset, c));
+    let before = text[..offset].char_indices().next_back();
+
+    [after, before]
+        .into_iter()
+        .flatten()
+        .find_map(|(index, c)| Some((index, match_bracket(text, index, c)?)))
+}
+
+fn match_bracket(text: &str, index: usize, bracket: char) -> Option<usize> {
+    let (counterpart, forward) =
+        BRACKETS.iter().find_map(|&(open, close)| {
+            if bracket == open {
+                Some((close, true))
+            } else if bracket == close {
+                Some((open, false))
+            } else {
+                None
+            }
+        })?;
+
+    let chars: Box<dyn Iterator<Item = (usize, char)>> = if forward {
+        Box::new(
+            text[index..]
+                .char_indices()
+                .map(move |(i, c)| (index + i, c)),
+        )
+    } else {
+        Box::new(text[..=index].char_indices().rev())
+    };
+
+    let mut depth = 0;
+
+    for (i, c) in chars {
+        if c == bracket {
+            depth += 1;
+        } else if c == counterpart {
+            depth -= 1;
+
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the selection spanning the bracket next to the caret, its match
+/// and everything in between.
+pub fn bracket_selection(content: &Content) -> Option<(Position, Position)> {
+    let text = content.text();
+    let offset = to_offset(&text, content.cursor_position());
+    let (bracket, matching) = matching_bracket(&text, offset)?;
+
+    // Brackets are a single byte long
+    let (anchor, cursor) = if bracket < matching {
+        (bracket, matching + 1)
+    } else {
+        (bracket + 1, matching)
+    };
+
+    Some((to_position(&text, anchor), to_position(&text, cursor)))
+}
+
+/// Returns the position right before the match of the bracket next to the
+/// caret, so jumping again from there comes back.
+pub fn matching_bracket_position(content: &Content) -> Option<Position> {
+    let text = content.text();
+    let offset = to_offset(&text, content.cursor_position());
+    let (_, matching) = matching_bracket(&text, offset)?;
+
+    Some(to_position(&text, matching))
+}
+
+/// Reindents a pasted block so that it follows the brace depth of the text
+/// it is pasted into.
+///
+/// The first line keeps the indentation already in front of the caret,
+/// unless the caret is at the very start of a line.
+pub fn reindent_paste(content: &Content, pasted: &str, unit: &str) -> String {
+    let text = content.text();
+    let cursor = content.cursor_position();
+    let start = selection(content)
+        .map_or(cursor, |(anchor, cursor)| anchor.min(cursor));
+    let before = &text[..to_offset(&text, start)];
+
+    let at_line_start = before.is_empty() || before.ends_with('\n');
+    let mut depth = brace_depth(0, before);
+
+    pasted
+        .split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            let line = line.trim_start();
+            let closing = line.chars().take_while(|c| *c == '}').count();
+            let level = depth.saturating_sub(closing);
+
+            depth = brace_depth(depth, line);
+
+            if line.is_empty() || (i == 0 && !at_line_start) {
+                line.to_owned()
+            } else {
+                format!("{}{line}", unit.repeat(level))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn brace_depth(depth: usize, text: &str) -> usize {
+    text.chars().fold(depth, |depth, c| match c {
+        '{' => depth + 1,
+        '}' => depth.saturating_sub(1),
+        _ => depth,
+    })
+}
+
+/// Sorts the given lines.
+///
+/// When every line but the last ends with a comma, the lines are taken to be
+/// the items of a code list: they are sorted without their commas, which are
+/// then put back so that only the last item may lack one, as it did before.
+pub fn sort_lines(lines: &[String]) -> Vec<String> {
+    let is_list = lines.len() > 1
+        && lines[..lines.len() - 1]
+            .iter()
+            .all(|line| line.trim_end().ends_with(','));
+
+    if !is_list {
+        let mut sorted = lines.to_vec();
+        sorted.sort();
+
+        return sorted;
+    }
+
+    let trailing_comma = lines[lines.len() - 1].trim_end().ends_with(',');
+
+    let mut items: Vec<_> = lines
+        .iter()
+        .map(|line| {
+            let line = line.trim_end();
+
+            line.strip_suffix(',').unwrap_or(line).to_owned()
+        })
+        .collect();
+    items.sort();
+
+    let last = items.len() - 1;
+
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            if i < last || trailing_comma {
+                format!("{item},")
+            } else {
+                item
+            }
+        })
+        .collect()
+}
+
+/// Returns the lines spanned by the selection, or every line without one.
+///
+/// A selection of whole lines usually ends at the start of the next line,
+/// which is then left out.
+pub fn selected_lines(content: &Content) -> Range<usize> {
+    let Some((anchor, cursor)) = selection(content) else {
+        return 0..content.line_count();
+    };
+    let (start, end) = (anchor.min(cursor), anchor.max(cursor));
+
+    if end.1 == 0 && end.0 > start.0 {
+        start.0..end.0
+    } else {
+        start.0..end.0 + 1
+    }
+}
+
+/// Breaks the lines longer than `column` characters at word boundaries,
+/// keeping their indentation on the lines they are broken into.
+///
+/// Shorter lines are left alone, so blank lines still separate paragraphs.
+pub fn hard_wrap(lines: &[String], column: usize) -> Vec<String> {
+    lines
+        .iter()
+        .flat_map(|line| {
+            if line.chars().count() <= column {
+                return vec![line.clone()];
+            }
+
+            let indentation = &line[..line.len() - line.trim_start().len()];
+
+            fill(indentation, indentation, line.split_whitespace(), column)
+        })
+        .collect()
+}
+
+/// Returns the lines of the paragraph around the given line, which blank
+/// lines delimit, or nothing on a blank line.
+pub fn paragraph(content: &Content, line: usize) -> Range<usize> {
+    let is_blank = |line: usize| {
+        content.line(line).is_none_or(|text| text.trim().is_empty())
+    };
+
+    if is_blank(line) {
+        return line..line;
+    }
+
+    let start = (0..line)
+        .rev()
+        .find(|&line| is_blank(line))
+        .map_or(0, |blank| blank + 1);
+    let end = (line + 1..content.line_count())
+        .find(|&line| is_blank(line))
+        .unwrap_or(content.line_count());
+
+    start..end
+}
+
+/// Joins the lines of each paragraph and wraps them again at `column`.
+///
+/// List items start lines of their own and are wrapped under their text,
+/// and the first line of a paragraph keeps its indentation.
+pub fn reflow(lines: &[String], column: usize) -> Vec<String> {
+    // The prefix of the first line, the indentation of the others and the
+    // words of each item, where blank lines are items without words
+    let mut items: Vec<(String, String, Vec<&str>)> = Vec::new();
+    let mut in_paragraph = false;
+
+    for line in lines {
+        if line.trim().is_empty() {
+            items.push((line.clone(), String::new(), Vec::new()));
+            in_paragraph = false;
+
+            continue;
+        }
+
+        let indentation = &line[..line.len() - line.trim_start().len()];
+        let marker = list_marker(line.trim_start());
+
+        if let (true, 0, Some((_, _, words))) =
+            (in_paragraph, marker, items.last_mut())
+        {
+            words.extend(line.split_whitespace());
+
+            continue;
+        }
+
+        let text = line[indentation.len() + marker..].trim_start();
+        let first = &line[..line.len() - text.len()];
+        let indentation = if marker == 0 {
+            indentation.to_owned()
+        } else {
+            format!(
+                "{indentation}{}",
+                " ".repeat(first.len() - indentation.len())
+            )
+        };
+
+        items.push((
+            first.to_owned(),
+            indentation,
+            text.split_whitespace().collect(),
+        ));
+        in_paragraph = true;
+    }
+
+    items
+        .iter()
+        .flat_map(|(first, indentation, words)| {
+            fill(first, indentation, words.iter().copied(), column)
+        })
+        .collect()
+}
+
+/// Returns the length of the bullet or number starting a list item, like
+/// `- ` or `1. `, or 0 if the line does not start one.
+fn list_marker(line: &str) -> usize {
+    let digits = line.len()
+        - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let marker = if digits > 0 {
+        line[digits..]
+            .starts_with(['.', ')'])
+            .then_some(digits + 1)
+    } else {
+        line.starts_with(['-', '*', '+']).then_some(1)
+    };
+
+    match marker {
+        Some(marker) if line[marker..].starts_with(' ') => marker + 1,
+        _ => 0,
+    }
+}
+
+/// Lays the words out after the prefix of the first line and the
+/// indentation of the others, starting a new line whenever the next word
+/// would go past `column` characters.
+///
+/// A word longer than the column gets a line of its own.
+fn fill<'a>(
+    first: &str,
+    indentation: &str,
+    words: impl Iterator<Item = &'a str>,
+    column: usize,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = first.to_owned();
+    let mut width = first.chars().count();
+    let mut is_empty = true;
+
+    for word in words {
+        let length = word.chars().count();
+
+        if !is_empty && width + 1 + length > column {
+            lines.push(line);
+            line = indentation.to_owned();
+            width = indentation.chars().count();
+            is_empty = true;
+        }
+
+        if !is_empty {
+            line.push(' ');
+            width += 1;
+        }
+
+        line.push_str(word);
+        width += length;
+        is_empty = false;
+    }
+
+    lines.push(line);
+    lines
+}
+
+/// Replaces whole lines with the given ones, returning the range now covered
+/// by the replacement.
+pub fn replace_lines(
+    content: &mut Content,
+    lines: Range<usize>,
+    replacement: &[String],
+) -> (Position, Position) {
+    let end_of = |content: &Content, line: usize| {
+        (line, content.line(line).map_or(0, |line| line.len()))
+    };
+
+    if lines.end < content.line_count() {
+        let replacement: String =
+            replacement.iter().map(|line| format!("{line}\n")).collect();
+
+        replace(content, (lines.start, 0), (lines.end, 0), &replacement)
+    } else if lines.start > 0 {
+        // There is no line after the range, so the newline before it goes
+        let start = end_of(content, lines.start - 1);
+        let end = if lines.is_empty() {
+            start
+        } else {
+            end_of(content, lines.end - 1)
+        };
+        let replacement: String =
+            replacement.iter().map(|line| format!("\n{line}")).collect();
+
+        replace(content, start, end, &replacement)
+    } else {
+        let end = end_of(content, content.line_count().saturating_sub(1));
+
+        replace(content, (0, 0), end, &replacement.join("\n"))
+    }
+}
+
+/// Returns the range of the first occurrence of the given text, if any.
+pub fn find(content: &Content, search: &str) -> Option<(Position, Position)> {
+    if search.is_empty() {
+        return None;
+    }
+
+    let text = content.text();
+    let start = text.find(search)?;
+
+    Some((
+        to_position(&text, start),
+        to_position(&text, start + search.len()),
+    ))
+}
+
+/// Parses `line` or `line:column`, both counted from 1, into a [`Position`]
+/// clamped to the content.
+pub fn parse_position(content: &Content, input: &str) -> Option<Position> {
+    let (line, column) = match input.trim().split_once(':') {
+        Some((line, column)) => (line, Some(column)),
+        None => (input.trim(), None),
+    };
+
+    let line = line.trim().parse().ok()?;
+    let column = match column {
+        Some(column) => column.trim().parse().ok()?,
+        None => 1,
+    };
+
+    Some(clamp(content, (line, column)))
+}
+
+/// Returns the byte range of a line that swapping the characters around the
+/// given column covers, and its replacement.
+///
+/// At the end of a line, the two characters before the column are swapped
+/// instead, so that repeating the command keeps dragging a character along.
+pub fn transpose_chars(
+    line: &str,
+    column: usize,
+) -> Option<(Range<usize>, String)> {
+    let column = column.min(line.len());
+    let (second, after) = if column == line.len() {
+        let (i, c) = line[..column].char_indices().next_back()?;
+
+        (c, i)
+    } else {
+        (line[column..].chars().next()?, column)
+    };
+
+    let (start, first) = line[..after].char_indices().next_back()?;
+    let end = after + second.len_utf8();
+
+    Some((start..end, format!("{second}{first}")))
+}
+
+/// Returns the byte range of a line that swapping the word before the given
+/// column with the word after it covers, and its replacement.
+pub fn transpose_words(
+    line: &str,
+    column: usize,
+) -> Option<(Range<usize>, String)> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+    let mut words = Vec::new();
+    let mut start = None;
+
+    for (i, c) in line.char_indices().chain([(line.len(), ' ')]) {
+        match (start, is_word(c)) {
+            (None, true) => start = Some(i),
+            (Some(word), false) => {
+                words.push(word..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    // The word the column is in or after, unless it is the last one
+    let second = words
+        .iter()
+        .position(|word| word.start >= column)
+        .unwrap_or(words.len())
+        .max(1);
+
+    let (first, second) = (words.get(second - 1)?, words.get(second)?);
+
+    Some((
+        first.start..second.end,
+        format!(
+            "{}{}{}",
+            &line[second.clone()],
+            &line[first.end..second.start],
+            &line[first.clone()]
+        ),
+    ))
+}
+
+/// Returns the line that opens the innermost scope around the cursor, going
+/// by indentation, and its index.
+pub fn scope_header(content: &Content) -> Option<(usize, String)> {
+    let indentation = |line: &str| {
+        let trimmed = line.trim_start();
+
+        (!trimmed.is_empty()).then(|| line.len() - trimmed.len())
+    };
+
+    // A blank line belongs to the scope of the code after it
+    let (line, _) = content.cursor_position();
+    let depth = (line..content.line_count()).find_map(|line| {
+        content.line(line).and_then(|line| indentation(&line))
+    })?;
+
+    (0..line).rev().find_map(|line| {
+        let text = content.line(line)?;
+        let trimmed = text.trim();
+
+        // Skip the ends of nested scopes, but not `} else {`
+        if trimmed.starts_with(['}', ')', ']']) && !trimmed.ends_with('{') {
+            return None;
+        }
+
+        (indentation(&text)? < depth).then(|| (line, trimmed.to_owned()))
+    })
+}
+
+/// Removes the ANSI escape sequences terminals use for colors and cursor
+/// movement, returning the clean text and how many sequences were removed.
+pub fn strip_ansi(text: &str) -> (String, usize) {
+    let mut clean = String::with_capacity(text.len());
+    let mut removed = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            clean.push(c);
+            continue;
+        }
+
+        removed += 1;
+
+        match chars.next() {
+            // Control sequences end with a byte in `@..=~`
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // Operating system commands end with BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\u{7}' {
+                        break;
+                    }
+
+                    if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // Character set selections like ESC ( B take one more byte
+            Some(' '..='/') => {
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+
+    (clean, removed)
+}
+
+/// Removes the spaces and tabs at the end of every line, keeping the line
+/// breaks, final ones included, and the indentation as they are.
+pub fn trim_trailing_whitespace(text: &str) -> String {
+    text.split('\n')
+        .map(|line| line.trim_end_matches([' ', '\t']))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Shortens the runs of blank lines to at most `max` lines, returning the
+/// lines left and how many were removed. With `trim_edges`, the runs at the
+/// start and end go altogether.
+pub fn collapse_blank_lines(
+    lines: &[String],
+    max: usize,
+    trim_edges: bool,
+) -> (Vec<String>, usize) {
+    let is_blank = |line: &String| line.trim().is_empty();
+    let first = lines.iter().position(|line| !is_blank(line));
+    let last = lines.iter().rposition(|line| !is_blank(line));
+
+    let mut collapsed = Vec::with_capacity(lines.len());
+    let mut run = 0;
+
+    for (index, line) in lines.iter().enumerate() {
+        if !is_blank(line) {
+            run = 0;
+            collapsed.push(line.clone());
+            continue;
+        }
+
+        let is_edge = first.is_none_or(|first| index < first)
+            || last.is_none_or(|last| index > last);
+        run += 1;
+
+        if run <= max && !(trim_edges && is_edge) {
+            collapsed.push(line.clone());
+        }
+    }
+
+    let removed = lines.len() - collapsed.len();
+
+    (collapsed, removed)
+}
+
+/// Characters that cannot be seen, or pass for a plain space, and the names
+/// they are reported under.
+pub const INVISIBLES: &[(char, &str)] = &[
+    ('\u{200b}', "zero-width space"),
+    ('\u{200c}', "zero-width non-joiner"),
+    ('\u{200d}', "zero-width joiner"),
+    ('\u{2060}', "word joiner"),
+    ('\u{feff}', "byte order mark"),
+    ('\u{ad}', "soft hyphen"),
+    ('\u{a0}', "non-breaking space"),
+    ('\u{202f}', "narrow non-breaking space"),
+];
+
+/// Returns the byte offsets and names of the invisible characters of the
+/// text, leaving out a byte order mark at its very start.
+pub fn invisibles(text: &str) -> Vec<(usize, &'static str)> {
+    text.char_indices()
+        .filter(|(offset, c)| *offset > 0 || *c != '\u{feff}')
+        .filter_map(|(offset, c)| {
+            INVISIBLES
+                .iter()
+                .find(|(invisible, _)| *invisible == c)
+                .map(|(_, name)| (offset, *name))
+        })
+        .collect()
+}
+
+/// Removes the invisible characters of the text, turning the ones that
+/// pass for a space into plain spaces, and returns how many there were.
+pub fn strip_invisibles(text: &str) -> (String, usize) {
+    let mut clean = String::with_capacity(text.len());
+    let mut removed = 0;
+
+    for (offset, c) in text.char_indices() {
+        let is_invisible = (offset > 0 || c != '\u{feff}')
+            && INVISIBLES.iter().any(|(invisible, _)| *invisible == c);
+
+        match c {
+            _ if !is_invisible => {
+                clean.push(c);
+                continue;
+            }
+            '\u{a0}' | '\u{202f}' => clean.push(' '),
+            _ => {}
+        }
+
+        removed += 1;
+    }
+
+    (clean, removed)
+}
+
+/// The Unicode normalization forms the text can be converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Normalization {
+    /// Composed characters, like `é` as one code point.
+    Nfc,
+    /// Decomposed characters, like `é` as `e` and a combining accent.
+    Nfd,
+}
+
+impl fmt::Display for Normalization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Nfc => "NFC",
+            Self::Nfd => "NFD",
+        })
+    }
+}
+
+/// Converts the text to the normalization form.
+pub fn normalize(text: &str, form: Normalization) -> String {
+    match form {
+        Normalization::Nfc => text.nfc().collect(),
+        Normalization::Nfd => text.nfd().collect(),
+    }
+}
+
+/// The cases the text can be changed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    Upper,
+    Lower,
+    /// Every word starting with an upper case letter, the rest lower case.
+    Title,
+}
+
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Upper => "upper case",
+            Self::Lower => "lower case",
+            Self::Title => "title case",
+        })
+    }
+}
+
+/// Changes the letters of the text to the case.
+pub fn change_case(text: &str, case: Case) -> String {
+    match case {
+        Case::Upper => text.to_uppercase(),
+        Case::Lower => text.to_lowercase(),
+        Case::Title => {
+            let mut title = String::with_capacity(text.len());
+            let mut starts_word = true;
+
+            for c in text.chars() {
+                if starts_word {
+                    title.extend(c.to_uppercase());
+                } else {
+                    title.extend(c.to_lowercase());
+                }
+
+                // Apostrophes stay within words, like in "don't"
+                starts_word = !c.is_alphanumeric() && c != '\'';
+            }
+
+            title
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! The character encodings files are read and written with.
+//!
+//! Only the encodings the standard library can decode are supported: UTF-8,
+//! UTF-16 and Windows-1252, the usual encoding of older Western files.
+use std::fmt;
+
+/// The characters Windows-1252 puts at bytes `0x80..=0x9f`, where Latin-1
+/// has control characters. The five undefined bytes keep those.
+#[rustfmt::skip]
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡',
+    'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—',
+    '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows1252,
+}
+
+impl Encoding {
+    pub const ALL: [Self; 4] =
+        [Self::Utf8, Self::Utf16Le, Self::Utf16Be, Self::Windows1252];
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "utf-8" => Some(Self::Utf8),
+            "utf-16le" => Some(Self::Utf16Le),
+            "utf-16be" => Some(Self::Utf16Be),
+            "windows-1252" => Some(Self::Windows1252),
+            _ => None,
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Utf16Le => "utf-16le",
+            Self::Utf16Be => "utf-16be",
+            Self::Windows1252 => "windows-1252",
+        }
+    }
+
+    /// Returns the encoding a byte order mark at the start of the bytes
+    /// stands for, if there is one.
+    pub fn from_bom(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0xff, 0xfe, ..] => Some(Self::Utf16Le),
+            [0xfe, 0xff, ..] => Some(Self::Utf16Be),
+            _ => None,
+        }
+    }
+
+    /// Guesses the encoding of the bytes: UTF-16 if every other byte is
+    /// mostly zero, UTF-8 if they are valid as such, or Windows-1252.
+    pub fn guess(bytes: &[u8]) -> Self {
+        if let Some(encoding) = Self::from_bom(bytes) {
+            return encoding;
+        }
+
+        let zeros = |parity: usize| {
+            bytes
+                .iter()
+                .skip(parity)
+                .step_by(2)
+                .filter(|b| **b == 0)
+                .count()
+        };
+        let half = bytes.len() / 2;
+
+        if half > 0 && zeros(1) > half / 2 {
+            Self::Utf16Le
+        } else if half > 0 && zeros(0) > half / 2 {
+            Self::Utf16Be
+        } else if std::str::from_utf8(bytes).is_ok() {
+            Self::Utf8
+        } else {
+            Self::Windows1252
+        }
+    }
+
+    /// Decodes the bytes, returning the text and how many invalid sequences
+    /// were replaced with `U+FFFD`.
+    pub fn decode(self, bytes: &[u8]) -> (String, usize) {
+        match self {
+            Self::Utf8 => {
+                let mut text = String::with_capacity(bytes.len());
+                let mut errors = 0;
+
+                for chunk in bytes.utf8_chunks() {
+                    text.push_str(chunk.valid());
+
+                    if !chunk.invalid().is_empty() {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        errors += 1;
+                    }
+                }
+
+                (text, errors)
+            }
+            Self::Utf16Le | Self::Utf16Be => {
+                let bytes = match Self::from_bom(bytes) {
+                    Some(_) => &bytes[2..],
+                    None => bytes,
+                };
+
+                let units = bytes.chunks_exact(2).map(|pair| {
+                    let pair = [pair[0], pair[1]];
+
+                    if self == Self::Utf16Le {
+                        u16::from_le_bytes(pair)
+                    } else {
+                        u16::from_be_bytes(pair)
+                    }
+                });
+
+                let mut errors = bytes.len() % 2;
+                let mut text: String = char::decode_utf16(units)
+                    .map(|c| {
+                        c.unwrap_or_else(|_| {
+                            errors += 1;
+                            char::REPLACEMENT_CHARACTER
+                        })
+                    })
+                    .collect();
+
+                if bytes.len() % 2 == 1 {
+                    text.push(char::REPLACEMENT_CHARACTER);
+                }
+
+                (text, errors)
+            }
+            Self::Windows1252 => {
+                let text = bytes
+                    .iter()
+                    .map(|&byte| match byte {
+                        0x80..=0x9f => WINDOWS_1252[usize::from(byte - 0x80)],
+                        _ => char::from(byte),
+                    })
+                    .collect();
+
+                (text, 0)
+            }
+        }
+    }
+
+    /// Encodes the text, writing a byte order mark for UTF-16 and `?` for
+    /// the characters Windows-1252 cannot represent.
+    pub fn encode(self, text: &str) -> Vec<u8> {
+        match self {
+            Self::Utf8 => text.as_bytes().to_vec(),
+            Self::Utf16Le | Self::Utf16Be => {
+                let mut bytes = Vec::with_capacity(text.len() * 2 + 2);
+
+                for unit in std::iter::once(0xfeff).chain(text.encode_utf16()) {
+                    bytes.extend(if self == Self::Utf16Le {
+                        unit.to_le_bytes()
+                    } else {
+                        unit.to_be_bytes()
+                    });
+                }
+
+                bytes
+            }
+            Self::Windows1252 => text
+                .chars()
+                .map(|c| match u8::try_from(c) {
+                    Ok(byte) if !(0x80..=0x9f).contains(&byte) => byte,
+                    _ => WINDOWS_1252
+                        .iter()
+                        .position(|special| *special == c)
+                        .map_or(b'?', |i| 0x80 + i as u8),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf16Le => "UTF-16 LE",
+            Self::Utf16Be => "UTF-16 BE",
+            Self::Windows1252 => "Windows-1252",
+        })
+    }
+}
@@ -0,0 +1,144 @@
+//! Settings remembered for each file, restored when it is opened again.
+//!
+//! They live next to the preferences, in a file of `[path]` sections with
+//! `key = value` lines, and only hold what differs from what the editor
+//! would pick on its own.
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::encoding::Encoding;
+use crate::indent::Indent;
+use crate::language::Language;
+use crate::line_ending::LineEnding;
+use crate::preferences::Preferences;
+use crate::view_mode::ViewMode;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FileSettings {
+    pub view_mode: Option<ViewMode>,
+    pub line_ending: Option<LineEnding>,
+    /// The encoding picked over the one the bytes suggest.
+    pub encoding: Option<Encoding>,
+    pub indent: Option<Indent>,
+    /// The language picked over the one the extension implies.
+    pub language: Option<&'static Language>,
+    /// The name the tab of the file is shown with instead of its own.
+    pub title: Option<String>,
+    /// Whether the file is saved as it is, with its trailing whitespace and
+    /// final newlines left alone.
+    pub save_as_is: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Store {
+    files: BTreeMap<PathBuf, FileSettings>,
+}
+
+impl Store {
+    /// Returns the path of the file the settings are kept in.
+    pub fn path() -> Option<PathBuf> {
+        Some(Preferences::path()?.with_file_name("files"))
+    }
+
+    /// Reads the settings, forgetting the files that no longer exist.
+    pub fn load() -> Self {
+        let text = Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+
+        let mut store = Self::parse(&text);
+        store.files.retain(|path, _| path.exists());
+        store
+    }
+
+    /// Reads the encoding remembered for a single file, without checking
+    /// which of the other files still exist.
+    pub fn encoding_of(path: &Path) -> Option<Encoding> {
+        let text = fs::read_to_string(Self::path()?).ok()?;
+
+        Self::parse(&text).get(path).encoding
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut files = BTreeMap::new();
+        let mut current: Option<&mut FileSettings> = None;
+
+        for line in text.lines().map(str::trim) {
+            if let Some(path) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                current = Some(files.entry(PathBuf::from(path)).or_default());
+                continue;
+            }
+
+            let (Some(settings), Some((key, value))) =
+                (current.as_deref_mut(), line.split_once('='))
+            else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key.trim() {
+                "view_mode" => settings.view_mode = ViewMode::from_key(value),
+                "line_ending" => {
+                    settings.line_ending = LineEnding::from_key(value);
+                }
+                "encoding" => settings.encoding = Encoding::from_key(value),
+                "indent" => settings.indent = Indent::from_key(value),
+                "language" => settings.language = Language::from_name(value),
+                "title" if !value.is_empty() => {
+                    settings.title = Some(value.to_owned());
+                }
+                "save_as_is" => settings.save_as_is = value == "true",
+                _ => {}
+            }
+        }
+
+        Self { files }
+    }
+
+    /// Writes the settings out in the format [`Store::load`] reads.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for (path, settings) in &self.files {
+            let _ = writeln!(text, "[{}]", path.display());
+
+            if let Some(view_mode) = settings.view_mode {
+                let _ = writeln!(text, "view_mode = {}", view_mode.key());
+            }
+            if let Some(line_ending) = settings.line_ending {
+                let _ = writeln!(text, "line_ending = {}", line_ending.key());
+            }
+            if let Some(encoding) = settings.encoding {
+                let _ = writeln!(text, "encoding = {}", encoding.key());
+            }
+            if let Some(indent) = settings.indent {
+                let _ = writeln!(text, "indent = {}", indent.key());
+            }
+            if let Some(language) = settings.language {
+                let _ = writeln!(text, "language = {}", language.name);
+            }
+            if let Some(title) = &settings.title {
+                let _ = writeln!(text, "title = {title}");
+            }
+            if settings.save_as_is {
+                let _ = writeln!(text, "save_as_is = true");
+            }
+        }
+
+        text
+    }
+
+    pub fn get(&self, path: &Path) -> FileSettings {
+        self.files.get(path).cloned().unwrap_or_default()
+    }
+
+    /// Changes the settings remembered for a file.
+    pub fn update(&mut self, path: &Path, f: impl FnOnce(&mut FileSettings)) {
+        f(self.files.entry(path.to_owned()).or_default());
+    }
+}
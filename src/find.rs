@@ -0,0 +1,212 @@
+//! Searching and replacing text in the current fragment, and searching every
+//! open one.
+use std::ops::Range;
+
+/// How many lines are listed at most when searching every fragment, as each
+/// one is redrawn with the editor.
+pub const LIMIT: usize = 500;
+
+/// How many characters of a long line are shown before an occurrence.
+const CONTEXT: usize = 40;
+
+/// How many characters of a line are shown at most.
+const PREVIEW_LENGTH: usize = 120;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Search {
+    pub query: String,
+    pub replacement: String,
+    /// Whether letters only match in the same case. Otherwise, ASCII letters
+    /// match either case.
+    pub case_sensitive: bool,
+}
+
+impl Search {
+    /// Returns the byte ranges of the occurrences of the query, without
+    /// overlaps.
+    pub fn matches(&self, text: &str) -> Vec<Range<usize>> {
+        if self.query.is_empty() {
+            return Vec::new();
+        }
+
+        // Changing the case of ASCII letters keeps the byte offsets
+        let (text, query) = if self.case_sensitive {
+            (text.to_owned(), self.query.clone())
+        } else {
+            (text.to_ascii_lowercase(), self.query.to_ascii_lowercase())
+        };
+
+        text.match_indices(&query)
+            .map(|(start, found)| start..start + found.len())
+            .collect()
+    }
+
+    /// Returns the first occurrence starting at the given offset or after
+    /// it, wrapping around to the first one of the text.
+    pub fn next(&self, text: &str, offset: usize) -> Option<Range<usize>> {
+        let matches = self.matches(text);
+
+        matches
+            .iter()
+            .find(|found| found.start >= offset)
+            .or(matches.first())
+            .cloned()
+    }
+
+    /// Returns the text with the occurrences that lie within the byte range
+    /// replaced, and how many there were.
+    pub fn replace_within(
+        &self,
+        text: &str,
+        range: Range<usize>,
+    ) -> (String, usize) {
+        let matches: Vec<_> = self
+            .matches(text)
+            .into_iter()
+            .filter(|found| {
+                found.start >= range.start && found.end <= range.end
+            })
+            .collect();
+        let mut replaced = String::with_capacity(text.len());
+        let mut end = 0;
+
+        for found in &matches {
+            replaced.push_str(&text[end..found.start]);
+            replaced.push_str(&self.replacement);
+            end = found.end;
+        }
+
+        replaced.push_str(&text[end..]);
+
+        (replaced, matches.len())
+    }
+}
+
+/// A line found by searching every fragment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hit {
+    pub fragment: usize,
+    /// The line, counted from 0.
+    pub line: usize,
+    /// The part of the line around its first occurrence.
+    pub preview: String,
+}
+
+/// The lines of every fragment a search occurs in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Results {
+    pub search: Search,
+    /// The first lines found, up to the [`LIMIT`].
+    pub hits: Vec<Hit>,
+    /// How many lines were found in all.
+    pub total: usize,
+    /// How many fragments the lines are in.
+    pub fragments: usize,
+    /// How many lines were found in each fragment.
+    counts: Vec<usize>,
+}
+
+impl Results {
+    /// Searches the texts of the fragments, in order.
+    pub fn new<'a>(
+        search: Search,
+        texts: impl Iterator<Item = &'a str>,
+    ) -> Self {
+        let mut hits = Vec::new();
+        let mut total = 0;
+        let mut fragments = 0;
+        let mut counts = Vec::new();
+
+        for (fragment, text) in texts.enumerate() {
+            counts.push(0);
+
+            let mut line = 0;
+            let mut scanned = 0;
+            let mut last_line = None;
+
+            for found in search.matches(text) {
+                line += text[scanned..found.start].matches('\n').count();
+                scanned = found.start;
+
+                if last_line == Some(line) {
+                    continue;
+                }
+
+                if last_line.is_none() {
+                    fragments += 1;
+                }
+
+                last_line = Some(line);
+                total += 1;
+                counts[fragment] += 1;
+
+                // Past the limit, the lines are only counted
+                if hits.len() < LIMIT {
+                    hits.push(Hit {
+                        fragment,
+                        line,
+                        preview: preview(text, found.start),
+                    });
+                }
+            }
+        }
+
+        Self {
+            search,
+            hits,
+            total,
+            fragments,
+            counts,
+        }
+    }
+
+    /// Drops the lines of a fragment that is gone, shifting the ones after
+    /// it.
+    pub fn forget(&mut self, fragment: usize) {
+        self.hits.retain(|hit| hit.fragment != fragment);
+
+        for hit in &mut self.hits {
+            if hit.fragment > fragment {
+                hit.fragment -= 1;
+            }
+        }
+
+        if fragment < self.counts.len() {
+            let count = self.counts.remove(fragment);
+
+            self.total -= count;
+
+            if count > 0 {
+                self.fragments -= 1;
+            }
+        }
+    }
+}
+
+/// Returns the line around the byte offset, cut short when it is long.
+fn preview(text: &str, offset: usize) -> String {
+    let start = text[..offset].rfind('\n').map_or(0, |newline| newline + 1);
+    let end = text[offset..]
+        .find('\n')
+        .map_or(text.len(), |newline| offset + newline);
+
+    let before: Vec<char> = text[start..offset].chars().collect();
+    let skipped = before.len().saturating_sub(CONTEXT);
+    let before: String = before[skipped..].iter().collect();
+    let after: String = text[offset..end]
+        .chars()
+        .take(PREVIEW_LENGTH - before.chars().count())
+        .collect();
+
+    let mut preview = if skipped > 0 {
+        format!("…{before}{after}")
+    } else {
+        format!("{before}{after}").trim_start().to_owned()
+    };
+
+    if offset + after.len() < end {
+        preview.push('…');
+    }
+
+    preview
+}
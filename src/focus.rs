@@ -0,0 +1,47 @@
+//! Telling whether a text field has the focus.
+//!
+//! Text fields leave Tab to the rest of the app, so a key bound for the
+//! editor would otherwise act on the text behind the field being typed in.
+use iced::advanced::widget::operation::{Focusable, Outcome};
+use iced::advanced::widget::{self, Operation};
+use iced::Rectangle;
+
+/// Produces the message unless one of the text fields has the focus.
+pub fn unless_field_focused<T: Clone>(message: T) -> impl Operation<T> {
+    UnlessFocused {
+        message,
+        focused: false,
+    }
+}
+
+struct UnlessFocused<T> {
+    message: T,
+    focused: bool,
+}
+
+impl<T: Clone> Operation<T> for UnlessFocused<T> {
+    fn container(
+        &mut self,
+        _id: Option<&widget::Id>,
+        _bounds: Rectangle,
+        operate_on_children: &mut dyn FnMut(&mut dyn Operation<T>),
+    ) {
+        operate_on_children(self);
+    }
+
+    fn focusable(
+        &mut self,
+        state: &mut dyn Focusable,
+        _id: Option<&widget::Id>,
+    ) {
+        self.focused |= state.is_focused();
+    }
+
+    fn finish(&self) -> Outcome<T> {
+        if self.focused {
+            Outcome::None
+        } else {
+            Outcome::Some(self.message.clone())
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! The size and position of the window, restored on the next launch.
+//!
+//! They are kept next to the preferences as `width`, `height`, `x` and `y`
+//! lines. The position is left out where the window system does not tell
+//! it, like on Wayland.
+use std::fs;
+use std::path::PathBuf;
+
+use crate::preferences::Preferences;
+
+/// The smallest size restored, so a window once shrunk to almost nothing
+/// opens usable again.
+const MIN_SIZE: (f32, f32) = (400.0, 300.0);
+
+/// The largest size restored, past any display the window could have been
+/// on, as a garbled file could ask for anything.
+const MAX_SIZE: f32 = 16_384.0;
+
+/// How far from the origin the window may be placed, either way. A window
+/// further away is on no display, like the minimized windows that Windows
+/// moves to (-32000, -32000).
+const MAX_OFFSET: i32 = 16_384;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geometry {
+    pub width: f32,
+    pub height: f32,
+    /// Where the top left corner of the window is, if known.
+    pub position: Option<(i32, i32)>,
+}
+
+impl Geometry {
+    /// Returns the path of the file the geometry is kept in.
+    pub fn path() -> Option<PathBuf> {
+        Some(Preferences::path()?.with_file_name("window"))
+    }
+
+    /// Reads the last geometry, if one was kept and it makes sense.
+    pub fn load() -> Option<Self> {
+        let text = fs::read_to_string(Self::path()?).ok()?;
+
+        Self::parse(&text)
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let value = |key: &str| {
+            text.lines()
+                .filter_map(|line| line.split_once('='))
+                .find(|(name, _)| name.trim() == key)
+                .map(|(_, value)| value.trim())
+        };
+
+        let size = |key: &str, min: f32| {
+            value(key)?
+                .parse::<f32>()
+                .ok()
+                .filter(|size| size.is_finite() && *size <= MAX_SIZE)
+                .map(|size| size.max(min))
+        };
+        let position = value("x")
+            .and_then(|x| x.parse().ok())
+            .zip(value("y").and_then(|y| y.parse().ok()))
+            .filter(|(x, y)| is_on_screen(*x, *y));
+
+        Some(Self {
+            width: size("width", MIN_SIZE.0)?,
+            height: size("height", MIN_SIZE.1)?,
+            position,
+        })
+    }
+
+    /// Takes the size the window was resized to, unless it is minimized,
+    /// which leaves it without any.
+    pub fn resize(&mut self, width: f32, height: f32) {
+        if width > 0.0 && height > 0.0 {
+            self.width = width.clamp(MIN_SIZE.0, MAX_SIZE);
+            self.height = height.clamp(MIN_SIZE.1, MAX_SIZE);
+        }
+    }
+
+    /// Takes the position the window was moved to, unless it is off every
+    /// display.
+    pub fn move_to(&mut self, x: i32, y: i32) {
+        if is_on_screen(x, y) {
+            self.position = Some((x, y));
+        }
+    }
+
+    /// Returns the contents of the geometry file.
+    pub fn to_file(self) -> String {
+        let mut file =
+            format!("width = {}\nheight = {}\n", self.width, self.height);
+
+        if let Some((x, y)) = self.position {
+            file.push_str(&format!("x = {x}\ny = {y}\n"));
+        }
+
+        file
+    }
+}
+
+fn is_on_screen(x: i32, y: i32) -> bool {
+    (-MAX_OFFSET..=MAX_OFFSET).contains(&x)
+        && (-MAX_OFFSET..=MAX_OFFSET).contains(&y)
+}
@@ -0,0 +1,88 @@
+//! Read-only queries against the git repository a file lives in.
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// The commit that last touched a line.
+#[derive(Debug, Clone)]
+pub struct Blame {
+    pub author: String,
+    pub summary: String,
+    /// Whether the line has changes that are not committed yet.
+    pub is_uncommitted: bool,
+}
+
+impl fmt::Display for Blame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_uncommitted {
+            f.write_str("Not committed yet")
+        } else {
+            write!(f, "{}, {}", self.author, self.summary)
+        }
+    }
+}
+
+/// Blames the given line of a file as it currently reads in the editor.
+///
+/// Returns `None` when git is missing or the file is not tracked.
+pub async fn blame(
+    path: PathBuf,
+    line: usize,
+    contents: String,
+) -> Option<Blame> {
+    let directory = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let line = line + 1;
+
+    let mut child = Command::new("git")
+        .current_dir(directory)
+        .args(["blame", "--porcelain", "--contents", "-", "-L"])
+        .arg(format!("{line},{line}"))
+        .arg("--")
+        .arg(path.file_name()?)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    // git reads all of the contents before blaming anything
+    let mut stdin = child.stdin.take()?;
+    stdin.write_all(contents.as_bytes()).await.ok()?;
+    drop(stdin);
+
+    let output = child.wait_with_output().await.ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse(porcelain: &str) -> Option<Blame> {
+    let mut lines = porcelain.lines();
+    let hash = lines.next()?.split(' ').next()?;
+
+    let mut author = None;
+    let mut summary = None;
+
+    for line in lines {
+        if let Some(name) = line.strip_prefix("author ") {
+            author = Some(name);
+        } else if let Some(title) = line.strip_prefix("summary ") {
+            summary = Some(title);
+        }
+    }
+
+    Some(Blame {
+        author: author?.to_owned(),
+        summary: summary?.to_owned(),
+        is_uncommitted: hash.bytes().all(|byte| byte == b'0'),
+    })
+}
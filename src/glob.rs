@@ -0,0 +1,46 @@
+//! Matching paths against the patterns of the preferences, like
+//! `secrets/**` or `*.env`.
+//!
+//! `*` matches within a folder name and `**` across folders. A pattern that
+//! does not start with `/` may match at any depth, so `*.env` matches every
+//! file ending with `.env`.
+use std::path::Path;
+
+pub fn matches(pattern: &str, path: &Path) -> bool {
+    let pattern = if pattern.starts_with('/') {
+        pattern.to_owned()
+    } else {
+        format!("**/{pattern}")
+    };
+    let path = path.to_string_lossy().replace('\\', "/");
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+
+    matches_from(&pattern, &path)
+}
+
+fn matches_from(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            // Any number of folders, none included
+            matches_from(rest, path)
+                || (0..path.len())
+                    .filter(|&i| path[i] == '/')
+                    .any(|i| matches_from(rest, &path[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => {
+            (0..=path.len()).any(|i| matches_from(rest, &path[i..]))
+        }
+        ['*', rest @ ..] => (0..=path.len())
+            .take_while(|&i| i == 0 || path[i - 1] != '/')
+            .any(|i| matches_from(rest, &path[i..])),
+        ['?', rest @ ..] => path
+            .split_first()
+            .is_some_and(|(c, path)| *c != '/' && matches_from(rest, path)),
+        [c, rest @ ..] => path.split_first().is_some_and(|(first, path)| {
+            first == c && matches_from(rest, path)
+        }),
+    }
+}
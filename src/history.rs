@@ -0,0 +1,117 @@
+//! Undo and redo for a fragment, the text editor having neither.
+//!
+//! The text is remembered whole before each group of edits, since the
+//! editor reports actions rather than the changes they make.
+use std::time::{Duration, Instant};
+
+use iced::widget::text_editor::Edit;
+
+use crate::editing::Position;
+
+/// How many groups of edits can be undone.
+const LIMIT: usize = 100;
+
+/// How long a pause ends a run of typing or deleting.
+const PAUSE: Duration = Duration::from_secs(1);
+
+/// The text of a fragment and where its cursor was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub text: String,
+    pub cursor: Position,
+}
+
+/// What an edit does, which tells whether it continues a run of edits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Typing,
+    Deleting,
+    /// Pastes, line breaks and commands, each undone on their own.
+    Other,
+}
+
+impl Kind {
+    pub fn of(edit: &Edit) -> Self {
+        match edit {
+            Edit::Insert(_) => Self::Typing,
+            Edit::Backspace | Edit::Delete => Self::Deleting,
+            Edit::Enter | Edit::Paste(_) => Self::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    /// The kind of the last edit and when it was made.
+    last: Option<(Kind, Instant)>,
+}
+
+impl History {
+    /// Starts a history that can be undone to the given texts, oldest first.
+    pub fn with_undo(undo: Vec<Snapshot>) -> Self {
+        Self {
+            undo,
+            ..Self::default()
+        }
+    }
+
+    /// Remembers the text as it is before an edit of the given kind,
+    /// unless the edit continues the run of typing or deleting before it.
+    pub fn record(&mut self, kind: Kind, snapshot: impl FnOnce() -> Snapshot) {
+        let now = Instant::now();
+        let continues = kind != Kind::Other
+            && self.last.is_some_and(|(last, at)| {
+                last == kind && now.duration_since(at) < PAUSE
+            });
+
+        self.last = Some((kind, now));
+        self.redo.clear();
+
+        if continues {
+            return;
+        }
+
+        if self.undo.len() == LIMIT {
+            self.undo.remove(0);
+        }
+
+        self.undo.push(snapshot());
+    }
+
+    /// Returns the text before the last group of edits, remembering the
+    /// current one to redo.
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let previous = self.undo.pop()?;
+
+        self.redo.push(current);
+        self.last = None;
+
+        Some(previous)
+    }
+
+    /// Returns the text the last undo went back from, remembering the
+    /// current one to undo again.
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let next = self.redo.pop()?;
+
+        self.undo.push(current);
+        self.last = None;
+
+        Some(next)
+    }
+
+    /// The texts that can be undone to, oldest first.
+    pub fn undo_snapshots(&self) -> &[Snapshot] {
+        &self.undo
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
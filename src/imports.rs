@@ -0,0 +1,141 @@
+//! Sorting the block of imports of a file and dropping the repeated ones.
+//!
+//! An import is a line starting with one of the words of its language, like
+//! `use` or `import`, along with the lines it goes on to while its brackets
+//! are open. Blank lines split the block into groups, which are sorted on
+//! their own and keep their order.
+use std::ops::Range;
+
+/// The imports of a block, sorted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sorted {
+    /// The lines the block spans, replaced by [`Sorted::lines`].
+    pub range: Range<usize>,
+    pub lines: Vec<String>,
+    /// How many imports are left.
+    pub count: usize,
+    /// How many repeated imports were dropped.
+    pub removed: usize,
+}
+
+/// Sorts the block of imports the given line is in, or else the first one of
+/// the file, if there is any.
+pub fn sort(
+    lines: &[String],
+    prefixes: &[&str],
+    line: usize,
+) -> Option<Sorted> {
+    let blocks = blocks(lines, prefixes);
+    let (range, groups) = blocks
+        .iter()
+        .find(|(range, _)| range.contains(&line))
+        .or(blocks.first())?;
+
+    let mut seen = Vec::new();
+    let mut sorted = Vec::new();
+    let mut removed = 0;
+
+    for group in groups {
+        let mut imports: Vec<&[String]> = Vec::new();
+
+        for import in group {
+            if seen.contains(import) {
+                removed += 1;
+            } else {
+                seen.push(*import);
+                imports.push(*import);
+            }
+        }
+
+        if imports.is_empty() {
+            continue;
+        }
+
+        imports.sort();
+
+        if !sorted.is_empty() {
+            sorted.push(String::new());
+        }
+
+        sorted.extend(imports.into_iter().flatten().cloned());
+    }
+
+    Some(Sorted {
+        range: range.clone(),
+        lines: sorted,
+        count: seen.len(),
+        removed,
+    })
+}
+
+type Block<'a> = (Range<usize>, Vec<Vec<&'a [String]>>);
+
+/// Finds the runs of imports, each split into the groups between blank
+/// lines.
+fn blocks<'a>(lines: &'a [String], prefixes: &[&str]) -> Vec<Block<'a>> {
+    let is_import = |line: &str| {
+        let line = line.trim_start();
+
+        prefixes.iter().any(|prefix| line.starts_with(prefix))
+    };
+
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        if !is_import(&lines[index]) {
+            index += 1;
+            continue;
+        }
+
+        let start = index;
+        let mut groups = vec![Vec::new()];
+
+        loop {
+            let end = statement_end(lines, index);
+            groups.last_mut().expect("a group").push(&lines[index..end]);
+            index = end;
+
+            // Blank lines only belong to the block if more imports follow
+            let next = (index..lines.len())
+                .find(|next| !lines[*next].trim().is_empty())
+                .unwrap_or(lines.len());
+
+            if next == lines.len() || !is_import(&lines[next]) {
+                break;
+            }
+
+            if next > index {
+                groups.push(Vec::new());
+            }
+
+            index = next;
+        }
+
+        blocks.push((start..index, groups));
+    }
+
+    blocks
+}
+
+/// Returns the line after the import starting at the given one, which goes
+/// on while its brackets are open.
+fn statement_end(lines: &[String], start: usize) -> usize {
+    let mut depth = 0;
+
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        for c in line.chars() {
+            match c {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {}
+            }
+        }
+
+        if depth <= 0 {
+            return index + 1;
+        }
+    }
+
+    lines.len()
+}
@@ -0,0 +1,226 @@
+//! How a fragment is indented: what the Tab key and re-indenting insert.
+//!
+//! The policy of a file comes from its `.editorconfig`, then from the
+//! indentation already in it, then from the preferences or the defaults of
+//! its language.
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Indent {
+    /// Whether a level is made of spaces rather than a tab.
+    pub insert_spaces: bool,
+    /// How many columns a level spans.
+    pub width: usize,
+}
+
+impl Default for Indent {
+    fn default() -> Self {
+        Self::spaces(4)
+    }
+}
+
+impl Indent {
+    /// The policies offered in the status bar.
+    pub const CHOICES: [Self; 5] = [
+        Self::spaces(2),
+        Self::spaces(4),
+        Self::spaces(8),
+        Self::tabs(4),
+        Self::tabs(8),
+    ];
+
+    pub const fn spaces(width: usize) -> Self {
+        Self {
+            insert_spaces: true,
+            width,
+        }
+    }
+
+    pub const fn tabs(width: usize) -> Self {
+        Self {
+            insert_spaces: false,
+            width,
+        }
+    }
+
+    /// The text of one indentation level.
+    pub fn unit(self) -> String {
+        if self.insert_spaces {
+            " ".repeat(self.width)
+        } else {
+            String::from("\t")
+        }
+    }
+
+    /// What the Tab key inserts at the given column: spaces up to the next
+    /// tab stop, or a tab.
+    pub fn tab(self, column: usize) -> String {
+        if self.insert_spaces {
+            " ".repeat(self.width - column % self.width)
+        } else {
+            String::from("\t")
+        }
+    }
+
+    /// Returns the line without one level of its indentation: a tab, or up
+    /// to a level of spaces.
+    pub fn dedent(self, line: &str) -> &str {
+        if let Some(rest) = line.strip_prefix('\t') {
+            return rest;
+        }
+
+        let spaces = line
+            .bytes()
+            .take(self.width)
+            .take_while(|byte| *byte == b' ')
+            .count();
+
+        &line[spaces..]
+    }
+
+    /// Parses `spaces <width>` or `tabs [<width>]`.
+    pub fn from_key(value: &str) -> Option<Self> {
+        let mut words = value.split_whitespace();
+        let style = words.next()?;
+        let width = match words.next() {
+            Some(width) => width.parse().ok().filter(|width| *width > 0)?,
+            None => 4,
+        };
+
+        match style {
+            "spaces" => Some(Self::spaces(width)),
+            "tabs" => Some(Self::tabs(width)),
+            _ => None,
+        }
+    }
+
+    /// Formats the policy the way [`Indent::from_key`] reads it.
+    pub fn key(self) -> String {
+        if self.insert_spaces {
+            format!("spaces {}", self.width)
+        } else {
+            format!("tabs {}", self.width)
+        }
+    }
+
+    /// Guesses the policy of a text from its indented lines: tabs if lines
+    /// start with one, or the smallest run of leading spaces otherwise.
+    pub fn detect(text: &str) -> Option<Self> {
+        if text.lines().any(|line| line.starts_with('\t')) {
+            return Some(Self::tabs(4));
+        }
+
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start_matches(' ').len())
+            .filter(|width| *width > 1)
+            .min()
+            .map(Self::spaces)
+    }
+
+    /// Reads the policy the `.editorconfig` files above a file set for it.
+    ///
+    /// Only the `[*]`, `[*.ext]`, `[*.{a,b}]` and `[name]` section forms
+    /// are understood.
+    pub fn from_editorconfig(file: &Path) -> Option<Self> {
+        let name = file.file_name()?.to_str()?;
+        let mut configs = Vec::new();
+
+        for directory in file.ancestors().skip(1) {
+            let Ok(config) =
+                fs::read_to_string(directory.join(".editorconfig"))
+            else {
+                continue;
+            };
+
+            let is_root = config.lines().any(|line| {
+                line.split_once('=').is_some_and(|(key, value)| {
+                    key.trim() == "root" && value.trim() == "true"
+                })
+            });
+
+            configs.push(config);
+
+            if is_root {
+                break;
+            }
+        }
+
+        // Closer files override the ones above them
+        let mut style = None;
+        let mut size = None;
+
+        for config in configs.iter().rev() {
+            let mut matches = false;
+
+            for line in config.lines().map(str::trim) {
+                if let Some(section) =
+                    line.strip_prefix('[').and_then(|s| s.strip_suffix(']'))
+                {
+                    matches = section_matches(section, name);
+                    continue;
+                }
+
+                let Some((key, value)) = line.split_once('=') else {
+                    continue;
+                };
+
+                if !matches {
+                    continue;
+                }
+
+                let value = value.trim().to_ascii_lowercase();
+
+                match key.trim() {
+                    "indent_style" => style = Some(value == "space"),
+                    "indent_size" | "tab_width" => {
+                        if let Ok(width) = value.parse::<usize>() {
+                            size = Some(width).filter(|width| *width > 0);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        match (style, size) {
+            (None, None) => None,
+            (style, size) => Some(Self {
+                insert_spaces: style.unwrap_or(true),
+                width: size.unwrap_or(4),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Indent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.insert_spaces {
+            write!(f, "Spaces: {}", self.width)
+        } else {
+            write!(f, "Tabs: {}", self.width)
+        }
+    }
+}
+
+fn section_matches(section: &str, name: &str) -> bool {
+    if section == "*" || section == name {
+        return true;
+    }
+
+    let Some(extensions) = section.strip_prefix("*.") else {
+        return false;
+    };
+
+    let extensions = extensions
+        .strip_prefix('{')
+        .and_then(|extensions| extensions.strip_suffix('}'))
+        .unwrap_or(extensions);
+
+    extensions.split(',').any(|extension| {
+        name.strip_suffix(extension.trim())
+            .is_some_and(|stem| stem.ends_with('.'))
+    })
+}
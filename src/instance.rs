@@ -0,0 +1,125 @@
+//! Hands files over to an editor that is already running, so that opening a
+//! file from a file manager adds a tab instead of another window.
+//!
+//! Instances find each other through a Unix socket. Elsewhere, every launch
+//! opens its own window.
+use iced::Subscription;
+
+use std::path::{Path, PathBuf};
+
+/// Sends the file to a running instance, returning whether one took it.
+#[cfg(unix)]
+pub fn forward(file: &Path) -> bool {
+    use std::io::Write;
+    use std::os::unix::net::UnixStream;
+
+    let Ok(directory) = std::env::current_dir() else {
+        return false;
+    };
+
+    let Some(path) = socket_path() else {
+        return false;
+    };
+
+    let Ok(mut stream) = UnixStream::connect(path) else {
+        return false;
+    };
+
+    writeln!(stream, "{}", directory.join(file).display()).is_ok()
+}
+
+#[cfg(not(unix))]
+pub fn forward(_file: &Path) -> bool {
+    false
+}
+
+/// Listens for files forwarded by instances launched later.
+#[cfg(unix)]
+pub fn listen() -> Subscription<PathBuf> {
+    use std::io::ErrorKind;
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    struct Listener;
+
+    iced::subscription::channel(
+        std::any::TypeId::of::<Listener>(),
+        100,
+        |output| async move {
+            let Some(path) = socket_path() else {
+                return std::future::pending().await;
+            };
+
+            match UnixStream::connect(&path) {
+                // Another instance is running, and keeps the socket
+                Ok(_) => return std::future::pending().await,
+                // Left behind by an instance that did not shut down cleanly
+                Err(error) if error.kind() == ErrorKind::ConnectionRefused => {
+                    let _ = std::fs::remove_file(&path);
+                }
+                Err(_) => {}
+            }
+
+            if let Ok(listener) = UnixListener::bind(&path) {
+                std::thread::spawn(move || accept(listener, output));
+            }
+
+            std::future::pending().await
+        },
+    )
+}
+
+#[cfg(not(unix))]
+pub fn listen() -> Subscription<PathBuf> {
+    Subscription::none()
+}
+
+#[cfg(unix)]
+fn accept(
+    listener: std::os::unix::net::UnixListener,
+    mut output: iced::futures::channel::mpsc::Sender<PathBuf>,
+) {
+    use iced::futures::{executor, SinkExt};
+    use std::io::{BufRead, BufReader};
+
+    for stream in listener.incoming().map_while(Result::ok) {
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            if executor::block_on(output.send(PathBuf::from(line))).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Returns the path of the socket, in a folder only the user can get into.
+#[cfg(unix)]
+fn socket_path() -> Option<PathBuf> {
+    let directory = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(directory) => PathBuf::from(directory),
+        None => private_directory()?,
+    };
+
+    Some(directory.join("iced_proto.sock"))
+}
+
+/// Returns a folder of the user in the shared temporary one, creating it if
+/// needed.
+///
+/// Anyone can create files there, so a folder someone else made first, or
+/// that others can get into, is not used.
+#[cfg(unix)]
+fn private_directory() -> Option<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+    // SAFETY: getuid has no preconditions and cannot fail
+    let uid = unsafe { libc::getuid() };
+    let directory = std::env::temp_dir().join(format!("iced_proto-{uid}"));
+
+    let _ = std::fs::DirBuilder::new().mode(0o700).create(&directory);
+
+    let metadata = std::fs::symlink_metadata(&directory).ok()?;
+    let private = metadata.is_dir()
+        && metadata.uid() == uid
+        && metadata.permissions().mode() & 0o077 == 0;
+
+    private.then_some(directory)
+}
@@ -0,0 +1,571 @@
+//! Keyboard shortcuts and the messages they produce.
+use crate::convert::Format;
+use crate::editing::{Case, Normalization};
+use crate::Message;
+
+use iced::keyboard::key::Named;
+use iced::keyboard::{Key, Modifiers};
+
+/// A key press, with the exact modifiers held, bound to a [`Message`].
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub key: Key<&'static str>,
+    pub modifiers: Modifiers,
+    pub message: Message,
+    /// What the palette calls the action, like `Save All`.
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+impl Binding {
+    /// Returns the key combination as it is usually written, like `Ctrl+S`.
+    pub fn shortcut(&self) -> String {
+        let mut parts = Vec::new();
+
+        if self.modifiers.command() {
+            parts.push(if cfg!(target_os = "macos") {
+                "Cmd"
+            } else {
+                "Ctrl"
+            });
+        }
+
+        if self.modifiers.alt() {
+            parts.push("Alt");
+        }
+
+        if self.modifiers.shift() {
+            parts.push("Shift");
+        }
+
+        let key = match &self.key {
+            Key::Character(c) => c.to_uppercase(),
+            Key::Named(named) => format!("{named:?}"),
+            Key::Unidentified => String::from("?"),
+        };
+
+        parts.push(&key);
+        parts.join("+")
+    }
+}
+
+/// The shortcuts of the editor, looked up in order.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+    /// The actions the command palette offers besides the bound ones, by
+    /// name.
+    commands: Vec<(Message, &'static str)>,
+}
+
+impl Keymap {
+    /// Shortcuts the editor handles itself rather than through the keymap.
+    pub const GESTURES: &'static [(&'static str, &'static str)] = &[
+        ("Alt+Shift+Up", "Copy the lines up"),
+        ("Alt+Shift+Down", "Copy the lines down"),
+        ("Ctrl+Click", "Open the file under the cursor"),
+        ("Ctrl+Shift+V", "Paste without reindenting"),
+    ];
+
+    /// Returns the message bound to the given key press, if any.
+    pub fn resolve(&self, key: &Key, modifiers: Modifiers) -> Option<Message> {
+        self.bindings
+            .iter()
+            .find(|binding| {
+                key.as_ref() == binding.key && modifiers == binding.modifiers
+            })
+            .map(|binding| binding.message.clone())
+    }
+
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    pub fn commands(&self) -> &[(Message, &'static str)] {
+        &self.commands
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let command = Modifiers::COMMAND;
+        let command_shift = Modifiers::COMMAND | Modifiers::SHIFT;
+
+        Self {
+            bindings: vec![
+                bind_named(
+                    Named::F1,
+                    Modifiers::empty(),
+                    Message::ShowShortcuts,
+                    "Show Shortcuts",
+                    "Show keyboard shortcuts",
+                ),
+                bind_named(
+                    Named::Escape,
+                    Modifiers::empty(),
+                    Message::ClosePanels,
+                    "Close Panels",
+                    "Close the find, go to and other fields",
+                ),
+                bind("n", command, Message::NewFile, "New File", "New file"),
+                bind(
+                    "o",
+                    command,
+                    Message::OpenFile,
+                    "Open File",
+                    "Open a file",
+                ),
+                bind(
+                    "o",
+                    Modifiers::COMMAND | Modifiers::ALT,
+                    Message::OpenFiles,
+                    "Open Files",
+                    "Open several files in new tabs",
+                ),
+                bind("s", command, Message::SaveFile, "Save", "Save file"),
+                bind(
+                    "s",
+                    command_shift,
+                    Message::SaveAsFile,
+                    "Save As",
+                    "Save as a new file",
+                ),
+                bind(
+                    "a",
+                    command,
+                    Message::SelectAll,
+                    "Select All",
+                    "Select all",
+                ),
+                bind("z", command, Message::Undo, "Undo", "Undo"),
+                bind("z", command_shift, Message::Redo, "Redo", "Redo"),
+                bind("y", command, Message::Redo, "Redo", "Redo"),
+                bind(
+                    "t",
+                    command,
+                    Message::TabNew,
+                    "New Tab",
+                    "Open a new tab",
+                ),
+                bind_named(
+                    Named::F2,
+                    Modifiers::empty(),
+                    Message::RenameTab,
+                    "Rename Tab",
+                    "Give the current tab a title of its own",
+                ),
+                bind(
+                    "q",
+                    command,
+                    Message::SaveAllAndExit,
+                    "Save All and Exit",
+                    "Save every file and exit",
+                ),
+                bind(
+                    "w",
+                    command,
+                    Message::CloseActiveTab,
+                    "Close Tab",
+                    "Close the current tab",
+                ),
+                bind(
+                    "w",
+                    Modifiers::COMMAND | Modifiers::ALT,
+                    Message::CloseAllTabs,
+                    "Close All Tabs",
+                    "Close every tab",
+                ),
+                bind_named(
+                    Named::Tab,
+                    command,
+                    Message::NextTab,
+                    "Next Tab",
+                    "Switch to the next tab",
+                ),
+                bind_named(
+                    Named::Tab,
+                    command_shift,
+                    Message::PreviousTab,
+                    "Previous Tab",
+                    "Switch to the previous tab",
+                ),
+                bind(
+                    "\\",
+                    command,
+                    Message::ToggleSplit,
+                    "Toggle Split",
+                    "Show another tab beside the current one",
+                ),
+                bind_named(
+                    Named::PageUp,
+                    command_shift,
+                    Message::MoveTabLeft,
+                    "Move Tab Left",
+                    "Move the current tab left",
+                ),
+                bind_named(
+                    Named::PageDown,
+                    command_shift,
+                    Message::MoveTabRight,
+                    "Move Tab Right",
+                    "Move the current tab right",
+                ),
+                bind("=", command, Message::ZoomIn, "Zoom In", "Zoom in"),
+                bind("+", command, Message::ZoomIn, "Zoom In", "Zoom in"),
+                bind("+", command_shift, Message::ZoomIn, "Zoom In", "Zoom in"),
+                bind("-", command, Message::ZoomOut, "Zoom Out", "Zoom out"),
+                bind(
+                    "0",
+                    command,
+                    Message::ZoomReset,
+                    "Reset Zoom",
+                    "Reset the zoom",
+                ),
+                bind(
+                    "f",
+                    command,
+                    Message::OpenFind,
+                    "Find and Replace",
+                    "Find and replace",
+                ),
+                bind(
+                    "g",
+                    command,
+                    Message::OpenGoTo,
+                    "Go To Line",
+                    "Go to line:column",
+                ),
+                bind(
+                    "e",
+                    command,
+                    Message::ShowEditTimeline,
+                    "Show Edit Timeline",
+                    "Show the recent edits",
+                ),
+                bind(
+                    "p",
+                    command_shift,
+                    Message::OpenCommandPalette,
+                    "Command Palette",
+                    "Run a command by name",
+                ),
+                bind(
+                    "p",
+                    Modifiers::COMMAND | Modifiers::ALT,
+                    Message::OpenThemePalette,
+                    "Pick Theme",
+                    "Pick a syntax theme with a live preview",
+                ),
+                bind(
+                    "/",
+                    command_shift,
+                    Message::ToggleBlockComment,
+                    "Toggle Block Comment",
+                    "Toggle block comment",
+                ),
+                bind(
+                    "r",
+                    command_shift,
+                    Message::ToggleRecording,
+                    "Record Macro",
+                    "Start or stop recording a macro",
+                ),
+                bind(
+                    "e",
+                    command_shift,
+                    Message::PlayMacro,
+                    "Play Macro",
+                    "Play the recorded macro",
+                ),
+                bind(
+                    "t",
+                    command_shift,
+                    Message::TrimSelection,
+                    "Trim Selection",
+                    "Trim whitespace off the selection",
+                ),
+                bind(
+                    "d",
+                    command,
+                    Message::DuplicateSelection,
+                    "Duplicate Selection",
+                    "Duplicate the selection or line",
+                ),
+                bind(
+                    "l",
+                    command,
+                    Message::ExpandSelectionToLines,
+                    "Expand Selection to Lines",
+                    "Expand the selection to full lines",
+                ),
+                bind_named(
+                    Named::Tab,
+                    Modifiers::empty(),
+                    Message::InsertTab,
+                    "Indent",
+                    "Indent at the cursor",
+                ),
+                bind_named(
+                    Named::Tab,
+                    Modifiers::SHIFT,
+                    Message::DedentLines,
+                    "Dedent Lines",
+                    "Dedent the current or selected lines",
+                ),
+                bind(
+                    "u",
+                    command_shift,
+                    Message::InsertUuid,
+                    "Insert UUID",
+                    "Insert a random UUID",
+                ),
+                bind(
+                    "u",
+                    Modifiers::COMMAND | Modifiers::ALT,
+                    Message::InsertToken,
+                    "Insert Hex Token",
+                    "Insert a random hex token",
+                ),
+                bind_named(
+                    Named::F9,
+                    Modifiers::empty(),
+                    Message::SortLines,
+                    "Sort Lines",
+                    "Sort the selected lines",
+                ),
+                bind(
+                    "q",
+                    command_shift,
+                    Message::HardWrap,
+                    "Hard Wrap",
+                    "Wrap long lines at the wrap column",
+                ),
+                bind(
+                    "q",
+                    Modifiers::ALT,
+                    Message::ReflowParagraph,
+                    "Reflow Paragraph",
+                    "Rewrap the paragraph at the wrap column",
+                ),
+                bind(
+                    "k",
+                    command_shift,
+                    Message::TransposeChars,
+                    "Transpose Characters",
+                    "Swap the characters around the cursor",
+                ),
+                bind(
+                    "o",
+                    command_shift,
+                    Message::TransposeWords,
+                    "Transpose Words",
+                    "Swap the words around the cursor",
+                ),
+                bind(
+                    "o",
+                    Modifiers::ALT,
+                    Message::SwitchToRelated,
+                    "Switch to Related File",
+                    "Switch to the related file, like a header or its tests",
+                ),
+                bind(
+                    "\\",
+                    command_shift,
+                    Message::SelectToMatchingBracket,
+                    "Select to Matching Bracket",
+                    "Select to the matching bracket",
+                ),
+                bind(
+                    "m",
+                    command,
+                    Message::JumpToMatchingBracket,
+                    "Jump to Matching Bracket",
+                    "Jump to the matching bracket",
+                ),
+                bind(
+                    "b",
+                    command_shift,
+                    Message::ToggleBlame,
+                    "Toggle Blame",
+                    "Show or hide git blame",
+                ),
+                bind(
+                    "h",
+                    command_shift,
+                    Message::RevertHunk,
+                    "Revert Change",
+                    "Revert the change under the cursor",
+                ),
+                bind(
+                    "w",
+                    command_shift,
+                    Message::SurroundWith,
+                    "Surround With",
+                    "Surround the selection with a pair of strings",
+                ),
+                bind(
+                    "a",
+                    command_shift,
+                    Message::StripAnsi,
+                    "Strip ANSI Codes",
+                    "Strip ANSI escape codes from the selection or file",
+                ),
+                bind(
+                    "l",
+                    Modifiers::COMMAND | Modifiers::ALT,
+                    Message::CollapseBlankLines,
+                    "Collapse Blank Lines",
+                    "Collapse runs of blank lines in the selection or file",
+                ),
+                bind(
+                    "i",
+                    Modifiers::COMMAND | Modifiers::ALT,
+                    Message::SortImports,
+                    "Sort Imports",
+                    "Sort the imports and drop the repeated ones",
+                ),
+                bind(
+                    "i",
+                    command_shift,
+                    Message::StripInvisibles,
+                    "Strip Invisible Characters",
+                    "Strip invisible characters from the selection or file",
+                ),
+                bind(
+                    "n",
+                    command_shift,
+                    Message::NormalizeUnicode(Normalization::Nfc),
+                    "Normalize to NFC",
+                    "Normalize the selection or file to composed Unicode",
+                ),
+                bind(
+                    "n",
+                    Modifiers::COMMAND | Modifiers::ALT,
+                    Message::NormalizeUnicode(Normalization::Nfd),
+                    "Normalize to NFD",
+                    "Normalize the selection or file to decomposed Unicode",
+                ),
+                bind(
+                    "j",
+                    Modifiers::COMMAND | Modifiers::ALT,
+                    Message::ConvertTo(Format::Json),
+                    "Convert to JSON",
+                    "Convert the selection or file to JSON",
+                ),
+                bind(
+                    "y",
+                    Modifiers::COMMAND | Modifiers::ALT,
+                    Message::ConvertTo(Format::Yaml),
+                    "Convert to YAML",
+                    "Convert the selection or file to YAML",
+                ),
+                bind(
+                    "t",
+                    Modifiers::COMMAND | Modifiers::ALT,
+                    Message::ConvertTo(Format::Toml),
+                    "Convert to TOML",
+                    "Convert the selection or file to TOML",
+                ),
+                bind_named(
+                    Named::F8,
+                    Modifiers::empty(),
+                    Message::SelectNextInvisible,
+                    "Select Next Invisible",
+                    "Select the next invisible character",
+                ),
+                bind(
+                    "m",
+                    command_shift,
+                    Message::CompareWithDisk,
+                    "Compare with Disk",
+                    "Compare with the file on disk",
+                ),
+                bind(
+                    ",",
+                    command,
+                    Message::OpenConfigFile,
+                    "Open Preferences",
+                    "Open the preferences file",
+                ),
+                bind(
+                    "j",
+                    command_shift,
+                    Message::ExportSelectionToFile,
+                    "Export Selection to File",
+                    "Export the selection to a new file",
+                ),
+                bind(
+                    "y",
+                    command_shift,
+                    Message::ExportSelectionToClipboard,
+                    "Export Selection to Clipboard",
+                    "Export the selection to the clipboard",
+                ),
+                bind(
+                    "s",
+                    Modifiers::COMMAND | Modifiers::ALT,
+                    Message::ExportSnippet,
+                    "Export Snippet",
+                    "Export the selection or file as a snippet",
+                ),
+                bind(
+                    "m",
+                    Modifiers::COMMAND | Modifiers::ALT,
+                    Message::TogglePreview,
+                    "Toggle Markdown Preview",
+                    "Preview the Markdown file beside it",
+                ),
+            ],
+            commands: vec![
+                (Message::SaveAll, "Save All"),
+                (Message::ReloadFile, "Reload File"),
+                (Message::DuplicateTab, "Duplicate Tab"),
+                (Message::ToggleReadOnly, "Toggle Read-Only"),
+                (Message::Cut, "Cut"),
+                (Message::Copy, "Copy"),
+                (Message::Paste, "Paste"),
+                (Message::CopyLineUp, "Copy Lines Up"),
+                (Message::CopyLineDown, "Copy Lines Down"),
+                (Message::GoToFileUnderCursor, "Open File Under Cursor"),
+                (Message::ReplaceAll, "Replace All"),
+                (Message::ClearHighlights, "Clear Highlights"),
+                (Message::InsertBase64Token, "Insert Base64 Token"),
+                (Message::ChangeCase(Case::Upper), "Upper Case"),
+                (Message::ChangeCase(Case::Lower), "Lower Case"),
+                (Message::ChangeCase(Case::Title), "Title Case"),
+            ],
+        }
+    }
+}
+
+fn bind(
+    key: &'static str,
+    modifiers: Modifiers,
+    message: Message,
+    name: &'static str,
+    description: &'static str,
+) -> Binding {
+    Binding {
+        key: Key::Character(key),
+        modifiers,
+        message,
+        name,
+        description,
+    }
+}
+
+fn bind_named(
+    key: Named,
+    modifiers: Modifiers,
+    message: Message,
+    name: &'static str,
+    description: &'static str,
+) -> Binding {
+    Binding {
+        key: Key::Named(key),
+        modifiers,
+        message,
+        name,
+        description,
+    }
+}
@@ -0,0 +1,232 @@
+//! Per-language facts the editing commands rely on.
+use std::fmt;
+
+use crate::indent::Indent;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Language {
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+    pub line_comment: Option<&'static str>,
+    pub block_comment: Option<(&'static str, &'static str)>,
+    /// Whether blocks are delimited by braces, so indentation follows them.
+    pub braces: bool,
+    /// How files are indented unless they or the preferences say otherwise.
+    pub indent: Indent,
+    /// What ends a line after which auto-indent goes a level deeper.
+    pub indent_after: &'static [&'static str],
+    /// What auto-indent moves back a level when typed on a line alone.
+    pub dedent_on: &'static [&'static str],
+    /// What the lines bringing in other modules start with.
+    pub imports: &'static [&'static str],
+}
+
+impl Language {
+    pub const ALL: &'static [Self] = &[
+        Self {
+            name: "Rust",
+            extensions: &["rs"],
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            braces: true,
+            indent: Indent::spaces(4),
+            indent_after: &["{", "(", "["],
+            dedent_on: &["}", ")", "]"],
+            imports: &["use ", "pub use ", "pub(crate) use "],
+        },
+        Self {
+            name: "C",
+            extensions: &["c", "h"],
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            braces: true,
+            indent: Indent::spaces(4),
+            indent_after: &["{", "(", "["],
+            dedent_on: &["}", ")", "]"],
+            imports: &["#include "],
+        },
+        Self {
+            name: "C++",
+            extensions: &["cpp", "cc", "cxx", "hpp", "hh"],
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            braces: true,
+            indent: Indent::spaces(4),
+            indent_after: &["{", "(", "["],
+            dedent_on: &["}", ")", "]"],
+            imports: &["#include "],
+        },
+        Self {
+            name: "JavaScript",
+            extensions: &["js", "mjs", "jsx"],
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            braces: true,
+            indent: Indent::spaces(2),
+            indent_after: &["{", "(", "["],
+            dedent_on: &["}", ")", "]"],
+            imports: &["import "],
+        },
+        Self {
+            name: "TypeScript",
+            extensions: &["ts", "tsx"],
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            braces: true,
+            indent: Indent::spaces(2),
+            indent_after: &["{", "(", "["],
+            dedent_on: &["}", ")", "]"],
+            imports: &["import "],
+        },
+        Self {
+            name: "Go",
+            extensions: &["go"],
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            braces: true,
+            indent: Indent::tabs(4),
+            indent_after: &["{", "(", "["],
+            dedent_on: &["}", ")", "]"],
+            imports: &["import "],
+        },
+        Self {
+            name: "Java",
+            extensions: &["java"],
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            braces: true,
+            indent: Indent::spaces(4),
+            indent_after: &["{", "(", "["],
+            dedent_on: &["}", ")", "]"],
+            imports: &["import "],
+        },
+        Self {
+            name: "CSS",
+            extensions: &["css"],
+            line_comment: None,
+            block_comment: Some(("/*", "*/")),
+            braces: true,
+            indent: Indent::spaces(2),
+            indent_after: &["{", "(", "["],
+            dedent_on: &["}", ")", "]"],
+            imports: &["@import "],
+        },
+        Self {
+            name: "HTML",
+            extensions: &["html", "htm", "xml", "svg"],
+            line_comment: None,
+            block_comment: Some(("<!--", "-->")),
+            braces: false,
+            indent: Indent::spaces(2),
+            indent_after: &[],
+            dedent_on: &[],
+            imports: &[],
+        },
+        Self {
+            name: "Markdown",
+            extensions: &["md", "markdown"],
+            line_comment: None,
+            block_comment: Some(("<!--", "-->")),
+            braces: false,
+            indent: Indent::spaces(2),
+            indent_after: &[],
+            dedent_on: &[],
+            imports: &[],
+        },
+        Self {
+            name: "Python",
+            extensions: &["py"],
+            line_comment: Some("#"),
+            block_comment: None,
+            braces: false,
+            indent: Indent::spaces(4),
+            indent_after: &[":"],
+            dedent_on: &["else:", "finally:"],
+            imports: &["import ", "from "],
+        },
+        Self {
+            name: "Shell",
+            extensions: &["sh", "bash", "zsh"],
+            line_comment: Some("#"),
+            block_comment: None,
+            braces: false,
+            indent: Indent::spaces(2),
+            indent_after: &["then", "do", "else", "{"],
+            dedent_on: &["fi", "done", "else", "}"],
+            imports: &[],
+        },
+        Self {
+            name: "TOML",
+            extensions: &["toml"],
+            line_comment: Some("#"),
+            block_comment: None,
+            braces: false,
+            indent: Indent::spaces(4),
+            indent_after: &[],
+            dedent_on: &[],
+            imports: &[],
+        },
+        Self {
+            name: "YAML",
+            extensions: &["yml", "yaml"],
+            line_comment: Some("#"),
+            block_comment: None,
+            braces: false,
+            indent: Indent::spaces(2),
+            indent_after: &[":"],
+            dedent_on: &[],
+            imports: &[],
+        },
+        Self {
+            name: "JSON",
+            extensions: &["json"],
+            line_comment: None,
+            block_comment: None,
+            braces: true,
+            indent: Indent::spaces(2),
+            indent_after: &["{", "(", "["],
+            dedent_on: &["}", ")", "]"],
+            imports: &[],
+        },
+        Self {
+            name: "Lua",
+            extensions: &["lua"],
+            line_comment: Some("--"),
+            block_comment: Some(("--[[", "]]")),
+            braces: false,
+            indent: Indent::spaces(2),
+            indent_after: &["then", "do", "else", "{"],
+            dedent_on: &["end", "else", "}"],
+            imports: &[],
+        },
+        Self {
+            name: "SQL",
+            extensions: &["sql"],
+            line_comment: Some("--"),
+            block_comment: Some(("/*", "*/")),
+            braces: false,
+            indent: Indent::spaces(4),
+            indent_after: &["("],
+            dedent_on: &[")"],
+            imports: &[],
+        },
+    ];
+
+    pub fn from_extension(extension: &str) -> Option<&'static Self> {
+        Self::ALL
+            .iter()
+            .find(|language| language.extensions.contains(&extension))
+    }
+
+    pub fn from_name(name: &str) -> Option<&'static Self> {
+        Self::ALL
+            .iter()
+            .find(|language| language.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
@@ -0,0 +1,61 @@
+//! The line endings a file is written with.
+//!
+//! The editor splits lines on either ending when loading, so a fragment only
+//! remembers which one to write back.
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub const ALL: [Self; 2] = [Self::Lf, Self::Crlf];
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "lf" => Some(Self::Lf),
+            "crlf" => Some(Self::Crlf),
+            _ => None,
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Lf => "lf",
+            Self::Crlf => "crlf",
+        }
+    }
+
+    /// Returns the ending most lines of the text use, and how many lines use
+    /// the other one.
+    pub fn detect(text: &str) -> (Self, usize) {
+        let crlf = text.matches("\r\n").count();
+        let lf = text.matches('\n').count() - crlf;
+
+        if crlf > lf {
+            (Self::Crlf, lf)
+        } else {
+            (Self::Lf, crlf)
+        }
+    }
+
+    /// Writes the `\n` separated text with this ending.
+    pub fn apply(self, text: String) -> String {
+        match self {
+            Self::Lf => text,
+            Self::Crlf => text.replace('\n', "\r\n"),
+        }
+    }
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Lf => "LF",
+            Self::Crlf => "CRLF",
+        })
+    }
+}
@@ -0,0 +1,113 @@
+//! The column of line numbers left of the editor.
+//!
+//! The editor does not tell how far it is scrolled, so while the lines are
+//! numbered it is laid out as tall as its text, and a scrollable around both
+//! it and the numbers does the scrolling instead. A line that wraps takes up
+//! more room than its number, so the numbers below it drift.
+use iced::advanced::widget::operation::Scrollable;
+use iced::advanced::widget::{self, Operation};
+use iced::widget::scrollable::{self, AbsoluteOffset};
+use iced::{Rectangle, Vector};
+
+/// How tall a line of the editor is, at the default text size of 16 and line
+/// height of 1.3.
+pub const LINE_HEIGHT: f32 = 16.0 * 1.3;
+
+/// The space the editor leaves around its text.
+pub const PADDING: u16 = 5;
+
+/// The scrollable around the numbered editor of a fragment, as each pane of
+/// a split has its own.
+pub fn id(fragment: usize) -> scrollable::Id {
+    scrollable::Id::new(format!("line-numbers-{fragment}"))
+}
+
+/// Returns the line, counted from 0, at the given height from the top of the
+/// numbers.
+pub fn line_at(y: f32) -> usize {
+    ((y - f32::from(PADDING)) / LINE_HEIGHT).max(0.0) as usize
+}
+
+/// Returns the numbers of the lines of a text, one per line.
+pub fn numbers(line_count: usize) -> String {
+    (1..=line_count)
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Scrolls the numbered editor by the given lines, like the mouse wheel
+/// scrolls the editor.
+pub fn scroll<T>(fragment: usize, lines: i32) -> impl Operation<T> {
+    ScrollLines {
+        target: id(fragment).into(),
+        scroll: Scroll::By(lines as f32 * LINE_HEIGHT),
+    }
+}
+
+/// Scrolls the numbered editor as little as it takes to show the line.
+pub fn reveal<T>(fragment: usize, line: usize) -> impl Operation<T> {
+    ScrollLines {
+        target: id(fragment).into(),
+        scroll: Scroll::Reveal(f32::from(PADDING) + line as f32 * LINE_HEIGHT),
+    }
+}
+
+enum Scroll {
+    /// Scrolling by so many pixels.
+    By(f32),
+    /// Scrolling to the top of a line, given in pixels from the top of the
+    /// text.
+    Reveal(f32),
+}
+
+struct ScrollLines {
+    target: widget::Id,
+    scroll: Scroll,
+}
+
+impl<T> Operation<T> for ScrollLines {
+    fn container(
+        &mut self,
+        _id: Option<&widget::Id>,
+        _bounds: Rectangle,
+        operate_on_children: &mut dyn FnMut(&mut dyn Operation<T>),
+    ) {
+        operate_on_children(self);
+    }
+
+    fn scrollable(
+        &mut self,
+        state: &mut dyn Scrollable,
+        id: Option<&widget::Id>,
+        bounds: Rectangle,
+        translation: Vector,
+    ) {
+        if Some(&self.target) != id {
+            return;
+        }
+
+        // The scrollable keeps the offset within its content by itself
+        let y = match self.scroll {
+            Scroll::By(pixels) => translation.y + pixels,
+            Scroll::Reveal(top) => {
+                let margin = f32::from(PADDING);
+
+                if top - margin < translation.y {
+                    top - margin
+                } else if top + LINE_HEIGHT + margin
+                    > translation.y + bounds.height
+                {
+                    top + LINE_HEIGHT + margin - bounds.height
+                } else {
+                    return;
+                }
+            }
+        };
+
+        state.scroll_to(AbsoluteOffset {
+            x: translation.x,
+            y: y.max(0.0),
+        });
+    }
+}
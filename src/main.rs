@@ -1,337 +1,6834 @@
+mod auto_indent;
+mod convert;
+mod diff;
+mod editing;
+mod encoding;
+mod file_settings;
+mod find;
+mod focus;
+mod geometry;
+mod git;
+mod glob;
+mod history;
+mod imports;
+mod indent;
+mod instance;
+mod keymap;
+mod language;
+mod line_ending;
+mod line_numbers;
+mod markdown;
+mod palette;
+mod path_completion;
+mod preferences;
+mod recent;
+mod related;
+mod search_highlight;
+mod session;
+mod snippet;
+mod token;
+mod undo_store;
+mod view_mode;
+
+use iced::alignment;
+use iced::clipboard;
+use iced::event::{self, Event};
 use iced::executor;
-use iced::highlighter::{self, Highlighter};
-use iced::keyboard;
+use iced::font;
+use iced::futures::SinkExt;
+use iced::highlighter;
+use iced::keyboard::{self, key::Named, Key};
+use iced::mouse;
+use iced::subscription;
 use iced::theme::{self, Theme};
+use iced::time;
+use iced::window;
 use iced::widget::{
-    button, column, container, horizontal_space, pick_list, row, text,
-    text_editor, tooltip,
+    button, checkbox, column, container, horizontal_rule, horizontal_space,
+    mouse_area, pick_list, row, scrollable, text, text_editor, text_input,
+    tooltip, Column, Row,
 };
 use iced::{
-    Alignment, Application, Command, Element, Font, Length, Settings,
-    Subscription,
+    color, Alignment, Application, Color, Command, Element, Font, Length,
+    Point, Settings, Size, Subscription,
+};
+use iced_aw::{Modal, TabBar, TabLabel, Wrap};
+
+use editing::{Direction, Position};
+use encoding::Encoding;
+use find::{Results, Search};
+use geometry::Geometry;
+use history::{History, Snapshot};
+use indent::Indent;
+use keymap::Keymap;
+use language::Language;
+use line_ending::LineEnding;
+use markdown::Block;
+use palette::Palette;
+use path_completion::Completion;
+use view_mode::ViewMode;
+use recent::Recent;
+use session::Session;
+use snippet::Snippet;
+use search_highlight::SearchHighlighter;
+use preferences::{
+    HighlightBackground, Preferences, Startup, TabBarPosition, TabOverflow,
+    ToolbarAction, TrailingNewline,
 };
-use iced_aw::{TabBar, TabLabel};
 
+use std::cell::{OnceCell, RefCell};
+use std::collections::VecDeque;
 use std::ffi;
+use std::future::Future;
+use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+const ICON_FONT: Font = iced_aw::BOOTSTRAP_FONT;
+const ICON_FONT_BYTES: &[u8] = iced_aw::BOOTSTRAP_FONT_BYTES;
+/// How many edit locations a fragment remembers.
+const EDIT_HISTORY: usize = 50;
+/// How long the window has to stay the same size and place before they are
+/// written down.
+const GEOMETRY_DEBOUNCE: Duration = Duration::from_millis(500);
+/// The size text is laid out with, which zooming scales away from.
+const DEFAULT_FONT_SIZE: u16 = 16;
+/// How many times a macro can be played back in one go.
+const MAX_MACRO_REPEAT: usize = 1000;
 
 pub fn main() -> iced::Result {
+    let args = Args::parse();
+    let preferences = Preferences::load();
+
+    // Once a running editor takes the first file, it gets all of them
+    if preferences.single_instance
+        && args.files.first().is_some_and(|file| instance::forward(file))
+    {
+        for file in &args.files[1..] {
+            instance::forward(file);
+        }
+
+        return Ok(());
+    }
+
+    let geometry = Geometry::load();
+    let defaults = window::Settings::default();
+
     Editor::run(Settings {
+        flags: (args, preferences, geometry),
         default_font: Font::MONOSPACE,
+        window: window::Settings {
+            size: geometry.map_or(defaults.size, |geometry| {
+                Size::new(geometry.width, geometry.height)
+            }),
+            position: match geometry.and_then(|geometry| geometry.position) {
+                Some((x, y)) => {
+                    window::Position::Specific(Point::new(x as f32, y as f32))
+                }
+                None => defaults.position,
+            },
+            // Closing the window asks about unsaved changes first
+            exit_on_close_request: false,
+            ..defaults
+        },
         ..Settings::default()
     })
 }
 
+/// The command line arguments.
+#[derive(Debug, Default)]
+struct Args {
+    /// Whether `--follow -` was given, streaming stdin into a read-only tab.
+    follow_stdin: bool,
+    /// The files to open in tabs of their own, instead of the startup
+    /// preference.
+    files: Vec<PathBuf>,
+    /// Text to select the first occurrence of in the first file, given as
+    /// `/text`.
+    search: Option<String>,
+    /// The byte offset to put the cursor at in the first file, given as
+    /// `file@offset`.
+    offset: Option<usize>,
+}
+
+impl Args {
+    fn parse() -> Self {
+        let mut args = Self::default();
+        let mut arguments = std::env::args().skip(1);
+
+        while let Some(argument) = arguments.next() {
+            if argument == "--follow" {
+                args.follow_stdin = arguments.next().as_deref() == Some("-");
+            } else if let Some(search) = argument
+                .strip_prefix('/')
+                .filter(|_| !args.files.is_empty())
+            {
+                args.search = Some(search.to_owned());
+            } else if let Some((file, offset)) = argument
+                .rsplit_once('@')
+                .filter(|_| !Path::new(&argument).exists())
+                .and_then(|(file, offset)| Some((file, offset.parse().ok()?)))
+            {
+                if args.files.is_empty() {
+                    args.offset = Some(offset);
+                }
+
+                args.files.push(PathBuf::from(file));
+            } else {
+                args.files.push(PathBuf::from(argument));
+            }
+        }
+
+        args
+    }
+}
+
 struct Editor {
     theme: highlighter::Theme,
     fragment_index: usize,
     fragments: Vec<FragmentContent>,
+    modifiers: keyboard::Modifiers,
+    preferences: Preferences,
+    toast: Option<String>,
+    has_icon_font: bool,
+    recording: Option<Vec<text_editor::Action>>,
+    recorded_macro: Vec<text_editor::Action>,
+    macro_repeat: String,
+    /// The size the editor text is zoomed to.
+    font_size: u16,
+    playback: VecDeque<text_editor::Action>,
+    /// The tab the macro is being played back in.
+    playback_fragment: Option<FragmentId>,
+    keymap: Keymap,
+    file_settings: file_settings::Store,
+    /// The files opened or saved lately, the most recent first.
+    recent: Recent,
+    /// Whether the welcome screen is shown instead of the editor.
+    welcome: bool,
+    /// Text to select once the file given on the command line is opened.
+    pending_search: Option<String>,
+    /// The byte offset to put the cursor at once that file is opened.
+    pending_offset: Option<usize>,
+    show_shortcuts: bool,
+    show_edit_timeline: bool,
+    /// Whether the editor was last clicked or typed into, rather than left
+    /// with Escape. It does not tell when it gets or loses the focus.
+    editor_focused: bool,
+    /// The pane and line the mouse was last over in the line numbers.
+    gutter_line: Option<(usize, usize)>,
+    /// The pane whose lines are being selected from the line numbers, and
+    /// the line the selection started on.
+    gutter_drag: Option<(usize, usize)>,
+    /// Whether diff tabs show whitespace and mark whitespace-only changes.
+    reveal_whitespace: bool,
+    /// The theme to go back to if the theme palette is dismissed, while it
+    /// is open.
+    theme_palette: Option<highlighter::Theme>,
+    /// What is typed in the command palette, while it is open.
+    command_palette: Option<Palette>,
+    /// The input of the go to bar, while it is open.
+    go_to: Option<String>,
+    /// The title being typed for the current tab.
+    renaming: Option<String>,
+    /// The search of the find bar, while it is open.
+    find: Option<Search>,
+    /// The selection Replace All is limited to, from where it started to
+    /// where it ended, when it is not the whole text.
+    replace_scope: Option<(Position, Position)>,
+    /// Whether the occurrences stay highlighted once the find bar closes.
+    pin_search: bool,
+    /// The search of a closed find bar whose occurrences are still
+    /// highlighted, until Escape or Clear Highlights.
+    pinned_search: Option<Search>,
+    /// The lines of every fragment the search of the find bar was last
+    /// looked for in.
+    search_results: Option<Results>,
+    /// How many occurrences Replace All waits for confirmation to replace.
+    replacing: Option<usize>,
+    /// The deletion of the whole text waiting for confirmation.
+    emptying: Option<text_editor::Action>,
+    /// The input of the surround bar, while it is open.
+    surround: Option<String>,
+    /// The last strings a selection was surrounded with, to offer again.
+    last_surround: String,
+    /// The name of the snippet being exported, while it is typed.
+    snippet_name: Option<String>,
+    /// The tab of the last session to switch to once it is reopened.
+    restored_active: Option<PathBuf>,
+    /// The dirty tab whose closing waits for the user to save or discard.
+    closing: Option<usize>,
+    /// How many tabs Close All waits for confirmation to close.
+    confirming_close_all: Option<usize>,
+    /// How many files Save All waits for confirmation to save.
+    confirming_save_all: Option<usize>,
+    /// The untitled tabs Save All asks where to save, one after the other,
+    /// the one being asked about first.
+    save_all_queue: VecDeque<FragmentId>,
+    /// Whether every tab is being closed, one dirty tab after the other.
+    closing_all: bool,
+    /// The dirty tab whose reloading waits for the user to discard its
+    /// changes.
+    reloading: Option<usize>,
+    /// Whether the editor exits once every dirty tab has been saved.
+    exiting: bool,
+    /// How many dirty tabs closing the window waits for the user to save
+    /// or discard.
+    confirming_exit: Option<usize>,
+    /// When the text was last edited, which auto-save waits to be a while
+    /// ago.
+    last_edit: Instant,
+    /// How many files are being loaded or saved in the background.
+    operations: usize,
+    /// The size and position of the window, as last resized and moved.
+    geometry: Geometry,
+    /// When the window was last resized or moved, until the geometry is
+    /// written for the next launch.
+    geometry_changed: Option<Instant>,
+    session_write: Rewrite,
+    /// The undo history of the files saved lately, for when they reopen.
+    undo_store: undo_store::Store,
+    undo_store_write: Rewrite,
+    recent_write: Rewrite,
+    /// The tabs shown side by side, left and right, while the editor is
+    /// split. The current tab is always one of them.
+    split: Option<(usize, usize)>,
+    /// The files offered for the path being typed in quotes, if any.
+    completion: Option<Completion>,
+    /// Whether Markdown files show how they render beside the editor.
+    preview: bool,
+}
+
+/// Tells a fragment apart for as long as it is open, unlike its index, which
+/// changes as the tabs before it are closed or moved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FragmentId(usize);
+
+impl Default for FragmentId {
+    fn default() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
 }
 
+#[derive(Default)]
 struct FragmentContent {
+    /// What loads and saves report back to, as the tab may have moved by
+    /// the time they are done.
+    id: FragmentId,
     file: Option<PathBuf>,
+    link_target: Option<PathBuf>,
     content: text_editor::Content,
     is_loading: bool,
     is_dirty: bool,
+    is_saving: bool,
+    /// Whether the tab closes once it is saved.
+    close_after_save: bool,
+    follows_stdin: bool,
+    /// Whether edits are refused, for files only meant to be looked at.
+    read_only: bool,
+    view_mode: ViewMode,
+    /// The bytes of the content as shown in [`ViewMode::Hex`].
+    hex_dump: String,
+    /// The file whose unsaved changes this fragment lists as a diff.
+    diff_of: Option<PathBuf>,
+    /// The diff of a diff tab as it was generated, before any whitespace
+    /// is revealed.
+    diff_text: String,
+    final_newline: bool,
+    line_ending: LineEnding,
+    indent: Indent,
+    /// How many lines ended with the other line ending when loaded, until
+    /// the endings are normalized.
+    stray_line_endings: usize,
+    encoding: Encoding,
+    /// The language picked to highlight and edit the file as, whatever its
+    /// extension.
+    language_override: Option<&'static Language>,
+    /// How many invalid byte sequences the file had in its encoding when
+    /// loaded, which hints that it was read with the wrong one.
+    decode_errors: usize,
+    /// The encoding the file more likely has, when it had invalid bytes.
+    likely_encoding: Option<Encoding>,
+    blamed_line: Option<usize>,
+    blame: Option<git::Blame>,
+    /// The lines of the file as last loaded or saved, to diff against.
+    disk_lines: Option<Vec<String>>,
+    /// The line changes the status bar sums up, worked out when first
+    /// shown after a change, as diffing a large file takes a while.
+    line_changes: OnceCell<Vec<diff::Hunk>>,
+    /// When the tab was last switched away from, if ever.
+    last_active: Option<Instant>,
+    /// The name the tab is shown with instead of the one of its file.
+    title: Option<String>,
+    /// Whether saving leaves the whitespace and final newlines alone.
+    save_as_is: bool,
+    /// The line and column, counted from 1, to jump to once loaded.
+    pending_location: Option<(usize, usize)>,
+    /// Where and when the fragment was edited, oldest first, one entry per
+    /// run of edits on the same line.
+    edits: VecDeque<(Position, Instant)>,
+    /// When the file was modified on disk, as of its last load or save.
+    modified: Option<SystemTime>,
+    /// How many bytes the file had on disk, as of its last load or save.
+    disk_size: Option<u64>,
+    /// A newer modification time seen on disk and when it was first seen,
+    /// until it has settled long enough for the file to be reloaded.
+    disk_change: Option<(SystemTime, Instant)>,
+    /// Whether the file changed on disk while it had unsaved changes.
+    changed_on_disk: bool,
+    history: History,
+    /// Whether the file is highlighted even though it is too large to be.
+    highlight_anyway: bool,
+    /// What the status bar tells about the whole text, worked out when it
+    /// is first shown after a change.
+    stats: OnceCell<Stats>,
+    /// How many times the search of the find bar occurs, along with the
+    /// search counted.
+    match_count: RefCell<Option<(Search, usize)>>,
+}
+
+/// What is counted over the whole text of a fragment.
+#[derive(Debug, Default)]
+struct Stats {
+    bytes: usize,
+    chars: usize,
+    words: usize,
+    /// The names of the invisible characters there are, with how many of
+    /// each.
+    invisibles: Vec<(&'static str, usize)>,
+}
+
+impl Stats {
+    fn of(text: &str) -> Self {
+        let mut invisibles: Vec<(&'static str, usize)> = Vec::new();
+
+        for (_, name) in editing::invisibles(text) {
+            match invisibles.iter_mut().find(|(counted, _)| *counted == name) {
+                Some((_, count)) => *count += 1,
+                None => invisibles.push((name, 1)),
+            }
+        }
+
+        Self {
+            bytes: text.len(),
+            chars: text.chars().count(),
+            words: text.split_whitespace().count(),
+            invisibles,
+        }
+    }
+}
+
+/// How far a config file rewritten after every change is, as two writes
+/// running at once could land out of order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Rewrite {
+    #[default]
+    Done,
+    Writing,
+    /// Changed again while being written, so it is written once more after.
+    Outdated,
 }
 
 #[derive(Debug, Clone)]
 enum Message {
     ActionPerformed(text_editor::Action),
+    /// An action in the pane of the fragment at the index, which becomes
+    /// the current one unless the pane is only scrolled.
+    ActionPerformedIn(usize, text_editor::Action),
+    /// The mouse moved over the line numbers of a pane, this far down them.
+    GutterMoved(usize, f32),
+    GutterPressed(usize),
+    GutterReleased,
+    /// Shows another tab beside the current one, or the current one alone
+    /// again.
+    ToggleSplit,
+    /// Shows how the current Markdown file renders beside it, or hides it.
+    TogglePreview,
+    /// Types the rest of the offered file at the index.
+    CompletePath(usize),
+    /// Escape was pressed, even in a widget keeping it to itself, like the
+    /// editor and the text fields.
+    EscapePressed,
+    OpenCommandPalette,
+    CommandPaletteChanged(String),
+    /// Picks the command that many places down the matches.
+    SelectCommand(isize),
+    /// Runs the command at the position among the matches.
+    RunCommand(usize),
+    CloseCommandPalette,
     ThemeSelected(highlighter::Theme),
+    OpenThemePalette,
+    /// Applies the theme that many places down the list, for a preview.
+    PreviewTheme(isize),
+    CloseThemePalette,
     NewFile,
     OpenFile,
-    FileOpened(Result<(PathBuf, Arc<String>), Error>),
+    /// The load of a file into the fragment is done.
+    FileOpened(FragmentId, Result<LoadedFile, Error>),
+    OpenRecent(PathBuf),
+    SwitchToRelated,
+    FileDropped(PathBuf),
     SaveFile,
-    FileSaved(Result<PathBuf, Error>),
+    SaveAsFile,
+    /// The save of the fragment is done.
+    FileSaved(FragmentId, Arc<SavedText>, Result<PathBuf, Error>),
+    /// Saves every tab with changes, asking where to for the untitled ones.
+    SaveAll,
+    ConfirmSaveAll,
+    CancelSaveAll,
+    /// Counts so many loads or saves as done, then handles their result.
+    OperationsFinished(usize, Box<Message>),
+    OpenFiles,
+    FilesOpened(Result<Vec<PickedFile>, Error>),
+    SaveAllAndExit,
+    /// The window is asked to close, like with its close button.
+    CloseRequested,
+    DiscardAndExit,
+    CancelExit,
     TabSelected(usize),
+    NextTab,
+    PreviousTab,
+    MoveTabLeft,
+    MoveTabRight,
     TabClosed(usize),
+    ConfirmClose(usize),
+    CloseActiveTab,
+    SaveAndClose(usize),
+    CancelClose,
+    CloseAllTabs,
+    ConfirmCloseAll,
+    CancelCloseAll,
     TabNew,
+    DuplicateTab,
+    SelectAll,
+    Cut,
+    Copy,
+    Paste,
+    Pasted(Option<String>),
+    ToggleReadOnly,
+    ModifiersChanged(keyboard::Modifiers),
+    CopyLineUp,
+    CopyLineDown,
+    DuplicateSelection,
+    Select(Position, Position),
+    TrailingNewlineSelected(TrailingNewline),
+    SaveAsIsToggled(bool),
+    LineEndingSelected(LineEnding),
+    /// Saves every tab that can be edited with the line ending from now on.
+    LineEndingForAllTabs(LineEnding),
+    EncodingSelected(Encoding),
+    /// Picks the language of the current file, or goes back to the one of
+    /// its extension.
+    LanguageSelected(Option<&'static Language>),
+    ViewModeSelected(ViewMode),
+    IndentSelected(Indent),
+    FileSettingsWritten(Result<(), Error>),
+    ThemeWritten(Result<(), Error>),
+    WindowResized(u32, u32),
+    WindowMoved(i32, i32),
+    /// Writes the geometry of the window once it stopped changing.
+    GeometryTick,
+    GeometryWritten(Result<(), Error>),
+    SessionWritten(Result<(), Error>),
+    UndoStoreWritten(Result<(), Error>),
+    RecentWritten(Result<(), Error>),
+    InsertTab,
+    DedentLines,
+    InsertUuid,
+    InsertToken,
+    InsertBase64Token,
+    ReindentPasteToggled(bool),
+    AutoSaveToggled(bool),
+    AutoSaveTick,
+    LineNumbersToggled(bool),
+    /// Highlights the current file even though it is too large to be.
+    HighlightAnyway,
+    PreferencesWritten(Result<(), Error>),
+    RevealWhitespaceToggled(bool),
+    GoToFileUnderCursor,
+    DismissToast,
+    IconFontLoaded(Result<(), font::Error>),
+    ToggleBlockComment,
+    StartRecording,
+    StopRecording,
+    ToggleRecording,
+    PlayMacro,
+    PlaybackStep,
+    MacroRepeatChanged(String),
+    KeyPressed(keyboard::Key, keyboard::Modifiers),
+    TrimSelection,
+    ExpandSelectionToLines,
+    SelectToMatchingBracket,
+    /// Moves the caret to the match of the bracket next to it.
+    JumpToMatchingBracket,
+    SortLines,
+    HardWrap,
+    ReflowParagraph,
+    StripAnsi,
+    SortImports,
+    CollapseBlankLines,
+    StripInvisibles,
+    NormalizeUnicode(editing::Normalization),
+    /// Changes the case of the selection, or of the line of the caret.
+    ChangeCase(editing::Case),
+    ConvertTo(convert::Format),
+    SelectNextInvisible,
+    TransposeChars,
+    TransposeWords,
+    OpenWith(String),
+    ToolExited(String, Result<ExitStatus, Error>),
+    StdinChunk(String),
+    ToggleBlame,
+    BlameLoaded(PathBuf, usize, Option<git::Blame>),
+    RevertHunk,
+    CompareWithDisk,
+    DiskCompared(PathBuf, Result<LoadedFile, Error>),
+    OpenConfigFile,
+    ApplyPreferences(bool),
+    FileForwarded(PathBuf),
+    ShowShortcuts,
+    HideShortcuts,
+    CheckDisk,
+    DiskChecked(Vec<(PathBuf, Option<SystemTime>)>),
+    FileChangedOnDisk(usize),
+    ReloadFile,
+    ReloadFromDisk(usize),
+    CancelReload,
+    FileReloaded(PathBuf, Result<LoadedFile, Error>),
+    /// Dismisses the change on disk of a fragment, keeping its edits.
+    KeepMine(usize),
+    ShowEditTimeline,
+    HideEditTimeline,
+    /// Moves the cursor to an entry of the edit timeline.
+    JumpToEdit(usize),
+    ExportSelectionToFile,
+    ExportSelectionToClipboard,
+    SelectionExported(Result<PathBuf, Error>),
+    /// Asks for the name to export the selection, or the whole text, as a
+    /// snippet under.
+    ExportSnippet,
+    SnippetNameChanged(String),
+    SnippetNameSubmitted,
+    CloseSnippetName,
+    SnippetExported(Result<PathBuf, Error>),
+    RenameTab,
+    TabTitleChanged(String),
+    /// Shows a tab with the given title, or with its file name again when
+    /// the title is blank.
+    SetTabTitle(usize, String),
+    CloseRenameTab,
+    OpenGoTo,
+    OpenFind,
+    FindChanged(String),
+    ReplaceChanged(String),
+    CaseSensitiveToggled(bool),
+    /// Limits Replace All to the current selection, or lifts the limit.
+    InSelectionToggled(bool),
+    FindNext,
+    ReplaceAll,
+    ConfirmReplaceAll,
+    CancelReplaceAll,
+    ConfirmEmptying,
+    CancelEmptying,
+    CloseFind,
+    PinSearchToggled(bool),
+    ClearHighlights,
+    /// Looks for the query in every fragment, with the options of the find
+    /// bar.
+    SearchAll(String),
+    /// Switches to the fragment of a line found in every fragment, and goes
+    /// to the occurrence on it.
+    JumpToResult {
+        fragment: usize,
+        line: usize,
+    },
+    CloseSearchResults,
+    GoToChanged(String),
+    GoToSubmitted,
+    CloseGoTo,
+    SurroundWith,
+    SurroundChanged(String),
+    SurroundSubmitted,
+    CloseSurround,
+    Undo,
+    Redo,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    /// Closes the find, go to, surround, snippet name and tab title fields.
+    ClosePanels,
+}
+
+impl Message {
+    /// Whether the message may change the text of a fragment, or the file
+    /// on disk it is compared with, after which what is cached about the
+    /// text is worked out again.
+    fn changes_text(&self) -> bool {
+        match self {
+            Self::ActionPerformed(action)
+            | Self::ActionPerformedIn(_, action) => action.is_edit(),
+            // Besides edits, loads and saves replace the text, and so do
+            // the changes made on behalf of the user
+            Self::NewFile
+            | Self::FilesOpened(_)
+            | Self::FileOpened(..)
+            | Self::FileSaved(..)
+            | Self::FileReloaded(..)
+            | Self::CompletePath(_)
+            | Self::PlaybackStep
+            | Self::ConfirmEmptying
+            | Self::StdinChunk(_)
+            | Self::RevealWhitespaceToggled(_) => true,
+            message => message.edits_text(),
+        }
+    }
+
+    /// Whether the message changes the text of the current fragment, which
+    /// read-only fragments refuse. Edits typed in the editor itself come
+    /// through [`Message::ActionPerformed`] instead.
+    fn edits_text(&self) -> bool {
+        matches!(
+            self,
+            Self::CopyLineUp
+                | Self::CopyLineDown
+                | Self::DuplicateSelection
+                | Self::LineEndingSelected(_)
+                | Self::InsertTab
+                | Self::DedentLines
+                | Self::InsertUuid
+                | Self::InsertToken
+                | Self::InsertBase64Token
+                | Self::ToggleBlockComment
+                | Self::PlayMacro
+                | Self::SortLines
+                | Self::HardWrap
+                | Self::ReflowParagraph
+                | Self::StripAnsi
+                | Self::SortImports
+                | Self::CollapseBlankLines
+                | Self::StripInvisibles
+                | Self::NormalizeUnicode(_)
+                | Self::ChangeCase(_)
+                | Self::ConvertTo(_)
+                | Self::Cut
+                | Self::Paste
+                | Self::TransposeChars
+                | Self::TransposeWords
+                | Self::RevertHunk
+                | Self::ReplaceAll
+                | Self::ConfirmReplaceAll
+                | Self::SurroundSubmitted
+                | Self::Undo
+                | Self::Redo
+        )
+    }
 }
 
 impl Application for Editor {
     type Message = Message;
     type Theme = Theme;
     type Executor = executor::Default;
-    type Flags = ();
+    type Flags = (Args, Preferences, Option<Geometry>);
+
+    fn new(
+        (flags, preferences, geometry): Self::Flags,
+    ) -> (Self, Command<Message>) {
+        let mut restored_active = None;
+        let files = match &preferences.startup {
+            _ if flags.follow_stdin => Vec::new(),
+            _ if !flags.files.is_empty() => flags.files,
+            Startup::DefaultFile => match Session::load() {
+                Some(session) => {
+                    restored_active = session.active;
+                    session.files
+                }
+                None => vec![default_file()],
+            },
+            Startup::Empty | Startup::Welcome => Vec::new(),
+            Startup::File(path) => vec![path.clone()],
+        };
+        let welcome = !flags.follow_stdin
+            && files.is_empty()
+            && preferences.startup == Startup::Welcome;
 
-    fn new(_flags: Self::Flags) -> (Self, Command<Message>) {
         let fragment_content = FragmentContent {
-            file: None,
-            content: text_editor::Content::new(),
-            is_loading: true,
-            is_dirty: false,
+            is_loading: !files.is_empty(),
+            follows_stdin: flags.follow_stdin,
+            ..FragmentContent::default()
+        };
+        let operations = files.len();
+        let load = if files.is_empty() {
+            Command::none()
+        } else {
+            finishing(operations, load_files(files), Message::FilesOpened)
         };
         (
             Self {
-                theme: highlighter::Theme::SolarizedDark,
+                theme: load_theme()
+                    .unwrap_or(highlighter::Theme::SolarizedDark),
                 fragment_index: 0,
                 fragments: vec![fragment_content],
+                modifiers: keyboard::Modifiers::default(),
+                preferences,
+                toast: None,
+                has_icon_font: false,
+                recording: None,
+                recorded_macro: Vec::new(),
+                macro_repeat: String::new(),
+                font_size: DEFAULT_FONT_SIZE,
+                playback: VecDeque::new(),
+                playback_fragment: None,
+                keymap: Keymap::default(),
+                file_settings: file_settings::Store::load(),
+                recent: Recent::load(),
+                welcome,
+                pending_search: flags.search,
+                pending_offset: flags.offset,
+                show_shortcuts: false,
+                editor_focused: false,
+                gutter_line: None,
+                gutter_drag: None,
+                show_edit_timeline: false,
+                reveal_whitespace: false,
+                theme_palette: None,
+                command_palette: None,
+                go_to: None,
+                renaming: None,
+                find: None,
+                replace_scope: None,
+                pin_search: false,
+                pinned_search: None,
+                search_results: None,
+                replacing: None,
+                emptying: None,
+                surround: None,
+                last_surround: String::new(),
+                snippet_name: None,
+                restored_active,
+                closing: None,
+                confirming_close_all: None,
+                confirming_save_all: None,
+                save_all_queue: VecDeque::new(),
+                closing_all: false,
+                reloading: None,
+                exiting: false,
+                confirming_exit: None,
+                last_edit: Instant::now(),
+                operations,
+                geometry: geometry.unwrap_or_else(|| {
+                    let size = window::Settings::default().size;
+
+                    Geometry {
+                        width: size.width,
+                        height: size.height,
+                        position: None,
+                    }
+                }),
+                geometry_changed: None,
+                session_write: Rewrite::Done,
+                undo_store: undo_store::Store::load(),
+                undo_store_write: Rewrite::Done,
+                recent_write: Rewrite::Done,
+                split: None,
+                completion: None,
+                preview: false,
             },
-            Command::perform(load_file(default_file()), Message::FileOpened),
+            Command::batch([
+                load,
+                font::load(ICON_FONT_BYTES).map(Message::IconFontLoaded),
+            ]),
         )
     }
 
     fn title(&self) -> String {
-        String::from("Editor - Iced")
+        // The index is only clamped on the next update, so it may be stale
+        let fragment = &self.fragments
+            [self.fragment_index.min(self.fragments.len() - 1)];
+
+        let dirty = if fragment.is_dirty { " *" } else { "" };
+
+        format!("{}{dirty} — Editor", fragment.name())
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
+        // There is always at least one fragment, but the index may be stale
+        self.fragment_index = self.fragment_index.min(self.fragments.len() - 1);
+
+        if let Message::ActionPerformed(action)
+        | Message::ActionPerformedIn(_, action) = &message
+        {
+            if !matches!(action, text_editor::Action::Scroll { .. }) {
+                self.editor_focused = true;
+            }
+        }
+
+        let fragment = &mut self.fragments[self.fragment_index];
+        let edits = match &message {
+            Message::ActionPerformed(action) => action.is_edit(),
+            message => message.edits_text(),
+        };
+
+        if fragment.refuses_edits() && edits {
+            self.toast = Some(format!("{} is read-only", fragment.name()));
+
+            return Command::none();
+        }
+
+        // The counts are redone when next shown, rather than on every view
+        if message.changes_text() {
+            for fragment in &mut self.fragments {
+                fragment.forget_counts();
+            }
+        }
+
         let fragment = &mut self.fragments[self.fragment_index];
+
+        if edits {
+            self.last_edit = Instant::now();
+        }
+
         match message {
             Message::ActionPerformed(action) => {
+                // The offered files are picked from the keyboard
+                if let Some(completion) = &mut self.completion {
+                    match action {
+                        text_editor::Action::Move(text_editor::Motion::Up) => {
+                            completion.select_previous();
+
+                            return Command::none();
+                        }
+                        text_editor::Action::Move(
+                            text_editor::Motion::Down,
+                        ) => {
+                            completion.select_next();
+
+                            return Command::none();
+                        }
+                        text_editor::Action::Edit(text_editor::Edit::Enter) => {
+                            let selected = completion.selected;
+
+                            return self
+                                .update(Message::CompletePath(selected));
+                        }
+                        _ => {}
+                    }
+                }
+
+                // The numbered editor is as tall as its text, so the
+                // scrollable around it scrolls instead
+                if let text_editor::Action::Scroll { lines } = action {
+                    if self.preferences.show_line_numbers {
+                        return Command::widget(line_numbers::scroll(
+                            self.fragment_index,
+                            lines,
+                        ));
+                    }
+                }
+
+                if let Some(recording) = &mut self.recording {
+                    recording.push(action.clone());
+                }
+
+                // The editor turns Shift+Up/Down into selections before we
+                // ever see the key press, so Alt+Shift+Up/Down is resolved here
+                if self.modifiers.alt() && self.modifiers.shift() {
+                    match action {
+                        text_editor::Action::Select(
+                            text_editor::Motion::Up,
+                        ) => return self.update(Message::CopyLineUp),
+                        text_editor::Action::Select(
+                            text_editor::Motion::Down,
+                        ) => return self.update(Message::CopyLineDown),
+                        _ => {}
+                    }
+                }
+
+                // Ctrl+Shift+PageUp/PageDown would select a page otherwise
+                if self.modifiers.command() && self.modifiers.shift() {
+                    match action {
+                        text_editor::Action::Select(
+                            text_editor::Motion::PageUp,
+                        ) => return self.update(Message::MoveTabLeft),
+                        text_editor::Action::Select(
+                            text_editor::Motion::PageDown,
+                        ) => return self.update(Message::MoveTabRight),
+                        _ => {}
+                    }
+                }
+
+                let is_jump = self.modifiers.command()
+                    && matches!(action, text_editor::Action::Click(_));
+
+                // Ctrl+Shift+V still pastes the block as is
+                let action = match action {
+                    text_editor::Action::Edit(text_editor::Edit::Paste(
+                        pasted,
+                    )) if !self.modifiers.shift()
+                        && pasted.contains('\n')
+                        && fragment
+                            .language(&self.preferences.new_file_language)
+                            .is_some_and(|language| {
+                                self.preferences.reindents_paste(language)
+                            }) =>
+                    {
+                        text_editor::Action::Edit(text_editor::Edit::Paste(
+                            Arc::new(editing::reindent_paste(
+                                &fragment.content,
+                                &pasted,
+                                &fragment.indent.unit(),
+                            )),
+                        ))
+                    }
+                    action => action,
+                };
+
+                // New lines follow the indentation rules of the language
+                let mut caret = None;
+                let action = match action {
+                    text_editor::Action::Edit(text_editor::Edit::Enter)
+                        if self.preferences.auto_indent
+                            && fragment.content.selection().is_none() =>
+                    {
+                        let language = fragment
+                            .language(&self.preferences.new_file_language);
+                        let rules = self.preferences.indent_rules(language);
+                        let (line, column) = fragment.content.cursor_position();
+                        let text = fragment
+                            .content
+                            .line(line)
+                            .map(|text| text.to_string())
+                            .unwrap_or_default();
+                        let indentation =
+                            rules.line_break(&text, column, fragment.indent);
+                        let brackets = self
+                            .preferences
+                            .expand_brackets
+                            .then(|| {
+                                rules.bracket_break(
+                                    &text,
+                                    column,
+                                    fragment.indent,
+                                )
+                            })
+                            .flatten();
+
+                        if let Some((inner, outer)) = brackets {
+                            caret = Some((line + 1, inner.len()));
+
+                            text_editor::Action::Edit(text_editor::Edit::Paste(
+                                Arc::new(format!("\n{inner}\n{outer}")),
+                            ))
+                        } else if indentation.is_empty() {
+                            action
+                        } else {
+                            text_editor::Action::Edit(text_editor::Edit::Paste(
+                                Arc::new(format!("\n{indentation}")),
+                            ))
+                        }
+                    }
+                    action => action,
+                };
+
+                // A held shortcut should not wipe a whole file unnoticed
+                if self.preferences.confirm_empty_buffer
+                    && matches!(
+                        action,
+                        text_editor::Action::Edit(
+                            text_editor::Edit::Backspace
+                                | text_editor::Edit::Delete
+                        )
+                    )
+                    && editing::selects_all(&fragment.content)
+                {
+                    self.emptying = Some(action);
+
+                    return Command::none();
+                }
+
+                if let text_editor::Action::Edit(edit) = &action {
+                    fragment.remember_undo(history::Kind::of(edit));
+                }
+
                 fragment.is_dirty = fragment.is_dirty || action.is_edit();
 
+                let is_edit = action.is_edit();
+                let is_insert = matches!(
+                    action,
+                    text_editor::Action::Edit(text_editor::Edit::Insert(_))
+                );
+
                 fragment.content.perform(action);
 
-                Command::none()
-            }
-            Message::ThemeSelected(theme) => {
-                self.theme = theme;
+                // A closing typed on a line of its own goes back a level
+                if is_insert && self.preferences.auto_indent {
+                    let (line, column) = fragment.content.cursor_position();
+                    let text = fragment
+                        .content
+                        .line(line)
+                        .map(|text| text.to_string())
+                        .unwrap_or_default();
 
-                Command::none()
-            }
-            Message::NewFile => {
-                if !fragment.is_loading {
-                    fragment.file = None;
-                    fragment.content = text_editor::Content::new();
+                    if column == text.len() {
+                        let previous = (0..line)
+                            .rev()
+                            .filter_map(|line| fragment.content.line(line))
+                            .find(|line| !line.trim().is_empty())
+                            .map_or_else(String::new, |line| {
+                                auto_indent::leading(&line).to_owned()
+                            });
+                        let language = fragment
+                            .language(&self.preferences.new_file_language);
+                        let rules = self.preferences.indent_rules(language);
+
+                        if let Some(dedented) =
+                            rules.dedent(&text, &previous, fragment.indent)
+                        {
+                            editing::replace(
+                                &mut fragment.content,
+                                (line, 0),
+                                (line, column),
+                                &dedented,
+                            );
+                        }
+                    }
                 }
 
-                Command::none()
-            }
-            Message::OpenFile => {
-                if fragment.is_loading {
-                    Command::none()
+                if is_edit {
+                    fragment.record_edit();
+                }
+
+                // The caret goes to the indented line between the brackets
+                let caret = caret.map_or_else(Command::none, |caret| {
+                    select_after_layout(&mut fragment.content, caret, caret)
+                });
+                // Typing a path keeps offering the files it may go on to
+                self.completion = if is_edit {
+                    self.complete_path()
                 } else {
-                    fragment.is_loading = true;
+                    None
+                };
 
-                    Command::perform(open_file(), Message::FileOpened)
+                let blame = Command::batch([
+                    caret,
+                    self.reveal_cursor(),
+                    self.blame_current_line(),
+                ]);
+
+                if is_jump {
+                    Command::batch([
+                        self.update(Message::GoToFileUnderCursor),
+                        blame,
+                    ])
+                } else {
+                    blame
                 }
             }
-            Message::FileOpened(result) => {
-                fragment.is_loading = false;
-                fragment.is_dirty = false;
+            Message::ActionPerformedIn(index, action) => {
+                if index >= self.fragments.len() {
+                    return Command::none();
+                }
 
-                if let Ok((path, contents)) = result {
-                    fragment.file = Some(path);
-                    fragment.content = text_editor::Content::with_text(&contents);
+                if index == self.fragment_index {
+                    return self.update(Message::ActionPerformed(action));
                 }
 
-                Command::none()
+                // Scrolling the other pane leaves the current one as it is
+                if let text_editor::Action::Scroll { lines } = action {
+                    return if self.preferences.show_line_numbers {
+                        Command::widget(line_numbers::scroll(index, lines))
+                    } else {
+                        self.fragments[index].content.perform(action);
+
+                        Command::none()
+                    };
+                }
+
+                Command::batch([
+                    self.update(Message::TabSelected(index)),
+                    self.update(Message::ActionPerformed(action)),
+                ])
             }
-            Message::SaveFile => {
-                if fragment.is_loading {
-                    Command::none()
-                } else {
-                    fragment.is_loading = true;
+            Message::GutterMoved(index, y) => {
+                let line = line_numbers::line_at(y);
 
-                    Command::perform(
-                        save_file(fragment.file.clone(), fragment.content.text()),
-                        Message::FileSaved,
-                    )
+                self.gutter_line = Some((index, line));
+
+                match self.gutter_drag {
+                    Some((dragged, anchor)) if dragged == index => {
+                        self.select_lines(anchor, line)
+                    }
+                    _ => Command::none(),
                 }
             }
-            Message::FileSaved(result) => {
-                fragment.is_loading = false;
+            Message::GutterPressed(index) => {
+                let Some((hovered, line)) = self.gutter_line else {
+                    return Command::none();
+                };
 
-                if let Ok(path) = result {
-                    fragment.file = Some(path);
-                    fragment.is_dirty = false;
+                if hovered != index || index >= self.fragments.len() {
+                    return Command::none();
                 }
 
+                self.activate(index);
+                self.gutter_drag = Some((index, line));
+
+                self.select_lines(line, line)
+            }
+            Message::GutterReleased => {
+                self.gutter_drag = None;
+
                 Command::none()
             }
-            Message::TabSelected(index) => {
-                self.fragment_index = index;
+            Message::CompletePath(index) => {
+                let Some(completion) = self.completion.take() else {
+                    return Command::none();
+                };
+                let Some(rest) = completion.rest(index) else {
+                    return Command::none();
+                };
+
+                fragment.remember_undo(history::Kind::Other);
+                fragment.content.perform(text_editor::Action::Edit(
+                    text_editor::Edit::Paste(Arc::new(rest.to_owned())),
+                ));
+                fragment.is_dirty = true;
+                fragment.record_edit();
+
+                // A folder goes on to offer its own files
+                self.completion = self.complete_path();
+
                 Command::none()
             }
-            Message::TabClosed(index) => {
-                self.fragments.remove(index);
-                // Reset to the first tab if the current one was removed
-                // This should eventually move to adjacent tabs
-                self.fragment_index = 0;
+            Message::EscapePressed => {
+                self.editor_focused = false;
+                self.completion = None;
+                self.command_palette = None;
 
-                if self.fragments.is_empty() {
-                    self.fragments.push(FragmentContent {
-                        file: None,
-                        content: text_editor::Content::new(),
-                        is_loading: false,
-                        is_dirty: false,
-                    });
-                }
                 Command::none()
             }
-            Message::TabNew => {
-                let fragment_content = FragmentContent {
-                    file: None,
-                    content: text_editor::Content::new(),
-                    is_loading: false,
-                    is_dirty: false,
-                };
-                self.fragments.push(fragment_content);
-                self.fragment_index = self.fragments.len() - 1;
+            Message::OpenCommandPalette => {
+                self.command_palette = Some(Palette::default());
+
+                text_input::focus(command_palette_id())
+            }
+            Message::CommandPaletteChanged(query) => {
+                // The best match is picked again as the query changes
+                self.command_palette = Some(Palette { query, selected: 0 });
+
                 Command::none()
             }
-        }
-    }
+            Message::SelectCommand(offset) => {
+                let count = self.palette_matches().len();
 
-    fn subscription(&self) -> Subscription<Message> {
-        keyboard::on_key_press(|key, modifiers| match key.as_ref() {
-            keyboard::Key::Character("s") if modifiers.command() => {
-                Some(Message::SaveFile)
+                let Some(palette) = &mut self.command_palette else {
+                    return Command::none();
+                };
+
+                palette.select(offset, count);
+
+                // The list scrolls along to keep the picked one in sight
+                scrollable::snap_to(
+                    command_list_id(),
+                    scrollable::RelativeOffset {
+                        x: 0.0,
+                        y: palette.selected as f32
+                            / count.saturating_sub(1).max(1) as f32,
+                    },
+                )
             }
-            _ => None,
-        })
-    }
+            Message::RunCommand(position) => {
+                let mut commands = self.commands();
+                let index = self.palette_matches().get(position).copied();
 
-    fn view(&self) -> Element<Message> {
-        let idx = self.fragment_index;
-        let controls = row![
-            action(new_icon(), "New file", Some(Message::NewFile)),
-            action(
-                open_icon(),
-                "Open file",
-                (!self.fragments[idx].is_loading).then_some(Message::OpenFile)
-            ),
-            action(
-                save_icon(),
-                "Save file",
-                self.fragments[idx].is_dirty.then_some(Message::SaveFile)
-            ),
-            action(
-                new_tab_icon(),
-                "New Tab",
-                Some(Message::TabNew)
-            ),
-            horizontal_space(),
-            pick_list(
-                highlighter::Theme::ALL,
-                Some(self.theme),
-                Message::ThemeSelected
-            )
-            .text_size(14)
-            .padding([5, 10])
-        ]
-        .spacing(10)
-        .align_items(Alignment::Center);
+                self.command_palette = None;
 
-        let status = row![
-            text(if let Some(path) = &self.fragments[idx].file {
-                let path = path.display().to_string();
+                match index {
+                    Some(index) => {
+                        let (message, _, _) = commands.swap_remove(index);
 
-                if path.len() > 60 {
-                    format!("...{}", &path[path.len() - 40..])
-                } else {
-                    path
+                        self.update(message)
+                    }
+                    None => Command::none(),
                 }
-            } else {
-                String::from("New file")
-            }),
-            horizontal_space(),
-            text({
-                let (line, column) = self.fragments[idx].content.cursor_position();
+            }
+            Message::CloseCommandPalette => {
+                self.command_palette = None;
 
-                format!("{}:{}", line + 1, column + 1)
-            })
-        ]
-        .spacing(10);
+                Command::none()
+            }
+            Message::ToggleSplit => {
+                if self.split.take().is_some() {
+                    return Command::none();
+                }
 
-        let tabs = self
-            .fragments
-            .iter()
-            .fold(
-                TabBar::new(Message::TabSelected),
-                |tab_bar, fragment| {
-                    let label = if let Some(file) = &fragment.file {
-                        let file_name = file
-                            .file_name()
-                            .and_then(ffi::OsStr::to_str)
-                            .unwrap_or("Untitled")
-                            .to_string();
-                        TabLabel::Text(file_name)
-                    } else {
-                        TabLabel::Text(String::from("New"))
-                    };
-                    let idx = tab_bar.size();
-                    tab_bar.push(idx, label)
-                },
-            )
-            .on_close(Message::TabClosed)
-            .tab_width(Length::Shrink)
-            .spacing(5.0)
-            .padding(5.0)
-            .text_size(32.0);
+                let count = self.fragments.len();
 
-        column![
-            controls,
-            tabs,
-            text_editor(&self.fragments[idx].content)
-                .height(Length::Fill)
-                .on_action(Message::ActionPerformed)
-                .highlight::<Highlighter>(
-                    highlighter::Settings {
-                        theme: self.theme,
-                        extension: self
-                            .fragments[idx]
-                            .file
-                            .as_deref()
-                            .and_then(Path::extension)
-                            .and_then(ffi::OsStr::to_str)
-                            .map(str::to_string)
-                            .unwrap_or(String::from("rs")),
-                    },
-                    |highlight, _theme| highlight.to_format()
-                ),
-            status,
-        ]
-        .spacing(10)
-        .padding(10)
-        .into()
-    }
+                if count < 2 {
+                    self.toast = Some(String::from(
+                        "Open another tab to show beside this one",
+                    ));
 
-    fn theme(&self) -> Theme {
-        if self.theme.is_dark() {
-            Theme::Dark
-        } else {
-            Theme::Light
-        }
-    }
-}
+                    return Command::none();
+                }
 
-#[derive(Debug, Clone)]
-pub enum Error {
-    DialogClosed,
-    IoError(io::ErrorKind),
-}
+                self.preview = false;
+                self.split = Some((
+                    self.fragment_index,
+                    (self.fragment_index + 1) % count,
+                ));
 
-fn default_file() -> PathBuf {
-    PathBuf::from(format!("{}/src/main.rs", env!("CARGO_MANIFEST_DIR")))
-}
+                Command::none()
+            }
+            Message::TogglePreview => {
+                if self.preview {
+                    self.preview = false;
+                } else if self.is_markdown() {
+                    // The preview takes the place of the other pane
+                    self.split = None;
+                    self.preview = true;
+                } else {
+                    self.toast = Some(String::from(
+                        "Only Markdown files have a preview",
+                    ));
+                }
 
-async fn open_file() -> Result<(PathBuf, Arc<String>), Error> {
-    let picked_file = rfd::AsyncFileDialog::new()
-        .set_title("Open a text file...")
-        .pick_file()
-        .await
-        .ok_or(Error::DialogClosed)?;
+                Command::none()
+            }
+            Message::ThemeSelected(theme) => {
+                self.theme = theme;
+                self.theme_palette = None;
 
-    load_file(picked_file.path().to_owned()).await
-}
+                match theme_path() {
+                    Some(path) => Command::perform(
+                        write_config_file(path, format!("{theme}\n")),
+                        Message::ThemeWritten,
+                    ),
+                    None => Command::none(),
+                }
+            }
+            Message::OpenThemePalette => {
+                self.theme_palette = Some(self.theme);
 
-async fn load_file(path: PathBuf) -> Result<(PathBuf, Arc<String>), Error> {
-    let contents = tokio::fs::read_to_string(&path)
-        .await
-        .map(Arc::new)
-        .map_err(|error| Error::IoError(error.kind()))?;
+                Command::none()
+            }
+            Message::PreviewTheme(step) => {
+                let themes = highlighter::Theme::ALL;
+                let current = themes
+                    .iter()
+                    .position(|theme| *theme == self.theme)
+                    .unwrap_or(0);
 
-    Ok((path, contents))
-}
+                self.theme = themes[(current + themes.len())
+                    .saturating_add_signed(step)
+                    % themes.len()];
 
-async fn save_file(
-    path: Option<PathBuf>,
+                Command::none()
+            }
+            Message::CloseThemePalette => {
+                if let Some(theme) = self.theme_palette.take() {
+                    self.theme = theme;
+                }
+
+                Command::none()
+            }
+            Message::NewFile => {
+                if !fragment.is_loading {
+                    *fragment = FragmentContent::default();
+                    self.welcome = false;
+                }
+
+                Command::none()
+            }
+            Message::OpenFile => {
+                if fragment.is_loading {
+                    Command::none()
+                } else {
+                    fragment.is_loading = true;
+                    let id = fragment.id;
+
+                    self.in_flight(1, open_file(), move |result| {
+                        Message::FileOpened(id, result)
+                    })
+                }
+            }
+            Message::OpenFiles => {
+                if fragment.is_loading {
+                    Command::none()
+                } else {
+                    fragment.is_loading = true;
+
+                    self.in_flight(1, open_files(), Message::FilesOpened)
+                }
+            }
+            Message::FilesOpened(result) => {
+                fragment.is_loading = false;
+
+                let Ok(files) = result else {
+                    return Command::none();
+                };
+
+                let mut first = None;
+                let mut failed = Vec::new();
+                let mut commands = Vec::new();
+
+                for (path, file) in files {
+                    let file = match file {
+                        Ok(file) => file,
+                        Err(Error::IoError(kind)) => {
+                            failed.push(format!("{} ({kind})", path.display()));
+                            continue;
+                        }
+                        Err(Error::DialogClosed) => continue,
+                    };
+
+                    first.get_or_insert_with(|| file.path.clone());
+
+                    // Files already open keep their tab
+                    if self.fragments.iter().any(|fragment| {
+                        fragment.file.as_ref() == Some(&file.path)
+                    }) {
+                        continue;
+                    }
+
+                    // A blank tab is filled rather than left behind
+                    if !self.fragments[self.fragment_index].is_blank() {
+                        self.open_tab(FragmentContent::default());
+                    }
+
+                    commands.push(self.update(Message::FileOpened(
+                        self.fragments[self.fragment_index].id,
+                        Ok(file),
+                    )));
+                }
+
+                if !failed.is_empty() {
+                    self.toast =
+                        Some(format!("Could not open {}", failed.join(", ")));
+                }
+
+                // Tabs may have been closed to make room, so look it up last
+                let focus = [self.restored_active.take(), first]
+                    .into_iter()
+                    .flatten()
+                    .find_map(|path| {
+                        self.fragments
+                            .iter()
+                            .position(|f| f.file.as_ref() == Some(&path))
+                    });
+
+                if let Some(index) = focus {
+                    self.activate(index);
+                    commands.push(self.blame_current_line());
+                }
+
+                commands.push(self.remember_session());
+
+                Command::batch(commands)
+            }
+            Message::FileOpened(id, result) => {
+                // The tab may have been closed in the meantime
+                let Some(index) = self.position(id) else {
+                    return Command::none();
+                };
+                let fragment = &mut self.fragments[index];
+
+                fragment.is_loading = false;
+                fragment.is_dirty = false;
+
+                let opened = result.is_ok();
+
+                if let Err(Error::IoError(kind)) = &result {
+                    self.toast =
+                        Some(format!("Could not open the file: {kind}"));
+                }
+
+                if let Ok(file) = result {
+                    let settings = self.file_settings.get(&file.path);
+                    // Undo goes on from the last session if the file is
+                    // still as it was saved then
+                    let history = self
+                        .undo_store
+                        .history(&file.path, &file.contents)
+                        .unwrap_or_default();
+
+                    fragment.file = Some(file.path);
+                    fragment.link_target = file.link_target;
+                    fragment.read_only = file.read_only;
+                    fragment.encoding = file.encoding;
+                    fragment.language_override = settings.language;
+                    fragment.title = settings.title.clone();
+                    fragment.save_as_is = settings.save_as_is;
+                    fragment.decode_errors = file.decode_errors;
+                    fragment.likely_encoding = file.likely_encoding;
+                    fragment.modified = file.modified;
+                    fragment.disk_size = file.size;
+                    fragment.disk_change = None;
+                    fragment.changed_on_disk = false;
+                    fragment.history = history;
+                    fragment.final_newline = file.contents.ends_with('\n');
+                    (fragment.line_ending, fragment.stray_line_endings) =
+                        LineEnding::detect(&file.contents);
+
+                    // An ending picked before already normalized the file
+                    if let Some(line_ending) = settings.line_ending {
+                        fragment.line_ending = line_ending;
+                        fragment.stray_line_endings = 0;
+                    }
+
+                    fragment.disk_lines = Some(
+                        file.contents.lines().map(str::to_owned).collect(),
+                    );
+                    fragment.content =
+                        text_editor::Content::with_text(&file.contents);
+                    fragment.view_mode = settings
+                        .view_mode
+                        .unwrap_or_else(|| ViewMode::detect(&file.contents));
+                    fragment.indent = settings
+                        .indent
+                        .or(file.editorconfig)
+                        .or_else(|| Indent::detect(&file.contents))
+                        .unwrap_or_else(|| {
+                            self.preferences.indent(fragment.language(
+                                &self.preferences.new_file_language,
+                            ))
+                        });
+                    fragment.hex_dump = match fragment.view_mode {
+                        ViewMode::Text => String::new(),
+                        ViewMode::Hex => view_mode::hex_dump(
+                            file.contents.as_bytes(),
+                        ),
+                    };
+                    fragment.blamed_line = None;
+                    self.welcome = false;
+                }
+
+                let offset = self.pending_offset.take();
+                let found = self
+                    .pending_search
+                    .take()
+                    .and_then(|search| {
+                        editing::find(&fragment.content, &search)
+                    })
+                    .or_else(|| {
+                        let location = fragment.pending_location.take()?;
+                        let position =
+                            editing::clamp(&fragment.content, location);
+
+                        Some((position, position))
+                    })
+                    .or_else(|| {
+                        let offset = offset?;
+                        let text = editing::text(&fragment.content);
+
+                        // Offsets past the end or within a character are
+                        // moved back to the closest character
+                        let offset = (0..=offset.min(text.len()))
+                            .rev()
+                            .find(|offset| text.is_char_boundary(*offset))
+                            .unwrap_or_default();
+                        let position = editing::to_position(&text, offset);
+
+                        Some((position, position))
+                    });
+
+                let command = match found {
+                    Some((start, end)) => Command::batch([
+                        select_after_layout(&mut fragment.content, start, end),
+                        self.blame_current_line(),
+                    ]),
+                    None => self.blame_current_line(),
+                };
+
+                let remember = if opened {
+                    Command::batch([
+                        self.remember_session(),
+                        self.remember_recent(index),
+                    ])
+                } else {
+                    self.remember_session()
+                };
+
+                Command::batch([command, remember])
+            }
+            Message::OpenRecent(path) => {
+                let is_open = self
+                    .fragments
+                    .iter()
+                    .any(|fragment| fragment.file.as_ref() == Some(&path));
+
+                // A file that is gone is dropped from the list
+                if let (false, Err(error)) = (is_open, fs::metadata(&path)) {
+                    self.recent.remove(&path);
+                    self.toast = Some(format!(
+                        "Could not open {}: {}",
+                        path.display(),
+                        error.kind()
+                    ));
+
+                    return self.write_recent();
+                }
+
+                self.open_path(path)
+            }
+            Message::SwitchToRelated => {
+                let Some(file) = fragment.file.clone() else {
+                    self.toast =
+                        Some(String::from("Only files have related files"));
+
+                    return Command::none();
+                };
+                let name = fragment.name().to_owned();
+
+                let related = related::candidates(
+                    &self.preferences.related_files,
+                    &file,
+                )
+                .into_iter()
+                .find(|path| {
+                    path.is_file()
+                        || self.fragments.iter().any(|fragment| {
+                            fragment.file.as_ref() == Some(path)
+                        })
+                });
+
+                match related {
+                    Some(path) => self.open_path(path),
+                    None => {
+                        self.toast = Some(format!(
+                            "No file related to {name} was found"
+                        ));
+
+                        Command::none()
+                    }
+                }
+            }
+            Message::SaveFile | Message::SaveAsFile => {
+                // Save As always asks where to, even for a named file
+                let save_as = matches!(message, Message::SaveAsFile);
+
+                self.save(self.fragment_index, save_as)
+            }
+            Message::SaveAll | Message::ConfirmSaveAll => {
+                self.confirming_save_all = None;
+
+                let dirty: Vec<usize> = self
+                    .fragments
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, fragment)| {
+                        fragment.is_dirty
+                            && !fragment.is_loading
+                            && !fragment.read_only
+                    })
+                    .map(|(index, _)| index)
+                    .collect();
+
+                if let (Message::SaveAll, Some(threshold)) =
+                    (&message, self.preferences.confirm_save_all)
+                {
+                    if dirty.len() > threshold {
+                        self.confirming_save_all = Some(dirty.len());
+
+                        return Command::none();
+                    }
+                }
+
+                let (named, untitled): (Vec<usize>, Vec<usize>) = dirty
+                    .into_iter()
+                    .partition(|index| self.fragments[*index].file.is_some());
+
+                let mut commands: Vec<_> = named
+                    .into_iter()
+                    .map(|index| self.save(index, false))
+                    .collect();
+
+                // Only one save dialog is open at a time
+                self.save_all_queue = untitled
+                    .into_iter()
+                    .map(|index| self.fragments[index].id)
+                    .collect();
+                commands.push(self.save_next_untitled());
+
+                Command::batch(commands)
+            }
+            Message::CancelSaveAll => {
+                self.confirming_save_all = None;
+
+                Command::none()
+            }
+            Message::OperationsFinished(count, message) => {
+                self.operations = self.operations.saturating_sub(count);
+
+                self.update(*message)
+            }
+            Message::FileSaved(id, saved, result) => {
+                // Save All asks about its next untitled tab once this is done
+                let next = if self.save_all_queue.front() == Some(&id) {
+                    self.save_all_queue.pop_front();
+                    self.save_next_untitled()
+                } else {
+                    Command::none()
+                };
+
+                let Some(index) = self.position(id) else {
+                    return next;
+                };
+                let fragment = &mut self.fragments[index];
+
+                fragment.is_loading = false;
+                fragment.is_saving = false;
+
+                let mut confirm = Command::none();
+                let mut caret = Command::none();
+                let mut remember = Command::none();
+                let close = std::mem::take(&mut fragment.close_after_save);
+
+                if let Ok(path) = result {
+                    if Preferences::path().as_ref() == Some(&path) {
+                        confirm = Command::perform(
+                            confirm_apply_preferences(),
+                            Message::ApplyPreferences,
+                        );
+                    }
+
+                    // What is on screen becomes what was written to disk,
+                    // unless it was typed over while the file was written
+                    let text = editing::text(&fragment.content);
+                    let unchanged = text == saved.buffer;
+
+                    if unchanged && saved.written != text {
+                        fragment.remember_undo(history::Kind::Other);
+                        let (line, column) = fragment.content.cursor_position();
+
+                        fragment.content =
+                            text_editor::Content::with_text(&saved.written);
+                        let position = editing::clamp(
+                            &fragment.content,
+                            (line + 1, column + 1),
+                        );
+
+                        caret = select_after_layout(
+                            &mut fragment.content,
+                            position,
+                            position,
+                        );
+                    }
+
+                    // The next check of the disk picks up the saved time
+                    fragment.modified = None;
+                    fragment.disk_size =
+                        fs::metadata(&path).ok().map(|metadata| metadata.len());
+                    fragment.disk_change = None;
+                    fragment.changed_on_disk = false;
+                    fragment.file = Some(path.clone());
+                    fragment.is_dirty = !unchanged && saved.written != text;
+                    fragment.blamed_line = None;
+                    fragment.disk_lines = Some(
+                        saved.written.lines().map(str::to_owned).collect(),
+                    );
+
+                    let undo = if fragment.is_dirty {
+                        Command::none()
+                    } else {
+                        self.undo_store.update(
+                            &path,
+                            &saved.file,
+                            &fragment.history,
+                        );
+
+                        self.write_undo_store()
+                    };
+
+                    // Save As may have given the tab its first file
+                    remember = Command::batch([
+                        undo,
+                        self.remember_session(),
+                        self.remember_recent(index),
+                    ]);
+
+                    if close {
+                        return Command::batch([
+                            confirm,
+                            next,
+                            remember,
+                            self.update(Message::TabClosed(index)),
+                        ]);
+                    }
+
+                    if self.exiting {
+                        return Command::batch([
+                            confirm,
+                            next,
+                            remember,
+                            self.save_next_or_exit(),
+                        ]);
+                    }
+                } else if let Err(error) = result {
+                    let name = fragment.name().to_owned();
+
+                    self.toast = match error {
+                        Error::IoError(kind) if self.exiting => Some(format!(
+                            "Could not save {name}: {kind}, so the editor \
+                             stays open"
+                        )),
+                        Error::IoError(kind) => {
+                            Some(format!("Could not save the file: {kind}"))
+                        }
+                        Error::DialogClosed if self.exiting => Some(format!(
+                            "{name} was not saved, so the editor stays open"
+                        )),
+                        Error::DialogClosed => None,
+                    };
+                    self.exiting = false;
+                    self.closing_all = false;
+                }
+
+                Command::batch([
+                    confirm,
+                    caret,
+                    next,
+                    remember,
+                    self.blame_current_line(),
+                ])
+            }
+            Message::SaveAllAndExit => {
+                self.confirming_exit = None;
+
+                if self.fragments.iter().any(|f| f.is_loading) {
+                    self.toast = Some(String::from(
+                        "Wait for the files being opened or saved first",
+                    ));
+
+                    return Command::none();
+                }
+
+                self.exiting = true;
+
+                self.save_next_or_exit()
+            }
+            Message::CloseRequested => {
+                let count =
+                    self.fragments.iter().filter(|f| f.is_dirty).count();
+
+                if count == 0 {
+                    return window::close(window::Id::MAIN);
+                }
+
+                self.confirming_exit = Some(count);
+
+                Command::none()
+            }
+            Message::DiscardAndExit => window::close(window::Id::MAIN),
+            Message::CancelExit => {
+                self.confirming_exit = None;
+
+                Command::none()
+            }
+            Message::TabSelected(index) => {
+                if index >= self.fragments.len() {
+                    return Command::none();
+                }
+
+                self.activate(index);
+
+                Command::batch([
+                    self.remember_session(),
+                    self.blame_current_line(),
+                ])
+            }
+            Message::NextTab | Message::PreviousTab => {
+                let count = self.fragments.len();
+
+                if count < 2 {
+                    return Command::none();
+                }
+
+                let index = if let Message::NextTab = message {
+                    (self.fragment_index + 1) % count
+                } else {
+                    (self.fragment_index + count - 1) % count
+                };
+
+                self.update(Message::TabSelected(index))
+            }
+            Message::MoveTabLeft | Message::MoveTabRight => {
+                let index = self.fragment_index;
+                let target = if let Message::MoveTabLeft = message {
+                    index.checked_sub(1)
+                } else {
+                    Some(index + 1).filter(|next| *next < self.fragments.len())
+                };
+
+                let Some(target) = target else {
+                    return Command::none();
+                };
+
+                // The tab takes everything it holds along, and stays current
+                self.fragments.swap(index, target);
+                self.fragment_index = target;
+
+                // So do the prompts and panes showing either of the two
+                let swapped = |tab: usize| match tab {
+                    _ if tab == index => target,
+                    _ if tab == target => index,
+                    _ => tab,
+                };
+                self.closing = self.closing.map(swapped);
+                self.reloading = self.reloading.map(swapped);
+                self.split = self
+                    .split
+                    .map(|(left, right)| (swapped(left), swapped(right)));
+
+                if let Some(results) = &mut self.search_results {
+                    for hit in &mut results.hits {
+                        hit.fragment = swapped(hit.fragment);
+                    }
+
+                    // The lines stay listed in the order of the tabs
+                    results.hits.sort_by_key(|hit| (hit.fragment, hit.line));
+                }
+
+                self.remember_session()
+            }
+            Message::TabClosed(index) => {
+                self.closing = None;
+
+                let Some(closed) = self.fragments.get(index) else {
+                    return Command::none();
+                };
+
+                // What a load or save in flight ends with could no longer
+                // be reported, so the tab waits for it
+                if closed.is_loading {
+                    self.toast = Some(String::from(
+                        "Wait for the file to load or save before closing it",
+                    ));
+
+                    return Command::none();
+                }
+
+                self.fragments.remove(index);
+                self.forget_tab(index);
+
+                if index < self.fragment_index
+                    || self.fragment_index == self.fragments.len()
+                {
+                    self.fragment_index = self.fragment_index.saturating_sub(1);
+                }
+
+                if self.fragments.is_empty() {
+                    self.fragments.push(FragmentContent::default());
+                }
+
+                if self.closing_all {
+                    return self.close_next_or_finish();
+                }
+
+                Command::batch([
+                    self.remember_session(),
+                    self.blame_current_line(),
+                ])
+            }
+            Message::ConfirmClose(index) => {
+                match self.fragments.get(index) {
+                    Some(fragment) if fragment.is_dirty => {
+                        self.closing = Some(index);
+
+                        Command::none()
+                    }
+                    Some(_) => self.update(Message::TabClosed(index)),
+                    None => Command::none(),
+                }
+            }
+            Message::CloseActiveTab => {
+                self.update(Message::ConfirmClose(self.fragment_index))
+            }
+            Message::SaveAndClose(index) => {
+                self.closing = None;
+
+                if index >= self.fragments.len() {
+                    return Command::none();
+                }
+
+                self.activate(index);
+                self.fragments[index].close_after_save = true;
+
+                self.update(Message::SaveFile)
+            }
+            Message::CancelClose => {
+                self.closing = None;
+                self.closing_all = false;
+
+                Command::none()
+            }
+            Message::CloseAllTabs | Message::ConfirmCloseAll => {
+                self.confirming_close_all = None;
+
+                if self.fragments.iter().any(|f| f.is_loading) {
+                    self.toast = Some(String::from(
+                        "Wait for the files being opened or saved first",
+                    ));
+
+                    return Command::none();
+                }
+
+                let count = self.fragments.len();
+
+                if let (Message::CloseAllTabs, Some(threshold)) =
+                    (&message, self.preferences.confirm_close_all)
+                {
+                    if count > threshold {
+                        self.confirming_close_all = Some(count);
+
+                        return Command::none();
+                    }
+                }
+
+                self.closing_all = true;
+
+                self.close_next_or_finish()
+            }
+            Message::CancelCloseAll => {
+                self.confirming_close_all = None;
+
+                Command::none()
+            }
+            Message::TabNew => {
+                self.welcome = false;
+                self.open_tab(FragmentContent::default());
+                Command::none()
+            }
+            Message::ToggleReadOnly => {
+                fragment.read_only = !fragment.read_only;
+
+                Command::none()
+            }
+            Message::SelectAll => {
+                fragment.content.perform(text_editor::Action::Move(
+                    text_editor::Motion::DocumentStart,
+                ));
+                fragment.content.perform(text_editor::Action::Select(
+                    text_editor::Motion::DocumentEnd,
+                ));
+
+                Command::none()
+            }
+            // The editor handles these shortcuts itself, so the buttons go
+            // through its actions the same way
+            Message::Cut | Message::Copy => {
+                let Some(selection) = fragment.content.selection() else {
+                    return Command::none();
+                };
+
+                let copy = clipboard::write(selection);
+
+                if let Message::Copy = message {
+                    return copy;
+                }
+
+                Command::batch([
+                    copy,
+                    self.update(Message::ActionPerformed(
+                        text_editor::Action::Edit(text_editor::Edit::Delete),
+                    )),
+                ])
+            }
+            Message::Paste => clipboard::read(Message::Pasted),
+            Message::Pasted(contents) => match contents {
+                Some(contents) => self.update(Message::ActionPerformed(
+                    text_editor::Action::Edit(text_editor::Edit::Paste(
+                        Arc::new(contents),
+                    )),
+                )),
+                None => Command::none(),
+            },
+            Message::DuplicateTab => {
+                // The text of a file being loaded is not there to copy yet
+                if fragment.is_loading {
+                    return Command::none();
+                }
+
+                let name = fragment
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| fragment.name().to_owned());
+                let language =
+                    fragment.language(&self.preferences.new_file_language);
+                let (line, column) = fragment.content.cursor_position();
+
+                let mut copy = FragmentContent {
+                    content: text_editor::Content::with_text(&editing::text(
+                        &fragment.content,
+                    )),
+                    is_dirty: true,
+                    view_mode: fragment.view_mode,
+                    hex_dump: fragment.hex_dump.clone(),
+                    final_newline: fragment.final_newline,
+                    line_ending: fragment.line_ending,
+                    indent: fragment.indent,
+                    encoding: fragment.encoding,
+                    language_override: language,
+                    title: Some(format!("Copy of {name}")),
+                    ..FragmentContent::default()
+                };
+
+                let position = (line + 1, column + 1);
+                let caret =
+                    select_after_layout(&mut copy.content, position, position);
+
+                self.welcome = false;
+                self.open_tab(copy);
+
+                caret
+            }
+            Message::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+
+                Command::none()
+            }
+            Message::CopyLineUp => {
+                fragment.remember_undo(history::Kind::Other);
+                let (anchor, cursor) =
+                    editing::copy_lines(&mut fragment.content, Direction::Up);
+                fragment.is_dirty = true;
+
+                select_after_layout(&mut fragment.content, anchor, cursor)
+            }
+            Message::CopyLineDown => {
+                fragment.remember_undo(history::Kind::Other);
+                let (anchor, cursor) = editing::copy_lines(
+                    &mut fragment.content,
+                    Direction::Down,
+                );
+                fragment.is_dirty = true;
+
+                select_after_layout(&mut fragment.content, anchor, cursor)
+            }
+            Message::DuplicateSelection => {
+                let Some((anchor, cursor)) =
+                    editing::selection(&fragment.content)
+                else {
+                    return self.update(Message::CopyLineDown);
+                };
+                let selected =
+                    fragment.content.selection().unwrap_or_default();
+                let end = anchor.max(cursor);
+
+                fragment.remember_undo(history::Kind::Other);
+                // The copy goes right after the selection and takes it over
+                let (start, end) = editing::replace(
+                    &mut fragment.content,
+                    end,
+                    end,
+                    &selected,
+                );
+                fragment.is_dirty = true;
+
+                select_after_layout(&mut fragment.content, start, end)
+            }
+            Message::Select(anchor, cursor) => {
+                editing::select(&mut fragment.content, anchor, cursor);
+
+                self.reveal_cursor()
+            }
+            Message::TrailingNewlineSelected(trailing_newline) => {
+                self.preferences.trailing_newline = trailing_newline;
+
+                write_preference("trailing_newline", trailing_newline.key())
+            }
+            Message::SaveAsIsToggled(save_as_is) => {
+                fragment.save_as_is = save_as_is;
+
+                self.remember(|settings| settings.save_as_is = save_as_is)
+            }
+            Message::LineEndingSelected(line_ending) => {
+                let converted = fragment.convert_line_endings(line_ending);
+
+                if converted > 0 {
+                    self.toast = Some(format!(
+                        "Converted {converted} line endings to {line_ending}"
+                    ));
+                }
+
+                self.remember(|settings| {
+                    settings.line_ending = Some(line_ending);
+                })
+            }
+            Message::LineEndingForAllTabs(line_ending) => {
+                let mut converted = 0;
+
+                for fragment in self.fragments.iter_mut().filter(|fragment| {
+                    !fragment.refuses_edits() && !fragment.is_loading
+                }) {
+                    if fragment.convert_line_endings(line_ending) > 0 {
+                        converted += 1;
+                    }
+
+                    if let Some(file) = &fragment.file {
+                        self.file_settings.update(file, |settings| {
+                            settings.line_ending = Some(line_ending);
+                        });
+                    }
+                }
+
+                self.toast = Some(match converted {
+                    0 => format!(
+                        "Every tab already ends lines with {line_ending}"
+                    ),
+                    1 => format!("Converted 1 tab to {line_ending}"),
+                    tabs => format!("Converted {tabs} tabs to {line_ending}"),
+                });
+
+                match file_settings::Store::path() {
+                    Some(path) => Command::perform(
+                        write_config_file(path, self.file_settings.to_text()),
+                        Message::FileSettingsWritten,
+                    ),
+                    None => Command::none(),
+                }
+            }
+            Message::LanguageSelected(language) => {
+                fragment.language_override = language;
+
+                self.remember(|settings| settings.language = language)
+            }
+            Message::EncodingSelected(encoding) => {
+                // An unchanged file is read again in the new encoding, while
+                // changes are kept and will be saved in it
+                let path = fragment.file.clone().filter(|_| {
+                    !fragment.is_dirty && !fragment.is_loading
+                });
+
+                fragment.encoding = encoding;
+                fragment.is_loading = fragment.is_loading || path.is_some();
+
+                let id = fragment.id;
+
+                let remember = self.remember(|settings| {
+                    settings.encoding = Some(encoding);
+                });
+
+                let Some(path) = path else {
+                    return remember;
+                };
+
+                Command::batch([
+                    remember,
+                    self.in_flight(
+                        1,
+                        load_file_as(path, Some(encoding)),
+                        move |result| Message::FileOpened(id, result),
+                    ),
+                ])
+            }
+            Message::ViewModeSelected(view_mode) => {
+                fragment.view_mode = view_mode;
+                fragment.hex_dump = match view_mode {
+                    ViewMode::Text => String::new(),
+                    ViewMode::Hex => view_mode::hex_dump(
+                        editing::text(&fragment.content).as_bytes(),
+                    ),
+                };
+
+                self.remember(|settings| settings.view_mode = Some(view_mode))
+            }
+            Message::IndentSelected(indent) => {
+                fragment.indent = indent;
+
+                self.remember(|settings| settings.indent = Some(indent))
+            }
+            Message::FileSettingsWritten(result) => {
+                if let Err(Error::IoError(kind)) = result {
+                    self.toast = Some(format!(
+                        "Could not remember the file settings: {kind}"
+                    ));
+                }
+
+                Command::none()
+            }
+            Message::ThemeWritten(result) => {
+                if let Err(Error::IoError(kind)) = result {
+                    self.toast =
+                        Some(format!("Could not remember the theme: {kind}"));
+                }
+
+                Command::none()
+            }
+            // The window tells its size and position in zoomed units, while
+            // it is opened at them unzoomed
+            Message::WindowResized(width, height) => {
+                let zoom = self.scale_factor() as f32;
+
+                self.geometry.resize(width as f32 * zoom, height as f32 * zoom);
+                self.geometry_changed = Some(Instant::now());
+
+                Command::none()
+            }
+            Message::WindowMoved(x, y) => {
+                let zoom = self.scale_factor();
+                let unzoom = |value: i32| (f64::from(value) * zoom).round();
+
+                self.geometry.move_to(unzoom(x) as i32, unzoom(y) as i32);
+                self.geometry_changed = Some(Instant::now());
+
+                Command::none()
+            }
+            Message::GeometryTick => {
+                // Dragging the window moves it many times a second
+                let settled = self.geometry_changed.is_some_and(|changed| {
+                    changed.elapsed() >= GEOMETRY_DEBOUNCE
+                });
+
+                match (settled, Geometry::path()) {
+                    (true, Some(path)) => {
+                        self.geometry_changed = None;
+
+                        Command::perform(
+                            write_config_file(path, self.geometry.to_file()),
+                            Message::GeometryWritten,
+                        )
+                    }
+                    _ => Command::none(),
+                }
+            }
+            Message::GeometryWritten(result) => {
+                if let Err(Error::IoError(kind)) = result {
+                    self.toast = Some(format!(
+                        "Could not remember the window size: {kind}"
+                    ));
+                }
+
+                Command::none()
+            }
+            Message::SessionWritten(result) => {
+                if let Err(Error::IoError(kind)) = result {
+                    self.toast = Some(format!(
+                        "Could not remember the open tabs: {kind}"
+                    ));
+                }
+
+                let outdated = self.session_write == Rewrite::Outdated;
+                self.session_write = Rewrite::Done;
+
+                if outdated {
+                    self.remember_session()
+                } else {
+                    Command::none()
+                }
+            }
+            Message::UndoStoreWritten(result) => {
+                if let Err(Error::IoError(kind)) = result {
+                    self.toast = Some(format!(
+                        "Could not remember the undo history: {kind}"
+                    ));
+                }
+
+                let outdated = self.undo_store_write == Rewrite::Outdated;
+                self.undo_store_write = Rewrite::Done;
+
+                if outdated {
+                    self.write_undo_store()
+                } else {
+                    Command::none()
+                }
+            }
+            Message::RecentWritten(result) => {
+                if let Err(Error::IoError(kind)) = result {
+                    self.toast = Some(format!(
+                        "Could not remember the recent files: {kind}"
+                    ));
+                }
+
+                let outdated = self.recent_write == Rewrite::Outdated;
+                self.recent_write = Rewrite::Done;
+
+                if outdated {
+                    self.write_recent()
+                } else {
+                    Command::none()
+                }
+            }
+            Message::InsertTab => {
+                if let Some(completion) = &self.completion {
+                    return self
+                        .update(Message::CompletePath(completion.selected));
+                }
+
+                // The editor leaves the Tab key alone, so it is bound here
+                let (line, column) = fragment.content.cursor_position();
+                let column = fragment
+                    .content
+                    .line(line)
+                    .map_or(0, |line| line[..column].chars().count());
+                let tab = fragment.indent.tab(column);
+
+                self.update(Message::ActionPerformed(text_editor::Action::Edit(
+                    text_editor::Edit::Paste(Arc::new(tab)),
+                )))
+            }
+            Message::DedentLines => {
+                let selection = editing::selection(&fragment.content);
+                let (line, column) = fragment.content.cursor_position();
+                let range = match selection {
+                    Some(_) => editing::selected_lines(&fragment.content),
+                    None => line..line + 1,
+                };
+                let lines: Vec<_> = range
+                    .clone()
+                    .filter_map(|line| fragment.content.line(line))
+                    .map(|line| line.to_string())
+                    .collect();
+                let dedented: Vec<_> = lines
+                    .iter()
+                    .map(|line| fragment.indent.dedent(line).to_owned())
+                    .collect();
+
+                if dedented == lines {
+                    return Command::none();
+                }
+
+                fragment.remember_undo(history::Kind::Other);
+                let (start, end) = editing::replace_lines(
+                    &mut fragment.content,
+                    range,
+                    &dedented,
+                );
+                fragment.is_dirty = true;
+
+                // The caret stays on its character when nothing is selected
+                let (start, end) = match selection {
+                    Some(_) => (start, end),
+                    None => {
+                        let removed = lines[0].len() - dedented[0].len();
+                        let caret = (line, column.saturating_sub(removed));
+
+                        (caret, caret)
+                    }
+                };
+
+                select_after_layout(&mut fragment.content, start, end)
+            }
+            Message::InsertUuid
+            | Message::InsertToken
+            | Message::InsertBase64Token => {
+                let length = self.preferences.token_length;
+                let inserted = match message {
+                    Message::InsertUuid => token::uuid_v4(),
+                    Message::InsertToken => token::hex_token(length),
+                    _ => token::base64_token(length),
+                };
+
+                self.update(Message::ActionPerformed(text_editor::Action::Edit(
+                    text_editor::Edit::Paste(Arc::new(inserted)),
+                )))
+            }
+            Message::ReindentPasteToggled(reindent_paste) => {
+                self.preferences.reindent_paste = reindent_paste;
+
+                write_preference("reindent_paste", &reindent_paste.to_string())
+            }
+            Message::AutoSaveToggled(auto_save) => {
+                self.preferences.auto_save = auto_save;
+
+                write_preference("auto_save", &auto_save.to_string())
+            }
+            Message::LineNumbersToggled(show_line_numbers) => {
+                self.preferences.show_line_numbers = show_line_numbers;
+
+                write_preference(
+                    "show_line_numbers",
+                    &show_line_numbers.to_string(),
+                )
+            }
+            Message::HighlightAnyway => {
+                self.fragments[self.fragment_index].highlight_anyway = true;
+
+                Command::none()
+            }
+            Message::AutoSaveTick => {
+                // Prompts and Save All are left to finish their own saves
+                if self.last_edit.elapsed() < self.preferences.auto_save_delay
+                    || self.exiting
+                    || self.closing_all
+                    || self.closing.is_some()
+                {
+                    return Command::none();
+                }
+
+                let saves: Vec<usize> = self
+                    .fragments
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, fragment)| {
+                        fragment.is_dirty
+                            && !fragment.is_loading
+                            && !fragment.read_only
+                            && fragment.file.as_deref().is_some_and(|path| {
+                                self.preferences.auto_saves(path)
+                            })
+                    })
+                    .map(|(index, _)| index)
+                    .collect();
+
+                if saves.is_empty() {
+                    return Command::none();
+                }
+
+                // A failing save waits for another pause before trying again
+                self.last_edit = Instant::now();
+
+                Command::batch(
+                    saves.into_iter().map(|index| self.save(index, false)),
+                )
+            }
+            Message::PreferencesWritten(result) => {
+                if let Err(Error::IoError(kind)) = result {
+                    self.toast = Some(format!(
+                        "Could not remember the preference: {kind}"
+                    ));
+                }
+
+                Command::none()
+            }
+            Message::RevealWhitespaceToggled(reveal_whitespace) => {
+                self.reveal_whitespace = reveal_whitespace;
+
+                for fragment in &mut self.fragments {
+                    if fragment.diff_of.is_none() {
+                        continue;
+                    }
+
+                    let shown = if reveal_whitespace {
+                        diff::reveal_whitespace(&fragment.diff_text)
+                    } else {
+                        fragment.diff_text.clone()
+                    };
+
+                    fragment.content = text_editor::Content::with_text(&shown);
+                }
+
+                Command::none()
+            }
+            Message::GoToFileUnderCursor => {
+                let (line, column) = fragment.content.cursor_position();
+                let candidate = fragment.content.line(line).and_then(|line| {
+                    editing::location_at(&line, column)
+                        .map(|(path, location)| (PathBuf::from(path), location))
+                });
+
+                let Some((candidate, location)) = candidate else {
+                    return Command::none();
+                };
+
+                // Compilers print paths relative to where they were run
+                let path = match fragment.file.as_deref().and_then(Path::parent)
+                {
+                    Some(directory)
+                        if candidate.is_relative()
+                            && !(location.is_some() && candidate.is_file()) =>
+                    {
+                        directory.join(&candidate)
+                    }
+                    _ => candidate.clone(),
+                };
+
+                if !path.is_file() {
+                    self.toast = Some(format!(
+                        "Could not find {}",
+                        candidate.display()
+                    ));
+
+                    return Command::none();
+                }
+
+                if let Some(index) = self
+                    .fragments
+                    .iter()
+                    .position(|fragment| fragment.file.as_ref() == Some(&path))
+                {
+                    self.activate(index);
+
+                    let Some(location) = location else {
+                        return Command::none();
+                    };
+
+                    let content = &mut self.fragments[index].content;
+                    let position = editing::clamp(content, location);
+
+                    return select_after_layout(content, position, position);
+                }
+
+                self.open_tab(FragmentContent {
+                    is_loading: true,
+                    pending_location: location,
+                    ..FragmentContent::default()
+                });
+
+                self.load(path)
+            }
+            Message::DismissToast => {
+                self.toast = None;
+
+                Command::none()
+            }
+            Message::IconFontLoaded(result) => {
+                self.has_icon_font = result.is_ok();
+
+                Command::none()
+            }
+            Message::ToggleBlockComment => {
+                let Some((open, close)) = fragment
+                    .language(&self.preferences.new_file_language)
+                    .and_then(|language| language.block_comment)
+                else {
+                    return Command::none();
+                };
+
+                let (start, end) = match editing::selection(&fragment.content)
+                {
+                    Some((anchor, cursor)) => {
+                        (anchor.min(cursor), anchor.max(cursor))
+                    }
+                    None => {
+                        let (line, _) = fragment.content.cursor_position();
+                        let length = fragment
+                            .content
+                            .line(line)
+                            .map_or(0, |line| line.len());
+
+                        ((line, 0), (line, length))
+                    }
+                };
+
+                let text = editing::text(&fragment.content);
+                let selected = &text[editing::to_offset(&text, start)
+                    ..editing::to_offset(&text, end)];
+
+                let replacement =
+                    editing::toggle_block_comment(selected, open, close);
+
+                fragment.remember_undo(history::Kind::Other);
+
+                let (start, end) = editing::replace(
+                    &mut fragment.content,
+                    start,
+                    end,
+                    &replacement,
+                );
+                fragment.is_dirty = true;
+
+                select_after_layout(&mut fragment.content, start, end)
+            }
+            Message::StartRecording => {
+                self.recording = Some(Vec::new());
+
+                Command::none()
+            }
+            Message::StopRecording => {
+                if let Some(recording) = self.recording.take() {
+                    self.recorded_macro = recording;
+                }
+
+                Command::none()
+            }
+            Message::ToggleRecording => {
+                if self.recording.is_some() {
+                    self.update(Message::StopRecording)
+                } else {
+                    self.update(Message::StartRecording)
+                }
+            }
+            Message::PlayMacro => {
+                if self.recording.is_some() || !self.playback.is_empty() {
+                    return Command::none();
+                }
+
+                let repeat = self
+                    .macro_repeat
+                    .parse()
+                    .unwrap_or(1)
+                    .min(MAX_MACRO_REPEAT);
+
+                for _ in 0..repeat {
+                    self.playback.extend(self.recorded_macro.iter().cloned());
+                }
+
+                // Switching tabs while it plays leaves it where it started
+                self.playback_fragment = Some(fragment.id);
+
+                // The whole playback is undone at once
+                fragment.remember_undo(history::Kind::Other);
+
+                after_layout(Message::PlaybackStep)
+            }
+            Message::PlaybackStep => {
+                // Actions are replayed as-is, bypassing the modifier-based
+                // shortcuts resolved in `ActionPerformed`, and one at a time
+                // so that every motion runs over laid out lines
+                let Some(action) = self.playback.pop_front() else {
+                    return Command::none();
+                };
+                let index =
+                    self.playback_fragment.and_then(|id| self.position(id));
+
+                // The rest is dropped when its tab is closed partway through
+                let Some(index) = index else {
+                    self.playback.clear();
+
+                    return Command::none();
+                };
+                let fragment = &mut self.fragments[index];
+                let is_edit = action.is_edit();
+
+                // The tab may have been made read-only since it started
+                if is_edit && fragment.refuses_edits() {
+                    self.toast = Some(format!(
+                        "{} is read-only, so the macro stopped",
+                        fragment.name()
+                    ));
+                    self.playback.clear();
+
+                    return Command::none();
+                }
+
+                fragment.content.perform(action);
+
+                if is_edit {
+                    fragment.is_dirty = true;
+                    fragment.record_edit();
+                    self.last_edit = Instant::now();
+                }
+
+                if self.playback.is_empty() {
+                    Command::none()
+                } else {
+                    after_layout(Message::PlaybackStep)
+                }
+            }
+            Message::MacroRepeatChanged(repeat) => {
+                if repeat.chars().all(|c| c.is_ascii_digit()) {
+                    let too_many = !repeat.is_empty()
+                        && repeat.parse().map_or(true, |repeat: usize| {
+                            repeat > MAX_MACRO_REPEAT
+                        });
+
+                    self.macro_repeat = if too_many {
+                        MAX_MACRO_REPEAT.to_string()
+                    } else {
+                        repeat
+                    };
+                }
+
+                Command::none()
+            }
+            Message::KeyPressed(key, modifiers) => {
+                // The palettes are browsed with the arrows, past the keymap
+                if self.command_palette.is_some() {
+                    match key.as_ref() {
+                        Key::Named(Named::ArrowUp) => {
+                            return self.update(Message::SelectCommand(-1));
+                        }
+                        Key::Named(Named::ArrowDown) => {
+                            return self.update(Message::SelectCommand(1));
+                        }
+                        _ => {}
+                    }
+                }
+
+                if self.theme_palette.is_some() {
+                    match key.as_ref() {
+                        Key::Named(Named::ArrowUp) => {
+                            return self.update(Message::PreviewTheme(-1));
+                        }
+                        Key::Named(Named::ArrowDown) => {
+                            return self.update(Message::PreviewTheme(1));
+                        }
+                        Key::Named(Named::Enter) => {
+                            return self
+                                .update(Message::ThemeSelected(self.theme));
+                        }
+                        _ => {}
+                    }
+                }
+
+                match self.keymap.resolve(&key, modifiers) {
+                    // Text fields and an unfocused editor pass Tab on too
+                    Some(
+                        message @ (Message::InsertTab | Message::DedentLines),
+                    ) => {
+                        if !self.editor_focused || self.has_overlay() {
+                            return Command::none();
+                        }
+
+                        Command::widget(focus::unless_field_focused(message))
+                    }
+                    Some(message) => self.update(message),
+                    None => Command::none(),
+                }
+            }
+            Message::TrimSelection => {
+                if let Some((anchor, cursor)) =
+                    editing::trimmed_selection(&fragment.content)
+                {
+                    editing::select(&mut fragment.content, anchor, cursor);
+                }
+
+                Command::none()
+            }
+            Message::ExpandSelectionToLines => {
+                let (anchor, cursor) =
+                    editing::line_selection(&fragment.content);
+
+                editing::select(&mut fragment.content, anchor, cursor);
+
+                Command::none()
+            }
+            Message::SelectToMatchingBracket => {
+                match editing::bracket_selection(&fragment.content) {
+                    Some((anchor, cursor)) => select_after_layout(
+                        &mut fragment.content,
+                        anchor,
+                        cursor,
+                    ),
+                    None => Command::none(),
+                }
+            }
+            Message::JumpToMatchingBracket => {
+                match editing::matching_bracket_position(&fragment.content) {
+                    Some(position) => select_after_layout(
+                        &mut fragment.content,
+                        position,
+                        position,
+                    ),
+                    None => Command::none(),
+                }
+            }
+            Message::SortLines
+            | Message::HardWrap
+            | Message::ReflowParagraph => {
+                let has_selection =
+                    editing::selection(&fragment.content).is_some();
+
+                // Sorting the whole file is rarely what was meant
+                if let (Message::SortLines, false) = (&message, has_selection)
+                {
+                    return Command::none();
+                }
+
+                let range = match message {
+                    Message::ReflowParagraph if !has_selection => {
+                        editing::paragraph(
+                            &fragment.content,
+                            fragment.content.cursor_position().0,
+                        )
+                    }
+                    _ => editing::selected_lines(&fragment.content),
+                };
+                let lines: Vec<_> = range
+                    .clone()
+                    .filter_map(|line| fragment.content.line(line))
+                    .map(|line| line.to_string())
+                    .collect();
+
+                let column = self.preferences.wrap_column;
+                let replacement = match message {
+                    Message::SortLines => editing::sort_lines(&lines),
+                    Message::HardWrap => editing::hard_wrap(&lines, column),
+                    _ => editing::reflow(&lines, column),
+                };
+
+                if replacement == lines {
+                    return Command::none();
+                }
+
+                fragment.remember_undo(history::Kind::Other);
+                let (start, end) = editing::replace_lines(
+                    &mut fragment.content,
+                    range,
+                    &replacement,
+                );
+                fragment.is_dirty = true;
+
+                select_after_layout(&mut fragment.content, start, end)
+            }
+            Message::CollapseBlankLines => {
+                let has_selection = fragment.content.selection().is_some();
+                let range = editing::selected_lines(&fragment.content);
+                let lines: Vec<_> = range
+                    .clone()
+                    .filter_map(|line| fragment.content.line(line))
+                    .map(|line| line.to_string())
+                    .collect();
+
+                let (collapsed, removed) = editing::collapse_blank_lines(
+                    &lines,
+                    self.preferences.max_blank_lines,
+                    self.preferences.trim_blank_edges,
+                );
+
+                if removed == 0 {
+                    self.toast = Some(String::from(
+                        "There are no blank lines to remove",
+                    ));
+
+                    return Command::none();
+                }
+
+                fragment.remember_undo(history::Kind::Other);
+                let (line, column) = fragment.content.cursor_position();
+                let (start, end) = editing::replace_lines(
+                    &mut fragment.content,
+                    range,
+                    &collapsed,
+                );
+                fragment.is_dirty = true;
+                self.toast = Some(match removed {
+                    1 => String::from("Removed 1 blank line"),
+                    removed => format!("Removed {removed} blank lines"),
+                });
+
+                if has_selection {
+                    select_after_layout(&mut fragment.content, start, end)
+                } else {
+                    // The caret stays about where it was
+                    let position = editing::clamp(
+                        &fragment.content,
+                        (line + 1, column + 1),
+                    );
+
+                    select_after_layout(
+                        &mut fragment.content,
+                        position,
+                        position,
+                    )
+                }
+            }
+            Message::SortImports => {
+                let language =
+                    fragment.language(&self.preferences.new_file_language);
+                let Some(language) =
+                    language.filter(|language| !language.imports.is_empty())
+                else {
+                    self.toast = Some(String::from(
+                        "Imports can only be sorted in a known language",
+                    ));
+
+                    return Command::none();
+                };
+
+                let lines: Vec<String> = fragment
+                    .content
+                    .lines()
+                    .map(|line| line.to_string())
+                    .collect();
+                let (line, _) = fragment.content.cursor_position();
+
+                let Some(sorted) =
+                    imports::sort(&lines, language.imports, line)
+                else {
+                    self.toast = Some(format!(
+                        "There are no {language} imports to sort"
+                    ));
+
+                    return Command::none();
+                };
+
+                if lines[sorted.range.clone()] == sorted.lines[..] {
+                    self.toast = Some(String::from("The imports are sorted"));
+
+                    return Command::none();
+                }
+
+                fragment.remember_undo(history::Kind::Other);
+                let (start, _) = editing::replace_lines(
+                    &mut fragment.content,
+                    sorted.range,
+                    &sorted.lines,
+                );
+                fragment.is_dirty = true;
+
+                self.toast = Some(match sorted.removed {
+                    0 => format!("Sorted {} imports", sorted.count),
+                    removed => format!(
+                        "Sorted {} imports and removed {removed} repeated",
+                        sorted.count
+                    ),
+                });
+
+                select_after_layout(&mut fragment.content, start, start)
+            }
+            Message::StripAnsi | Message::StripInvisibles => {
+                let is_ansi = matches!(message, Message::StripAnsi);
+                let strip = if is_ansi {
+                    editing::strip_ansi
+                } else {
+                    editing::strip_invisibles
+                };
+                let what = if is_ansi {
+                    "escape codes"
+                } else {
+                    "invisible characters"
+                };
+
+                let text = editing::text(&fragment.content);
+                let selection =
+                    editing::selection(&fragment.content).map(
+                        |(anchor, cursor)| {
+                            (anchor.min(cursor), anchor.max(cursor))
+                        },
+                    );
+
+                // Without a selection, the whole buffer is cleaned up
+                let (start, end) = selection.unwrap_or_else(|| {
+                    ((0, 0), editing::to_position(&text, text.len()))
+                });
+                let (clean, removed) = strip(
+                    &text[editing::to_offset(&text, start)
+                        ..editing::to_offset(&text, end)],
+                );
+
+                if removed == 0 {
+                    self.toast = Some(format!("There are no {what} to strip"));
+
+                    return Command::none();
+                }
+
+                fragment.remember_undo(history::Kind::Other);
+                let (start, end) =
+                    editing::replace(&mut fragment.content, start, end, &clean);
+                fragment.is_dirty = true;
+                self.toast = Some(format!("Stripped {removed} {what}"));
+
+                if selection.is_some() {
+                    select_after_layout(&mut fragment.content, start, end)
+                } else {
+                    select_after_layout(&mut fragment.content, start, start)
+                }
+            }
+            Message::NormalizeUnicode(form) => {
+                let text = editing::text(&fragment.content);
+                let selection =
+                    editing::selection(&fragment.content).map(
+                        |(anchor, cursor)| {
+                            (anchor.min(cursor), anchor.max(cursor))
+                        },
+                    );
+
+                // Without a selection, the whole buffer is normalized
+                let (start, end) = selection.unwrap_or_else(|| {
+                    ((0, 0), editing::to_position(&text, text.len()))
+                });
+                let original = &text[editing::to_offset(&text, start)
+                    ..editing::to_offset(&text, end)];
+                let normalized = editing::normalize(original, form);
+
+                if normalized == original {
+                    self.toast = Some(format!("The text is already in {form}"));
+
+                    return Command::none();
+                }
+
+                fragment.remember_undo(history::Kind::Other);
+                let (start, end) = editing::replace(
+                    &mut fragment.content,
+                    start,
+                    end,
+                    &normalized,
+                );
+                fragment.is_dirty = true;
+                self.toast = Some(format!("Normalized the text to {form}"));
+
+                if selection.is_some() {
+                    select_after_layout(&mut fragment.content, start, end)
+                } else {
+                    select_after_layout(&mut fragment.content, start, start)
+                }
+            }
+            Message::ChangeCase(case) => {
+                let text = editing::text(&fragment.content);
+                let selection =
+                    editing::selection(&fragment.content).map(
+                        |(anchor, cursor)| {
+                            (anchor.min(cursor), anchor.max(cursor))
+                        },
+                    );
+
+                // Without a selection, the line of the caret is changed
+                let (start, end) = selection.unwrap_or_else(|| {
+                    let (line, _) = fragment.content.cursor_position();
+                    let length = text.split('\n').nth(line).map_or(0, str::len);
+
+                    ((line, 0), (line, length))
+                });
+                let original = &text[editing::to_offset(&text, start)
+                    ..editing::to_offset(&text, end)];
+                let changed = editing::change_case(original, case);
+
+                if changed == original {
+                    self.toast = Some(format!("The text is already in {case}"));
+
+                    return Command::none();
+                }
+
+                fragment.remember_undo(history::Kind::Other);
+                let (line, column) = fragment.content.cursor_position();
+                let (start, end) = editing::replace(
+                    &mut fragment.content,
+                    start,
+                    end,
+                    &changed,
+                );
+                fragment.is_dirty = true;
+
+                if selection.is_some() {
+                    select_after_layout(&mut fragment.content, start, end)
+                } else {
+                    // The caret stays where it was on the line
+                    let caret = editing::clamp(
+                        &fragment.content,
+                        (line + 1, column + 1),
+                    );
+
+                    select_after_layout(&mut fragment.content, caret, caret)
+                }
+            }
+            Message::ConvertTo(target) => {
+                let text = editing::text(&fragment.content);
+                let selection =
+                    editing::selection(&fragment.content).map(
+                        |(anchor, cursor)| {
+                            (anchor.min(cursor), anchor.max(cursor))
+                        },
+                    );
+
+                // Without a selection, the whole buffer is converted
+                let (start, end) = selection.unwrap_or_else(|| {
+                    ((0, 0), editing::to_position(&text, text.len()))
+                });
+                let original = &text[editing::to_offset(&text, start)
+                    ..editing::to_offset(&text, end)];
+
+                // The format of the file comes first, then any that reads it
+                let own = fragment
+                    .language(&self.preferences.new_file_language)
+                    .and_then(|language| {
+                        convert::Format::of_language(language.name)
+                    });
+                let mut sources = own
+                    .into_iter()
+                    .chain(convert::Format::ALL)
+                    .filter(|format| *format != target);
+                let first = sources.next().unwrap_or(target);
+
+                let parsed = first.parse(original).map(|value| (first, value));
+                let parsed = sources.fold(parsed, |parsed, format| {
+                    parsed.or_else(|error| {
+                        format
+                            .parse(original)
+                            .map(|value| (format, value))
+                            .map_err(|_| error)
+                    })
+                });
+
+                let converted = parsed.and_then(|(source, value)| {
+                    Ok((source, target.write(&value)?))
+                });
+
+                let (source, converted) = match converted {
+                    Ok(converted) => converted,
+                    Err(error) => {
+                        self.toast = Some(format!(
+                            "Could not convert to {target}: {error}"
+                        ));
+
+                        return Command::none();
+                    }
+                };
+                let converted = converted.trim_end_matches('\n');
+
+                fragment.remember_undo(history::Kind::Other);
+                let (start, end) = editing::replace(
+                    &mut fragment.content,
+                    start,
+                    end,
+                    converted,
+                );
+                fragment.is_dirty = true;
+                self.toast = Some(format!("Converted {source} to {target}"));
+
+                if selection.is_some() {
+                    select_after_layout(&mut fragment.content, start, end)
+                } else {
+                    select_after_layout(&mut fragment.content, start, start)
+                }
+            }
+            Message::SelectNextInvisible => {
+                let text = editing::text(&fragment.content);
+                let cursor = editing::to_offset(
+                    &text,
+                    fragment.content.cursor_position(),
+                );
+                let invisibles = editing::invisibles(&text);
+
+                let Some((offset, _)) = invisibles
+                    .iter()
+                    .find(|(offset, _)| *offset >= cursor)
+                    .or(invisibles.first())
+                else {
+                    return Command::none();
+                };
+
+                let length =
+                    text[*offset..].chars().next().map_or(0, char::len_utf8);
+
+                select_after_layout(
+                    &mut fragment.content,
+                    editing::to_position(&text, *offset),
+                    editing::to_position(&text, offset + length),
+                )
+            }
+            Message::TransposeChars | Message::TransposeWords => {
+                let (line, column) = fragment.content.cursor_position();
+                let transpose = if let Message::TransposeChars = message {
+                    editing::transpose_chars
+                } else {
+                    editing::transpose_words
+                };
+
+                let Some((range, replacement)) = fragment
+                    .content
+                    .line(line)
+                    .and_then(|text| transpose(&text, column))
+                else {
+                    return Command::none();
+                };
+
+                fragment.remember_undo(history::Kind::Other);
+                // Pasting leaves the cursor after both, as in Emacs
+                editing::replace(
+                    &mut fragment.content,
+                    (line, range.start),
+                    (line, range.end),
+                    &replacement,
+                );
+                fragment.is_dirty = true;
+
+                Command::none()
+            }
+            Message::OpenWith(name) => {
+                let Some(tool) =
+                    self.preferences.tools.iter().find(|tool| tool.name == name)
+                else {
+                    return Command::none();
+                };
+
+                let Some(file) = &fragment.file else {
+                    self.toast = Some(format!(
+                        "Save the file before opening it with {name}"
+                    ));
+
+                    return Command::none();
+                };
+
+                let (program, args) = tool.command_line(file);
+
+                Command::perform(run_tool(program, args), move |result| {
+                    Message::ToolExited(name.clone(), result)
+                })
+            }
+            Message::ToolExited(name, result) => {
+                match result {
+                    Ok(status) if status.success() => {}
+                    Ok(status) => {
+                        self.toast = Some(format!("{name} failed: {status}"));
+                    }
+                    Err(Error::IoError(kind)) => {
+                        self.toast =
+                            Some(format!("Could not run {name}: {kind}"));
+                    }
+                    Err(Error::DialogClosed) => {}
+                }
+
+                Command::none()
+            }
+            Message::ToggleBlame => {
+                self.preferences.show_blame = !self.preferences.show_blame;
+
+                for fragment in &mut self.fragments {
+                    fragment.blamed_line = None;
+                }
+
+                self.blame_current_line()
+            }
+            Message::BlameLoaded(path, line, blame) => {
+                if let Some(fragment) = self.fragments.iter_mut().find(|f| {
+                    f.file.as_ref() == Some(&path)
+                        && f.blamed_line == Some(line)
+                }) {
+                    fragment.blame = blame;
+                }
+
+                Command::none()
+            }
+            Message::RevertHunk => {
+                let line = fragment.content.cursor_position().0;
+
+                // Removed lines leave an empty range right where they were
+                let Some(hunk) = fragment.hunks().into_iter().find(|hunk| {
+                    let current = &hunk.current;
+
+                    current.contains(&line)
+                        || (current.is_empty() && current.start == line)
+                }) else {
+                    return Command::none();
+                };
+
+                let original = fragment.disk_lines.as_deref().unwrap_or(&[]);
+                let replacement = original[hunk.original].to_vec();
+
+                fragment.remember_undo(history::Kind::Other);
+                editing::replace_lines(
+                    &mut fragment.content,
+                    hunk.current,
+                    &replacement,
+                );
+
+                fragment.is_dirty = !fragment.hunks().is_empty();
+
+                Command::none()
+            }
+            Message::CompareWithDisk => {
+                let Some(path) = fragment.file.clone() else {
+                    self.toast =
+                        Some(String::from("The file is not saved on disk yet"));
+
+                    return Command::none();
+                };
+
+                self.in_flight(1, load_file(path.clone()), move |result| {
+                    Message::DiskCompared(path, result)
+                })
+            }
+            Message::DiskCompared(path, result) => {
+                let file = match result {
+                    Ok(file) => file,
+                    Err(Error::IoError(kind)) => {
+                        self.toast = Some(format!(
+                            "Could not read {}: {kind}",
+                            path.display()
+                        ));
+
+                        return Command::none();
+                    }
+                    Err(Error::DialogClosed) => return Command::none(),
+                };
+
+                let Some(fragment) = self
+                    .fragments
+                    .iter()
+                    .find(|fragment| fragment.file.as_ref() == Some(&path))
+                else {
+                    return Command::none();
+                };
+
+                let disk_lines: Vec<_> = file.contents.lines().collect();
+                let lines: Vec<_> = fragment
+                    .content
+                    .lines()
+                    .map(|line| line.to_string())
+                    .collect();
+                let hunks = diff::hunks(&disk_lines, &lines);
+
+                if hunks.is_empty() {
+                    self.toast = Some(String::from(
+                        "There are no changes from the file on disk",
+                    ));
+
+                    return Command::none();
+                }
+
+                let diff = diff::unified(&disk_lines, &lines, &hunks, 3);
+                let shown = if self.reveal_whitespace {
+                    diff::reveal_whitespace(&diff)
+                } else {
+                    diff.clone()
+                };
+
+                self.open_tab(FragmentContent {
+                    content: text_editor::Content::with_text(&shown),
+                    diff_of: Some(path),
+                    diff_text: diff,
+                    ..FragmentContent::default()
+                });
+
+                Command::none()
+            }
+            Message::OpenConfigFile => {
+                let Some(path) = Preferences::path() else {
+                    self.toast =
+                        Some(String::from("No config directory was found"));
+
+                    return Command::none();
+                };
+
+                if let Some(index) = self
+                    .fragments
+                    .iter()
+                    .position(|fragment| fragment.file.as_ref() == Some(&path))
+                {
+                    self.activate(index);
+
+                    return Command::none();
+                }
+
+                self.open_tab(FragmentContent {
+                    is_loading: true,
+                    ..FragmentContent::default()
+                });
+
+                let id = self.fragments[self.fragment_index].id;
+
+                self.in_flight(1, open_preferences(path), move |result| {
+                    Message::FileOpened(id, result)
+                })
+            }
+            Message::ApplyPreferences(apply) => {
+                if apply {
+                    self.preferences = Preferences::load();
+                }
+
+                Command::none()
+            }
+            Message::FileForwarded(path) => {
+                let focus = window::gain_focus(window::Id::MAIN);
+
+                if let Some(index) = self
+                    .fragments
+                    .iter()
+                    .position(|fragment| fragment.file.as_ref() == Some(&path))
+                {
+                    self.activate(index);
+
+                    return focus;
+                }
+
+                self.open_tab(FragmentContent {
+                    is_loading: true,
+                    ..FragmentContent::default()
+                });
+
+                Command::batch([focus, self.load(path)])
+            }
+            Message::FileDropped(path) => {
+                if path.is_dir() {
+                    self.toast = Some(format!(
+                        "Could not open {}, as it is a folder",
+                        path.display()
+                    ));
+
+                    return Command::none();
+                }
+
+                // Each dropped file comes on its own, and opens like a picked
+                // one so that loads finishing out of order find their tab
+                self.in_flight(1, load_files(vec![path]), Message::FilesOpened)
+            }
+            Message::ShowShortcuts => {
+                self.show_shortcuts = true;
+
+                Command::none()
+            }
+            Message::HideShortcuts => {
+                self.show_shortcuts = false;
+
+                Command::none()
+            }
+            Message::CheckDisk => {
+                let paths = self
+                    .fragments
+                    .iter()
+                    .filter(|fragment| {
+                        !fragment.is_loading && !fragment.is_saving
+                    })
+                    .filter_map(|fragment| fragment.file.clone())
+                    .collect();
+
+                Command::perform(
+                    modification_times(paths),
+                    Message::DiskChecked,
+                )
+            }
+            Message::DiskChecked(times) => {
+                let now = Instant::now();
+                let debounce = self.preferences.reload_debounce;
+                let mut changed = Vec::new();
+
+                for (path, modified) in times {
+                    let Some(modified) = modified else {
+                        continue;
+                    };
+
+                    for (index, fragment) in
+                        self.fragments.iter_mut().enumerate()
+                    {
+                        if fragment.file.as_ref() != Some(&path)
+                            || fragment.is_loading
+                            || fragment.is_saving
+                        {
+                            continue;
+                        }
+
+                        if fragment.modified.is_none_or(|m| m == modified) {
+                            fragment.modified = Some(modified);
+                            fragment.disk_change = None;
+                            continue;
+                        }
+
+                        // Writes keep pushing the time back, so a file being
+                        // written to is only reloaded once it settles
+                        match fragment.disk_change {
+                            Some((seen, since)) if seen == modified => {
+                                if now.duration_since(since) >= debounce {
+                                    fragment.modified = Some(modified);
+                                    fragment.disk_change = None;
+                                    changed.push(index);
+                                }
+                            }
+                            _ => fragment.disk_change = Some((modified, now)),
+                        }
+                    }
+                }
+
+                let commands: Vec<_> = changed
+                    .into_iter()
+                    .map(|index| self.update(Message::FileChangedOnDisk(index)))
+                    .collect();
+
+                Command::batch(commands)
+            }
+            Message::FileChangedOnDisk(index) => {
+                let Some(fragment) = self.fragments.get_mut(index) else {
+                    return Command::none();
+                };
+
+                if fragment.is_dirty {
+                    fragment.changed_on_disk = true;
+
+                    Command::none()
+                } else {
+                    self.update(Message::ReloadFromDisk(index))
+                }
+            }
+            Message::ReloadFile => {
+                if fragment.file.is_none() || fragment.is_loading {
+                    Command::none()
+                } else if fragment.is_dirty {
+                    self.reloading = Some(self.fragment_index);
+
+                    Command::none()
+                } else {
+                    self.update(Message::ReloadFromDisk(self.fragment_index))
+                }
+            }
+            Message::CancelReload => {
+                self.reloading = None;
+
+                Command::none()
+            }
+            Message::ReloadFromDisk(index) => {
+                self.reloading = None;
+
+                let Some(fragment) = self.fragments.get_mut(index) else {
+                    return Command::none();
+                };
+                let Some(path) = fragment.file.clone() else {
+                    return Command::none();
+                };
+
+                fragment.is_loading = true;
+                fragment.changed_on_disk = false;
+
+                let encoding = fragment.encoding;
+
+                self.in_flight(
+                    1,
+                    load_file_as(path.clone(), Some(encoding)),
+                    move |result| Message::FileReloaded(path, result),
+                )
+            }
+            Message::FileReloaded(path, result) => {
+                let Some(index) = self
+                    .fragments
+                    .iter()
+                    .position(|fragment| fragment.file.as_ref() == Some(&path))
+                else {
+                    return Command::none();
+                };
+                let fragment = &mut self.fragments[index];
+
+                fragment.is_loading = false;
+
+                let file = match result {
+                    Ok(file) => file,
+                    Err(Error::IoError(kind)) => {
+                        self.toast = Some(format!(
+                            "Could not reload {}: {kind}",
+                            fragment.name()
+                        ));
+
+                        return Command::none();
+                    }
+                    Err(Error::DialogClosed) => return Command::none(),
+                };
+
+                let (line, column) = fragment.content.cursor_position();
+
+                fragment.content =
+                    text_editor::Content::with_text(&file.contents);
+                fragment.is_dirty = false;
+                fragment.history = History::default();
+                fragment.modified = file.modified;
+                fragment.disk_size = file.size;
+                fragment.disk_change = None;
+                fragment.decode_errors = file.decode_errors;
+                fragment.likely_encoding = file.likely_encoding;
+                fragment.final_newline = file.contents.ends_with('\n');
+                (fragment.line_ending, fragment.stray_line_endings) =
+                    LineEnding::detect(&file.contents);
+                fragment.disk_lines =
+                    Some(file.contents.lines().map(str::to_owned).collect());
+                fragment.blamed_line = None;
+
+                if fragment.view_mode == ViewMode::Hex {
+                    fragment.hex_dump =
+                        view_mode::hex_dump(file.contents.as_bytes());
+                }
+
+                // Only the current fragment is laid out to select in
+                if index != self.fragment_index {
+                    return Command::none();
+                }
+
+                let position =
+                    editing::clamp(&fragment.content, (line + 1, column + 1));
+
+                Command::batch([
+                    select_after_layout(
+                        &mut fragment.content,
+                        position,
+                        position,
+                    ),
+                    self.blame_current_line(),
+                ])
+            }
+            Message::KeepMine(index) => {
+                if let Some(fragment) = self.fragments.get_mut(index) {
+                    fragment.changed_on_disk = false;
+                }
+
+                Command::none()
+            }
+            Message::ShowEditTimeline => {
+                self.show_edit_timeline = true;
+
+                Command::none()
+            }
+            Message::HideEditTimeline => {
+                self.show_edit_timeline = false;
+
+                Command::none()
+            }
+            Message::JumpToEdit(index) => {
+                self.show_edit_timeline = false;
+
+                let Some(&((line, column), _)) = fragment.edits.get(index)
+                else {
+                    return Command::none();
+                };
+
+                // Later edits may have removed the lines since
+                let position =
+                    editing::clamp(&fragment.content, (line + 1, column + 1));
+
+                select_after_layout(&mut fragment.content, position, position)
+            }
+            Message::ExportSelectionToFile => {
+                let Some(selection) = fragment.content.selection() else {
+                    self.toast = Some(String::from("Nothing is selected"));
+
+                    return Command::none();
+                };
+
+                Command::perform(
+                    save_file(None, selection.into_bytes(), false),
+                    Message::SelectionExported,
+                )
+            }
+            Message::ExportSelectionToClipboard => {
+                let Some(selection) = fragment.content.selection() else {
+                    self.toast = Some(String::from("Nothing is selected"));
+
+                    return Command::none();
+                };
+
+                self.toast =
+                    Some(String::from("Copied the selection to the clipboard"));
+
+                clipboard::write(selection)
+            }
+            Message::SelectionExported(result) => {
+                match result {
+                    Ok(path) => {
+                        self.toast = Some(format!(
+                            "Exported the selection to {}",
+                            path.display()
+                        ));
+                    }
+                    Err(Error::IoError(kind)) => {
+                        self.toast = Some(format!(
+                            "Could not export the selection: {kind}"
+                        ));
+                    }
+                    Err(Error::DialogClosed) => {}
+                }
+
+                Command::none()
+            }
+            Message::ExportSnippet => {
+                self.snippet_name = Some(String::new());
+                self.go_to = None;
+                self.surround = None;
+
+                text_input::focus(snippet_name_id())
+            }
+            Message::SnippetNameChanged(input) => {
+                self.snippet_name = Some(input);
+
+                Command::none()
+            }
+            Message::SnippetNameSubmitted => {
+                let input = self.snippet_name.take().unwrap_or_default();
+
+                let Some(name) = snippet::valid_name(&input) else {
+                    self.snippet_name = Some(input);
+                    self.toast = Some(String::from(
+                        "A snippet name cannot be blank or hold /, \\ or :",
+                    ));
+
+                    return Command::none();
+                };
+                let Some(directory) = self.preferences.snippets_directory()
+                else {
+                    self.toast = Some(String::from(
+                        "There is no folder to keep snippets in",
+                    ));
+
+                    return Command::none();
+                };
+
+                let default = &self.preferences.new_file_language;
+                let snippet = Snippet {
+                    name: name.to_owned(),
+                    language: fragment.language(default).map_or_else(
+                        || fragment.extension(default),
+                        |language| language.name.to_owned(),
+                    ),
+                    created: SystemTime::now(),
+                    text: fragment
+                        .content
+                        .selection()
+                        .unwrap_or_else(|| editing::text(&fragment.content)),
+                };
+                let path = snippet.path(&directory);
+
+                // Snippets are only replaced by hand
+                if path.exists() {
+                    self.snippet_name = Some(input);
+                    self.toast = Some(format!(
+                        "There already is a snippet named {}",
+                        snippet.name
+                    ));
+
+                    return Command::none();
+                }
+
+                Command::perform(
+                    async move {
+                        write_config_file(path.clone(), snippet.to_file())
+                            .await
+                            .map(|()| path)
+                    },
+                    Message::SnippetExported,
+                )
+            }
+            Message::CloseSnippetName => {
+                self.snippet_name = None;
+
+                Command::none()
+            }
+            Message::SnippetExported(result) => {
+                match result {
+                    Ok(path) => {
+                        self.toast = Some(format!(
+                            "Exported the snippet to {}",
+                            path.display()
+                        ));
+                    }
+                    Err(Error::IoError(kind)) => {
+                        self.toast = Some(format!(
+                            "Could not export the snippet: {kind}"
+                        ));
+                    }
+                    Err(Error::DialogClosed) => {}
+                }
+
+                Command::none()
+            }
+            Message::RenameTab => {
+                self.renaming =
+                    Some(fragment.title.clone().unwrap_or_default());
+
+                text_input::focus(rename_id())
+            }
+            Message::TabTitleChanged(input) => {
+                self.renaming = Some(input);
+
+                Command::none()
+            }
+            Message::SetTabTitle(index, title) => {
+                self.renaming = None;
+
+                let Some(fragment) = self.fragments.get_mut(index) else {
+                    return Command::none();
+                };
+                let title =
+                    Some(title.trim().to_owned()).filter(|t| !t.is_empty());
+
+                fragment.title = title.clone();
+
+                // Only the settings of the current file can be remembered
+                if index == self.fragment_index {
+                    self.remember(|settings| settings.title = title)
+                } else {
+                    Command::none()
+                }
+            }
+            Message::CloseRenameTab => {
+                self.renaming = None;
+
+                Command::none()
+            }
+            Message::OpenGoTo => {
+                self.go_to = Some(String::new());
+                self.surround = None;
+                self.snippet_name = None;
+
+                text_input::focus(go_to_id())
+            }
+            Message::GoToChanged(input) => {
+                self.go_to = Some(input);
+
+                Command::none()
+            }
+            Message::GoToSubmitted => {
+                let input = self.go_to.take().unwrap_or_default();
+
+                match editing::parse_position(&fragment.content, &input) {
+                    Some(position) => select_after_layout(
+                        &mut fragment.content,
+                        position,
+                        position,
+                    ),
+                    None => {
+                        self.go_to = Some(input);
+                        self.toast = Some(String::from(
+                            "Go to expects line or line:column",
+                        ));
+
+                        Command::none()
+                    }
+                }
+            }
+            Message::CloseGoTo => {
+                self.go_to = None;
+
+                Command::none()
+            }
+            Message::SurroundWith => {
+                if fragment.content.selection().is_none() {
+                    self.toast = Some(String::from("Nothing is selected"));
+
+                    return Command::none();
+                }
+
+                self.surround = Some(self.last_surround.clone());
+                self.go_to = None;
+                self.snippet_name = None;
+
+                text_input::focus(surround_id())
+            }
+            Message::SurroundChanged(input) => {
+                self.surround = Some(input);
+
+                Command::none()
+            }
+            Message::OpenFind => {
+                let mut search = self
+                    .find
+                    .take()
+                    .or_else(|| self.pinned_search.take())
+                    .unwrap_or_default();
+
+                // A short selection is most likely what is looked for, and
+                // one over several lines where to replace it
+                match fragment.content.selection() {
+                    Some(selection) if selection.contains('\n') => {
+                        self.replace_scope = editing::selection(
+                            &fragment.content,
+                        )
+                        .map(|(anchor, cursor)| {
+                            (anchor.min(cursor), anchor.max(cursor))
+                        });
+                    }
+                    Some(selection) => search.query = selection,
+                    None => {}
+                }
+
+                self.find = Some(search);
+
+                text_input::focus(find_id())
+            }
+            Message::FindChanged(query) => {
+                if let Some(search) = &mut self.find {
+                    search.query = query;
+                }
+
+                Command::none()
+            }
+            Message::ReplaceChanged(replacement) => {
+                if let Some(search) = &mut self.find {
+                    search.replacement = replacement;
+                }
+
+                Command::none()
+            }
+            Message::CaseSensitiveToggled(case_sensitive) => {
+                if let Some(search) = &mut self.find {
+                    search.case_sensitive = case_sensitive;
+                }
+
+                Command::none()
+            }
+            Message::FindNext => {
+                let Some(search) = &self.find else {
+                    return Command::none();
+                };
+
+                let text = editing::text(&fragment.content);
+                let cursor = fragment.content.cursor_position();
+                let (_, end) = editing::selection(&fragment.content)
+                    .map_or((cursor, cursor), |(anchor, cursor)| {
+                        (anchor.min(cursor), anchor.max(cursor))
+                    });
+
+                match search.next(&text, editing::to_offset(&text, end)) {
+                    Some(found) => select_after_layout(
+                        &mut fragment.content,
+                        editing::to_position(&text, found.start),
+                        editing::to_position(&text, found.end),
+                    ),
+                    None => {
+                        self.toast = Some(format!(
+                            "Could not find {}",
+                            search.query
+                        ));
+
+                        Command::none()
+                    }
+                }
+            }
+            Message::InSelectionToggled(in_selection) => {
+                self.replace_scope = None;
+
+                if !in_selection {
+                    return Command::none();
+                }
+
+                match editing::selection(&fragment.content) {
+                    Some((anchor, cursor)) => {
+                        self.replace_scope =
+                            Some((anchor.min(cursor), anchor.max(cursor)));
+                    }
+                    None => {
+                        self.toast = Some(String::from("Nothing is selected"));
+                    }
+                }
+
+                Command::none()
+            }
+            Message::ReplaceAll | Message::ConfirmReplaceAll => {
+                self.replacing = None;
+
+                let Some(search) = &self.find else {
+                    return Command::none();
+                };
+
+                let text = editing::text(&fragment.content);
+                let range = match self.replace_scope {
+                    Some((start, end)) => {
+                        // Edits since may have moved the ends inside a
+                        // character
+                        let floor = |position| {
+                            let offset = editing::to_offset(&text, position);
+
+                            (0..=offset)
+                                .rev()
+                                .find(|offset| text.is_char_boundary(*offset))
+                                .unwrap_or_default()
+                        };
+
+                        floor(start)..floor(end)
+                    }
+                    None => 0..text.len(),
+                };
+                let (replaced, count) =
+                    search.replace_within(&text, range.clone());
+
+                if count == 0 {
+                    self.toast =
+                        Some(format!("Could not find {}", search.query));
+
+                    return Command::none();
+                }
+
+                if let (Message::ReplaceAll, Some(threshold)) =
+                    (&message, self.preferences.confirm_replace_all)
+                {
+                    if count > threshold {
+                        self.replacing = Some(count);
+
+                        return Command::none();
+                    }
+                }
+
+                fragment.remember_undo(history::Kind::Other);
+                let (line, column) = fragment.content.cursor_position();
+
+                fragment.content = text_editor::Content::with_text(&replaced);
+                fragment.is_dirty = true;
+                self.toast = Some(format!("Replaced {count} occurrences"));
+
+                // The selection then covers what it became
+                if let Some((start, _)) = self.replace_scope {
+                    let end = range.end + replaced.len() - text.len();
+                    let end = editing::to_position(&replaced, end);
+
+                    self.replace_scope = Some((start, end));
+
+                    return select_after_layout(
+                        &mut fragment.content,
+                        start,
+                        end,
+                    );
+                }
+
+                // The caret stays about where it was
+                let position =
+                    editing::clamp(&fragment.content, (line + 1, column + 1));
+
+                select_after_layout(&mut fragment.content, position, position)
+            }
+            Message::CancelReplaceAll => {
+                self.replacing = None;
+
+                Command::none()
+            }
+            Message::ConfirmEmptying => {
+                let Some(action) = self.emptying.take() else {
+                    return Command::none();
+                };
+
+                fragment.remember_undo(history::Kind::Other);
+                fragment.content.perform(action);
+                fragment.is_dirty = true;
+                fragment.record_edit();
+
+                self.blame_current_line()
+            }
+            Message::CancelEmptying => {
+                self.emptying = None;
+
+                Command::none()
+            }
+            Message::CloseFind => {
+                self.close_find();
+
+                Command::none()
+            }
+            Message::PinSearchToggled(pin_search) => {
+                self.pin_search = pin_search;
+
+                Command::none()
+            }
+            Message::ClearHighlights => {
+                self.pinned_search = None;
+
+                Command::none()
+            }
+            Message::SearchAll(query) => {
+                if query.is_empty() {
+                    return Command::none();
+                }
+
+                let search = Search {
+                    query,
+                    ..self.find.clone().unwrap_or_default()
+                };
+                let texts: Vec<String> = self
+                    .fragments
+                    .iter()
+                    .map(|fragment| editing::text(&fragment.content))
+                    .collect();
+
+                self.search_results = Some(Results::new(
+                    search,
+                    texts.iter().map(String::as_str),
+                ));
+
+                Command::none()
+            }
+            Message::JumpToResult { fragment, line } => {
+                if fragment >= self.fragments.len() {
+                    return Command::none();
+                }
+
+                self.activate(fragment);
+
+                let content = &mut self.fragments[fragment].content;
+
+                // The line may have been edited since it was found
+                let found = self
+                    .search_results
+                    .as_ref()
+                    .zip(content.line(line))
+                    .and_then(|(results, text)| {
+                        results.search.matches(&text).first().cloned()
+                    });
+
+                let Some(found) = found else {
+                    let position = editing::clamp(content, (line + 1, 1));
+
+                    return select_after_layout(content, position, position);
+                };
+
+                select_after_layout(
+                    content,
+                    (line, found.start),
+                    (line, found.end),
+                )
+            }
+            Message::CloseSearchResults => {
+                self.search_results = None;
+
+                Command::none()
+            }
+            Message::SurroundSubmitted => {
+                let input = self.surround.take().unwrap_or_default();
+
+                let Some((open, close)) = editing::surround_pair(&input) else {
+                    self.surround = Some(input);
+
+                    return Command::none();
+                };
+
+                let Some((anchor, cursor)) =
+                    editing::selection(&fragment.content)
+                else {
+                    return Command::none();
+                };
+                let (start, end) = (anchor.min(cursor), anchor.max(cursor));
+
+                let text = editing::text(&fragment.content);
+                let (from, to) = (
+                    editing::to_offset(&text, start),
+                    editing::to_offset(&text, end),
+                );
+
+                fragment.remember_undo(history::Kind::Other);
+                editing::replace(
+                    &mut fragment.content,
+                    start,
+                    end,
+                    &format!("{open}{}{close}", &text[from..to]),
+                );
+                fragment.is_dirty = true;
+                self.last_surround = input;
+
+                // Keep the surrounded text selected, inside the new strings
+                let text = editing::text(&fragment.content);
+                let start = editing::to_position(&text, from + open.len());
+                let end = editing::to_position(&text, to + open.len());
+
+                select_after_layout(&mut fragment.content, start, end)
+            }
+            Message::CloseSurround => {
+                self.surround = None;
+
+                Command::none()
+            }
+            Message::Undo | Message::Redo => {
+                let current = Snapshot {
+                    text: editing::text(&fragment.content),
+                    cursor: fragment.content.cursor_position(),
+                };
+                let restored = if matches!(message, Message::Undo) {
+                    fragment.history.undo(current)
+                } else {
+                    fragment.history.redo(current)
+                };
+
+                let Some(restored) = restored else {
+                    return Command::none();
+                };
+
+                fragment.content =
+                    text_editor::Content::with_text(&restored.text);
+                // Undoing back to the saved text leaves the file clean
+                fragment.is_dirty = fragment.disk_lines.is_none()
+                    || !fragment.hunks().is_empty();
+
+                let (line, column) = restored.cursor;
+                let position =
+                    editing::clamp(&fragment.content, (line + 1, column + 1));
+
+                select_after_layout(&mut fragment.content, position, position)
+            }
+            Message::ZoomIn | Message::ZoomOut | Message::ZoomReset => {
+                self.font_size = match message {
+                    Message::ZoomIn => self.font_size + 2,
+                    Message::ZoomOut => self.font_size.saturating_sub(2),
+                    _ => DEFAULT_FONT_SIZE,
+                }
+                .clamp(8, 48);
+
+                Command::none()
+            }
+            Message::ClosePanels => {
+                // Escape again once the find bar is closed clears its
+                // highlights
+                if self.find.is_none() {
+                    self.pinned_search = None;
+                }
+
+                self.close_find();
+                self.go_to = None;
+                self.surround = None;
+                self.snippet_name = None;
+                self.renaming = None;
+                self.completion = None;
+
+                Command::none()
+            }
+            Message::StdinChunk(chunk) => {
+                if let Some(stdin) =
+                    self.fragments.iter_mut().find(|f| f.follows_stdin)
+                {
+                    // Keeping the caret at the end scrolls the editor along
+                    stdin.content.perform(text_editor::Action::Move(
+                        text_editor::Motion::DocumentEnd,
+                    ));
+                    stdin.content.perform(text_editor::Action::Edit(
+                        text_editor::Edit::Paste(Arc::new(chunk)),
+                    ));
+                }
+
+                Command::none()
+            }
+        }
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let stdin = if self.fragments.iter().any(|f| f.follows_stdin) {
+            follow_stdin()
+        } else {
+            Subscription::none()
+        };
+
+        let forwarded = if self.preferences.single_instance {
+            instance::listen().map(Message::FileForwarded)
+        } else {
+            Subscription::none()
+        };
+
+        // Files are polled, so only the ones open at the time are watched
+        let disk = if self.fragments.iter().any(|f| f.file.is_some()) {
+            time::every(Duration::from_secs(1)).map(|_| Message::CheckDisk)
+        } else {
+            Subscription::none()
+        };
+
+        // Untitled tabs are never saved on their own, as that asks where to
+        let auto_save = if self.preferences.auto_save
+            && self.fragments.iter().any(|f| f.is_dirty && f.file.is_some())
+        {
+            time::every(Duration::from_millis(500))
+                .map(|_| Message::AutoSaveTick)
+        } else {
+            Subscription::none()
+        };
+
+        // A drag over the line numbers may end anywhere in the window
+        let gutter_drag = if self.gutter_drag.is_some() {
+            event::listen_with(|event, _status| match event {
+                Event::Mouse(mouse::Event::ButtonReleased(
+                    mouse::Button::Left,
+                )) => Some(Message::GutterReleased),
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        };
+
+        let geometry = if self.geometry_changed.is_some() {
+            time::every(GEOMETRY_DEBOUNCE).map(|_| Message::GeometryTick)
+        } else {
+            Subscription::none()
+        };
+
+        Subscription::batch([
+            stdin,
+            geometry,
+            forwarded,
+            auto_save,
+            disk,
+            gutter_drag,
+            keyboard::on_key_press(|key, modifiers| {
+                Some(Message::KeyPressed(key, modifiers))
+            }),
+            event::listen_with(|event, _status| match event {
+                Event::Keyboard(keyboard::Event::ModifiersChanged(
+                    modifiers,
+                )) => Some(Message::ModifiersChanged(modifiers)),
+                Event::Window(_, window::Event::FileDropped(path)) => {
+                    Some(Message::FileDropped(path))
+                }
+                Event::Window(_, window::Event::CloseRequested) => {
+                    Some(Message::CloseRequested)
+                }
+                Event::Window(_, window::Event::Resized { width, height }) => {
+                    Some(Message::WindowResized(width, height))
+                }
+                Event::Window(_, window::Event::Moved { x, y }) => {
+                    Some(Message::WindowMoved(x, y))
+                }
+                // The editor keeps Escape to itself to give up its focus
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: Key::Named(Named::Escape),
+                    ..
+                }) => Some(Message::EscapePressed),
+                _ => None,
+            }),
+        ])
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        let idx = self.fragment_index;
+        let controls = Row::with_children(
+            self.preferences
+                .toolbar
+                .iter()
+                .map(|toolbar_action| self.toolbar_action(*toolbar_action)),
+        )
+        .push_maybe((!self.preferences.tools.is_empty()).then(|| {
+            pick_list(
+                self.preferences
+                    .tools
+                    .iter()
+                    .map(|tool| tool.name.clone())
+                    .collect::<Vec<_>>(),
+                None::<String>,
+                Message::OpenWith,
+            )
+            .placeholder("Open with...")
+            .text_size(14)
+            .padding([5, 10])
+        }))
+        .push_maybe((!self.recent.files.is_empty()).then(|| {
+            pick_list(
+                self.recent
+                    .files
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect::<Vec<_>>(),
+                None::<String>,
+                |file| Message::OpenRecent(PathBuf::from(file)),
+            )
+            .placeholder("Recent files...")
+            .text_size(14)
+            .padding([5, 10])
+        }))
+        .push(horizontal_space())
+        .push(
+            pick_list(
+                Language::ALL.iter().collect::<Vec<_>>(),
+                self.fragments[idx]
+                    .language(&self.preferences.new_file_language),
+                |language| Message::LanguageSelected(Some(language)),
+            )
+            .placeholder("Plain text")
+            .text_size(14)
+            .padding([5, 10]),
+        )
+        .push_maybe(self.fragments[idx].language_override.map(|_| {
+            tooltip(
+                button(text("Auto").size(14))
+                    .style(theme::Button::Text)
+                    .on_press(Message::LanguageSelected(None)),
+                "Go back to the language of the extension",
+                tooltip::Position::Bottom,
+            )
+            .style(theme::Container::Box)
+        }))
+        .push(
+            pick_list(
+                highlighter::Theme::ALL,
+                Some(self.theme),
+                Message::ThemeSelected,
+            )
+            .text_size(14)
+            .padding([5, 10]),
+        )
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        let file_name: Element<_> = if let Some(title) = &self.renaming {
+            row![
+                text_input("Tab title", title)
+                    .id(rename_id())
+                    .on_input(Message::TabTitleChanged)
+                    .on_submit(Message::SetTabTitle(idx, title.clone()))
+                    .width(200)
+                    .size(14),
+                button(text("Cancel").size(14))
+                    .style(theme::Button::Text)
+                    .on_press(Message::CloseRenameTab),
+            ]
+            .align_items(Alignment::Center)
+            .into()
+        } else {
+            text(if let Some(path) = &self.fragments[idx].file {
+                let path = path.display().to_string();
+
+                if path.len() > 60 {
+                    format!("...{}", &path[path.len() - 40..])
+                } else {
+                    path
+                }
+            } else if let Some(path) = &self.fragments[idx].diff_of {
+                format!("Unsaved changes to {}", path.display())
+            } else if self.fragments[idx].follows_stdin {
+                String::from("Following stdin")
+            } else {
+                String::from("New file")
+            })
+            .into()
+        };
+
+        let file_status = row![file_name]
+            .push(
+                tooltip(
+                    button(text(if self.fragments[idx].read_only {
+                        "Read-only"
+                    } else {
+                        "Editable"
+                    }))
+                    .style(theme::Button::Text)
+                    .on_press(Message::ToggleReadOnly),
+                    "Click to allow or refuse edits",
+                    tooltip::Position::Top,
+                )
+                .style(theme::Container::Box),
+            )
+            .push_maybe(self.fragments[idx].link_target.as_ref().map(|target| {
+                text(format!(
+                    "symlink to {} (saving writes there)",
+                    target.display()
+                ))
+            }))
+            .push_maybe(
+                self.fragments[idx]
+                    .blame
+                    .as_ref()
+                    .filter(|_| self.preferences.show_blame)
+                    .map(|blame| text(blame.to_string())),
+            )
+            .push_maybe(self.fragments[idx].changes())
+            .push_maybe(self.save_status())
+            .push_maybe((self.operations > 1).then(|| {
+                text(format!("{} operations...", self.operations))
+            }))
+            .spacing(10);
+
+        let position: Element<_> = if let Some(surround) = &self.surround {
+            row![
+                text_input("<b> or ** or ( )", surround)
+                    .id(surround_id())
+                    .on_input(Message::SurroundChanged)
+                    .on_submit(Message::SurroundSubmitted)
+                    .width(160)
+                    .size(14),
+                button(text("Cancel").size(14))
+                    .style(theme::Button::Text)
+                    .on_press(Message::CloseSurround),
+            ]
+            .align_items(Alignment::Center)
+            .into()
+        } else if let Some(name) = &self.snippet_name {
+            row![
+                text_input("Snippet name", name)
+                    .id(snippet_name_id())
+                    .on_input(Message::SnippetNameChanged)
+                    .on_submit(Message::SnippetNameSubmitted)
+                    .width(160)
+                    .size(14),
+                button(text("Cancel").size(14))
+                    .style(theme::Button::Text)
+                    .on_press(Message::CloseSnippetName),
+            ]
+            .align_items(Alignment::Center)
+            .into()
+        } else if let Some(go_to) = &self.go_to {
+            row![
+                text_input("line:column", go_to)
+                    .id(go_to_id())
+                    .on_input(Message::GoToChanged)
+                    .on_submit(Message::GoToSubmitted)
+                    .width(120)
+                    .size(14),
+                button(text("Cancel").size(14))
+                    .style(theme::Button::Text)
+                    .on_press(Message::CloseGoTo),
+            ]
+            .align_items(Alignment::Center)
+            .into()
+        } else {
+            let content = &self.fragments[idx].content;
+            let cursor = content.cursor_position();
+            let position = |(line, column): Position| {
+                format!("{}:{}", line + 1, column + 1)
+            };
+
+            // A selection shows where it was started as well
+            let label = match editing::selection(content) {
+                Some((anchor, cursor)) => {
+                    format!("{} → {}", position(anchor), position(cursor))
+                }
+                None => position(cursor),
+            };
+
+            tooltip(
+                button(text(label))
+                    .style(theme::Button::Text)
+                    .on_press(Message::OpenGoTo),
+                "Go to line:column (Ctrl+G)",
+                tooltip::Position::Top,
+            )
+            .style(theme::Container::Box)
+            .into()
+        };
+
+        let status = row![
+            file_status,
+            horizontal_space(),
+            pick_list(
+                TrailingNewline::ALL,
+                Some(self.preferences.trailing_newline),
+                Message::TrailingNewlineSelected
+            )
+            .text_size(14)
+            .padding([5, 10]),
+            tooltip(
+                checkbox("As is", self.fragments[idx].save_as_is)
+                    .on_toggle(Message::SaveAsIsToggled)
+                    .text_size(14),
+                "Save this file without trimming it or changing its final \
+                 newlines",
+                tooltip::Position::Top,
+            )
+            .style(theme::Container::Box),
+        ]
+        .push_maybe(self.pinned_search.as_ref().map(|search| {
+            button(text(format!("Clear highlights of {}", search.query)))
+                .style(theme::Button::Text)
+                .on_press(Message::ClearHighlights)
+        }))
+        .push(
+            pick_list(
+                ViewMode::ALL,
+                Some(self.fragments[idx].view_mode),
+                Message::ViewModeSelected,
+            )
+            .text_size(14)
+            .padding([5, 10]),
+        )
+        .push(
+            tooltip(
+                pick_list(
+                    Indent::CHOICES,
+                    Some(self.fragments[idx].indent),
+                    Message::IndentSelected,
+                )
+                .text_size(14)
+                .padding([5, 10]),
+                "What the Tab key and reindenting insert",
+                tooltip::Position::Top,
+            )
+            .style(theme::Container::Box),
+        )
+        .push_maybe(self.fragments[idx].mixed_line_endings())
+        .push_maybe(self.fragments[idx].encoding_mismatch())
+        .push(
+            tooltip(
+                pick_list(
+                    Encoding::ALL,
+                    Some(self.fragments[idx].encoding),
+                    Message::EncodingSelected,
+                )
+                .text_size(14)
+                .padding([5, 10]),
+                "Encoding to read the file with",
+                tooltip::Position::Top,
+            )
+            .style(theme::Container::Box),
+        )
+        .push(
+            tooltip(
+                pick_list(
+                    LineEnding::ALL,
+                    Some(self.fragments[idx].line_ending),
+                    Message::LineEndingSelected,
+                )
+                .text_size(14)
+                .padding([5, 10]),
+                "Line endings to save with",
+                tooltip::Position::Top,
+            )
+            .style(theme::Container::Box),
+        )
+        .push_maybe((self.fragments.len() > 1).then(|| {
+            tooltip(
+                button(text("All tabs").size(14))
+                    .style(theme::Button::Text)
+                    .on_press(Message::LineEndingForAllTabs(
+                        self.fragments[idx].line_ending,
+                    )),
+                "Save every open tab with these line endings",
+                tooltip::Position::Top,
+            )
+            .style(theme::Container::Box)
+        }))
+        .push(
+            checkbox("Reindent pastes", self.preferences.reindent_paste)
+                .on_toggle(Message::ReindentPasteToggled)
+                .text_size(14),
+        )
+        .push(
+            tooltip(
+                checkbox("Auto-save", self.preferences.auto_save)
+                    .on_toggle(Message::AutoSaveToggled)
+                    .text_size(14),
+                "Save files with a path once edits pause",
+                tooltip::Position::Top,
+            )
+            .style(theme::Container::Box),
+        )
+        .push(
+            checkbox("Line numbers", self.preferences.show_line_numbers)
+                .on_toggle(Message::LineNumbersToggled)
+                .text_size(14),
+        )
+        .push_maybe(
+            (!self.fragments[idx].is_highlighted(&self.preferences)).then(
+                || {
+                    tooltip(
+                        button(text("Highlight anyway").size(14))
+                            .style(theme::Button::Text)
+                            .on_press(Message::HighlightAnyway),
+                        "Too large to highlight without slowing down",
+                        tooltip::Position::Top,
+                    )
+                    .style(theme::Container::Box)
+                },
+            ),
+        )
+        .push_maybe(self.is_markdown().then(|| {
+            checkbox("Preview", self.preview)
+                .on_toggle(|_| Message::TogglePreview)
+                .text_size(14)
+        }))
+        .push_maybe(self.fragments[idx].diff_of.is_some().then(|| {
+            checkbox("Reveal whitespace", self.reveal_whitespace)
+                .on_toggle(Message::RevealWhitespaceToggled)
+                .text_size(14)
+        }))
+        .push_maybe(
+            self.preferences
+                .show_invisibles
+                .then(|| self.fragments[idx].invisibles())
+                .flatten(),
+        )
+        .push(self.fragments[idx].counts())
+        .push(self.fragments[idx].size())
+        .push_maybe((self.font_size != DEFAULT_FONT_SIZE).then(|| {
+            tooltip(
+                button(text(format!(
+                    "{}%",
+                    u32::from(self.font_size) * 100
+                        / u32::from(DEFAULT_FONT_SIZE)
+                )))
+                .style(theme::Button::Text)
+                .on_press(Message::ZoomReset),
+                "Zoom level, click or Ctrl+0 to reset",
+                tooltip::Position::Top,
+            )
+            .style(theme::Container::Box)
+        }))
+        .push(position)
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        let tabs: Element<_> = match self.preferences.tab_overflow {
+            TabOverflow::Scroll => self
+                .fragments
+                .iter()
+                .fold(TabBar::new(Message::TabSelected), |tab_bar, fragment| {
+                    let idx = tab_bar.size();
+                    tab_bar.push(idx, TabLabel::Text(fragment.tab_label()))
+                })
+                .on_close(Message::ConfirmClose)
+                .tab_width(Length::Shrink)
+                .spacing(5.0)
+                .padding(5.0)
+                .text_size(32.0)
+                .into(),
+            TabOverflow::Wrap => self.wrapped_tabs(),
+        };
+
+        let toast = self.toast.as_ref().map(|toast| {
+            container(
+                row![
+                    text(toast),
+                    horizontal_space(),
+                    button("Dismiss")
+                        .on_press(Message::DismissToast)
+                        .style(theme::Button::Secondary)
+                ]
+                .spacing(10)
+                .align_items(Alignment::Center),
+            )
+            .padding(5)
+            .style(theme::Container::Box)
+        });
+
+        let editor: Element<_> = if self.welcome {
+            welcome()
+        } else {
+            let scope = self
+                .preferences
+                .sticky_scroll
+                .then(|| editing::scope_header(&self.fragments[idx].content))
+                .flatten()
+                .map(|(line, header)| {
+                    container(text(format!("{:>4}  {header}", line + 1)))
+                        .width(Length::Fill)
+                        .padding([2, 5])
+                        .style(theme::Container::Box)
+                });
+
+            let panes = match self.split {
+                Some((left, right)) => {
+                    // The pane the status bar and commands act on stands out
+                    let pane = |index| {
+                        container(self.pane(index)).padding(5).style(
+                            if index == idx {
+                                theme::Container::Box
+                            } else {
+                                theme::Container::Transparent
+                            },
+                        )
+                    };
+
+                    row![pane(left), pane(right)].spacing(10).into()
+                }
+                None if self.preview && self.is_markdown() => {
+                    row![self.pane(idx), self.preview()].spacing(10).into()
+                }
+                None => self.pane(idx),
+            };
+
+            // The hex dump cannot be searched, so it shows on its own
+            if self.split.is_none()
+                && self.fragments[idx].view_mode == ViewMode::Hex
+            {
+                panes
+            } else {
+                column![]
+                    .push_maybe(self.find_bar())
+                    .push_maybe(self.search_results())
+                    .push_maybe(scope)
+                    .push(panes)
+                    .push_maybe(self.completion_list())
+                    .into()
+            }
+        };
+
+        let content = match self.preferences.tab_bar_position {
+            TabBarPosition::Top => column![controls, tabs, editor],
+            TabBarPosition::Bottom => column![controls, editor, tabs],
+        }
+        .push_maybe(self.disk_change_banner())
+        .push_maybe(toast)
+        .push(status)
+        .spacing(10)
+        .padding(10);
+
+        let (overlay, dismiss) = match self.closing {
+            _ if self.confirming_exit.is_some() => (
+                self.confirming_exit.map(|count| self.exit_prompt(count)),
+                Message::CancelExit,
+            ),
+            Some(index) => {
+                (Some(self.close_prompt(index)), Message::CancelClose)
+            }
+            None if self.reloading.is_some() => (
+                self.reloading.map(|index| self.reload_prompt(index)),
+                Message::CancelReload,
+            ),
+            None if self.confirming_close_all.is_some() => (
+                self.confirming_close_all
+                    .map(|count| self.close_all_prompt(count)),
+                Message::CancelCloseAll,
+            ),
+            None if self.confirming_save_all.is_some() => (
+                self.confirming_save_all
+                    .map(|count| self.save_all_prompt(count)),
+                Message::CancelSaveAll,
+            ),
+            None if self.emptying.is_some() => {
+                (Some(self.empty_prompt()), Message::CancelEmptying)
+            }
+            None if self.replacing.is_some() => (
+                self.replacing.map(|count| self.replace_prompt(count)),
+                Message::CancelReplaceAll,
+            ),
+            None if self.command_palette.is_some() => (
+                self.command_palette
+                    .as_ref()
+                    .map(|palette| self.command_palette(palette)),
+                Message::CloseCommandPalette,
+            ),
+            None if self.theme_palette.is_some() => {
+                (Some(self.theme_palette()), Message::CloseThemePalette)
+            }
+            None if self.show_edit_timeline => {
+                (Some(self.edit_timeline()), Message::HideEditTimeline)
+            }
+            None => (
+                self.show_shortcuts.then(|| self.shortcuts()),
+                Message::HideShortcuts,
+            ),
+        };
+
+        Modal::new(content, overlay)
+            .backdrop(dismiss.clone())
+            .on_esc(dismiss)
+            .into()
+    }
+
+    // The editor widget takes no text size, so zooming scales the window
+    fn scale_factor(&self) -> f64 {
+        f64::from(self.font_size) / f64::from(DEFAULT_FONT_SIZE)
+    }
+
+    fn theme(&self) -> Theme {
+        if self.theme.is_dark() {
+            Theme::Dark
+        } else {
+            Theme::Light
+        }
+    }
+}
+
+impl FragmentContent {
+    /// The name shown on the tab, marked while there are unsaved changes.
+    fn tab_label(&self) -> String {
+        let name = if let Some(title) = &self.title {
+            title.clone()
+        } else if let Some(file) = &self.file {
+            file.file_name()
+                .and_then(ffi::OsStr::to_str)
+                .unwrap_or("Untitled")
+                .to_string()
+        } else if let Some(file) = &self.diff_of {
+            let file_name = file
+                .file_name()
+                .and_then(ffi::OsStr::to_str)
+                .unwrap_or("Untitled");
+            format!("{file_name} (diff)")
+        } else if self.follows_stdin {
+            String::from("stdin")
+        } else {
+            String::from("New")
+        };
+
+        if self.is_dirty {
+            format!("• {name}")
+        } else {
+            name
+        }
+    }
+
+    /// Returns the line changes since the file was last loaded or saved.
+    fn hunks(&self) -> Vec<diff::Hunk> {
+        let Some(disk_lines) = &self.disk_lines else {
+            return Vec::new();
+        };
+
+        let lines: Vec<_> = self.content.lines().collect();
+
+        diff::hunks(disk_lines, &lines)
+    }
+
+    /// Returns the line changes, diffed again only once the text or the file
+    /// on disk changed.
+    fn line_changes(&self) -> &[diff::Hunk] {
+        self.line_changes.get_or_init(|| self.hunks())
+    }
+
+    /// Remembers the text before an edit of the given kind, to undo it.
+    fn remember_undo(&mut self, kind: history::Kind) {
+        let content = &self.content;
+
+        self.history.record(kind, || Snapshot {
+            text: editing::text(content),
+            cursor: content.cursor_position(),
+        });
+    }
+
+    /// Remembers the cursor as the place of the latest edit, moving the
+    /// entry of its line up instead of adding one per keystroke.
+    fn record_edit(&mut self) {
+        let position = self.content.cursor_position();
+
+        if self
+            .edits
+            .back()
+            .is_some_and(|((line, _), _)| *line == position.0)
+        {
+            self.edits.pop_back();
+        }
+
+        if self.edits.len() == EDIT_HISTORY {
+            self.edits.pop_front();
+        }
+
+        self.edits.push_back((position, Instant::now()));
+    }
+
+    /// Warns about lines that end differently from the rest of the file.
+    fn mixed_line_endings(&self) -> Option<Element<'_, Message>> {
+        (self.stray_line_endings > 0).then(|| {
+            tooltip(
+                text(format!("{} mixed", self.stray_line_endings)),
+                "Lines ending with the other line ending\n\
+                 Pick a line ending to normalize them",
+                tooltip::Position::Top,
+            )
+            .style(theme::Container::Box)
+            .into()
+        })
+    }
+
+    /// Warns about characters that cannot be seen, if any, and selects the
+    /// next one when clicked.
+    fn invisibles(&self) -> Option<Element<'_, Message>> {
+        let counts = &self.stats().invisibles;
+
+        if counts.is_empty() {
+            return None;
+        }
+
+        let total: usize = counts.iter().map(|(_, count)| count).sum();
+        let summary = counts
+            .iter()
+            .map(|(name, count)| format!("{count} × {name}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Some(
+            tooltip(
+                button(text(format!("{total} invisible")))
+                    .style(theme::Button::Text)
+                    .on_press(Message::SelectNextInvisible),
+                text(format!(
+                    "{summary}\nClick to select the next one, \
+                     Ctrl+Shift+I to strip them"
+                )),
+                tooltip::Position::Top,
+            )
+            .style(theme::Container::Box)
+            .into(),
+        )
+    }
+
+    /// Warns that the file had bytes that are invalid in its encoding, and
+    /// reopens it with a better guess when clicked.
+    fn encoding_mismatch(&self) -> Option<Element<'_, Message>> {
+        if self.decode_errors == 0 {
+            return None;
+        }
+
+        let guess = self.likely_encoding;
+
+        let badge = button(text(format!(
+            "⚠ {} invalid {}",
+            self.decode_errors, self.encoding
+        )))
+        .style(theme::Button::Destructive)
+        .on_press_maybe(guess.map(Message::EncodingSelected));
+
+        let tip = match guess {
+            Some(guess) => format!(
+                "The file is likely not {}\nClick to reopen it as {guess}",
+                self.encoding
+            ),
+            None => format!("The file is likely not {}", self.encoding),
+        };
+
+        Some(
+            tooltip(badge, text(tip), tooltip::Position::Top)
+                .style(theme::Container::Box)
+                .into(),
+        )
+    }
+
+    /// Whether the text is highlighted, which it is unless it is too large
+    /// and was not asked to be anyway.
+    fn is_highlighted(&self, preferences: &Preferences) -> bool {
+        self.highlight_anyway
+            || preferences
+                .highlights(self.stats().bytes, self.content.line_count())
+    }
+
+    fn stats(&self) -> &Stats {
+        self.stats
+            .get_or_init(|| Stats::of(&editing::text(&self.content)))
+    }
+
+    /// Counts the occurrences of the search, again only once the text or
+    /// the search changed.
+    fn match_count(&self, search: &Search) -> usize {
+        let mut cached = self.match_count.borrow_mut();
+
+        match &*cached {
+            Some((counted, count)) if counted == search => *count,
+            _ => {
+                let count = search.matches(&editing::text(&self.content)).len();
+
+                *cached = Some((search.clone(), count));
+                count
+            }
+        }
+    }
+
+    /// Drops what is cached about the text, once it may have changed.
+    fn forget_counts(&mut self) {
+        self.stats.take();
+        self.line_changes.take();
+        self.match_count.get_mut().take();
+    }
+
+    /// Counts the words and characters of the selection, along with the
+    /// lines it spans, or of the whole text when nothing is selected.
+    fn counts(&self) -> Element<'_, Message> {
+        let selection = self.content.selection();
+        let (words, chars) = match &selection {
+            Some(selection) => (
+                selection.split_whitespace().count(),
+                selection.chars().count(),
+            ),
+            None => (self.stats().words, self.stats().chars),
+        };
+
+        let words = match words {
+            1 => String::from("1 word"),
+            words => format!("{words} words"),
+        };
+        let counts = format!("{words}, {chars} chars");
+
+        match selection.map(|selection| selection.matches('\n').count() + 1) {
+            Some(1) => text(format!("1 line, {counts} selected")).into(),
+            Some(lines) => {
+                text(format!("{lines} lines, {counts} selected")).into()
+            }
+            None => text(counts).into(),
+        }
+    }
+
+    /// The size of the file on disk, or of the text while it has changes,
+    /// followed by the size on disk.
+    fn size(&self) -> Element<'_, Message> {
+        let size = self.stats().bytes as u64;
+
+        text(match self.disk_size {
+            Some(disk_size) if !self.is_dirty => format_size(disk_size),
+            Some(disk_size) => format!(
+                "{} ({} on disk)",
+                format_size(size),
+                format_size(disk_size)
+            ),
+            None => format_size(size),
+        })
+        .into()
+    }
+
+    /// Summarizes the line changes since the file was last loaded or saved.
+    fn changes(&self) -> Option<Element<'_, Message>> {
+        let (mut added, mut modified, mut removed) = (0, 0, 0);
+
+        for hunk in self.line_changes() {
+            match hunk.kind() {
+                diff::Kind::Added => added += hunk.current.len(),
+                diff::Kind::Modified => modified += hunk.current.len(),
+                diff::Kind::Removed => removed += hunk.original.len(),
+            }
+        }
+
+        let summary = [(added, "+"), (modified, "~"), (removed, "-")]
+            .into_iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, sign)| format!("{sign}{count}"))
+            .collect::<Vec<_>>();
+
+        (!summary.is_empty()).then(|| {
+            tooltip(
+                button(text(summary.join(" ")))
+                    .style(theme::Button::Text)
+                    .on_press(Message::CompareWithDisk),
+                "Lines added, changed and removed since last saved\n\
+                 Click or Ctrl+Shift+M to compare with the file on disk\n\
+                 Ctrl+Shift+H reverts the change at the cursor",
+                tooltip::Position::Top,
+            )
+            .style(theme::Container::Box)
+            .into()
+        })
+    }
+
+    /// Saves the text with the line ending from now on, returning how many
+    /// lines end differently than they will be saved, which makes the
+    /// fragment dirty.
+    fn convert_line_endings(&mut self, line_ending: LineEnding) -> usize {
+        // Lines only have their endings rewritten when saved
+        let converted = if line_ending == self.line_ending {
+            self.stray_line_endings
+        } else {
+            (self.content.line_count() - 1)
+                .saturating_sub(self.stray_line_endings)
+        };
+
+        self.line_ending = line_ending;
+        self.stray_line_endings = 0;
+        self.is_dirty = self.is_dirty || converted > 0;
+
+        converted
+    }
+
+    /// Whether the tab has nothing in it worth keeping: no file, no text, and
+    /// no stream or diff it shows.
+    fn is_blank(&self) -> bool {
+        self.file.is_none()
+            && !self.is_dirty
+            && !self.follows_stdin
+            && self.diff_of.is_none()
+            && self.content.line_count() <= 1
+            && self.content.line(0).is_none_or(|line| line.is_empty())
+    }
+
+    /// Whether the text cannot be edited, as the tab is read-only, follows
+    /// the standard input or shows a diff.
+    fn refuses_edits(&self) -> bool {
+        self.read_only || self.follows_stdin || self.diff_of.is_some()
+    }
+
+    /// The name of the file, or `Untitled` while it has none.
+    fn name(&self) -> &str {
+        self.file
+            .as_deref()
+            .and_then(Path::file_name)
+            .and_then(ffi::OsStr::to_str)
+            .unwrap_or("Untitled")
+    }
+
+    /// The extension used to pick a syntax, defaulting to the given one
+    /// while the file has none.
+    fn extension(&self, default: &str) -> String {
+        if self.diff_of.is_some() {
+            return String::from("diff");
+        }
+
+        if let Some(language) = self.language_override {
+            return language.extensions[0].to_owned();
+        }
+
+        self.file
+            .as_deref()
+            .and_then(Path::extension)
+            .and_then(ffi::OsStr::to_str)
+            .unwrap_or(default)
+            .to_string()
+    }
+
+    fn language(&self, default: &str) -> Option<&'static Language> {
+        Language::from_extension(&self.extension(default))
+    }
+}
+
+impl Editor {
+    /// Lists the current key bindings, followed by the fixed gestures.
+    fn shortcuts(&self) -> Element<'_, Message> {
+        let bindings = self
+            .keymap
+            .bindings()
+            .iter()
+            .map(|binding| (binding.shortcut(), binding.description))
+            .chain(Keymap::GESTURES.iter().map(|(shortcut, description)| {
+                (shortcut.to_string(), *description)
+            }))
+            .map(|(shortcut, description)| {
+                row![text(shortcut).width(160), text(description)].into()
+            });
+
+        container(
+            column![
+                text("Keyboard shortcuts").size(20),
+                scrollable(Column::with_children(bindings).spacing(5)),
+                text("Press Escape to close").size(14),
+            ]
+            .spacing(15),
+        )
+        .width(480)
+        .max_height(600)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+
+    /// Returns every command the palette offers, by name, with all of the
+    /// shortcuts bound to it.
+    fn commands(&self) -> Vec<(Message, String, Vec<String>)> {
+        let mut commands: Vec<(Message, String, Vec<String>)> = Vec::new();
+
+        // An action bound to several keys is listed once
+        for binding in self.keymap.bindings() {
+            match commands
+                .iter_mut()
+                .find(|(_, name, _)| name == binding.name)
+            {
+                Some((_, _, shortcuts)) => shortcuts.push(binding.shortcut()),
+                None => commands.push((
+                    binding.message.clone(),
+                    binding.name.to_owned(),
+                    vec![binding.shortcut()],
+                )),
+            }
+        }
+
+        let unbound = self.keymap.commands().iter().map(|(message, name)| {
+            (message.clone(), (*name).to_owned(), Vec::new())
+        });
+        let themes = highlighter::Theme::ALL.iter().map(|theme| {
+            (
+                Message::ThemeSelected(*theme),
+                format!("Theme: {theme}"),
+                Vec::new(),
+            )
+        });
+
+        commands.extend(unbound.chain(themes));
+        commands
+    }
+
+    /// Returns the indices of the commands matching the palette, best first.
+    fn palette_matches(&self) -> Vec<usize> {
+        let Some(palette) = &self.command_palette else {
+            return Vec::new();
+        };
+
+        palette::matches(
+            &palette.query,
+            self.commands().iter().map(|(_, name, _)| name.as_str()),
+        )
+    }
+
+    /// Lists the commands matching what is typed, with the picked one
+    /// highlighted.
+    fn command_palette(&self, palette: &Palette) -> Element<'_, Message> {
+        let commands = self.commands();
+
+        let items = self.palette_matches().into_iter().enumerate().map(
+            |(position, index)| {
+                let (_, name, shortcuts) = &commands[index];
+
+                button(row![
+                    text(name).width(Length::Fill),
+                    text(shortcuts.join(", ")).size(14)
+                ])
+                .width(Length::Fill)
+                .style(if position == palette.selected {
+                    theme::Button::Primary
+                } else {
+                    theme::Button::Text
+                })
+                .on_press(Message::RunCommand(position))
+                .into()
+            },
+        );
+
+        container(
+            column![
+                text_input("Type a command", &palette.query)
+                    .id(command_palette_id())
+                    .on_input(Message::CommandPaletteChanged)
+                    .on_submit(Message::RunCommand(palette.selected)),
+                scrollable(Column::with_children(items).spacing(5))
+                    .id(command_list_id())
+                    .height(Length::Fixed(360.0)),
+                text("Up and Down to pick, Enter to run, Escape to close")
+                    .size(14),
+            ]
+            .spacing(15),
+        )
+        .width(480)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+
+    /// Lists the syntax themes, with the one being previewed highlighted.
+    fn theme_palette(&self) -> Element<'_, Message> {
+        let themes = highlighter::Theme::ALL.iter().map(|theme| {
+            button(text(theme.to_string()))
+                .width(Length::Fill)
+                .style(if *theme == self.theme {
+                    theme::Button::Primary
+                } else {
+                    theme::Button::Text
+                })
+                .on_press(Message::ThemeSelected(*theme))
+                .into()
+        });
+
+        container(
+            column![
+                text("Syntax theme").size(20),
+                Column::with_children(themes).spacing(5),
+                text("Up and Down to preview, Enter to keep, Escape to undo")
+                    .size(14),
+            ]
+            .spacing(15),
+        )
+        .width(360)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+
+    /// Lists where the current fragment was edited, latest first, with the
+    /// text of each line and how long ago it was.
+    fn edit_timeline(&self) -> Element<'_, Message> {
+        let fragment = &self.fragments[self.fragment_index];
+        let now = Instant::now();
+
+        let entries = fragment.edits.iter().enumerate().rev().map(
+            |(index, ((line, column), at))| {
+                let seconds = now.duration_since(*at).as_secs();
+                let ago = match seconds {
+                    0..=9 => String::from("just now"),
+                    10..=59 => format!("{seconds} s ago"),
+                    60..=3599 => format!("{} min ago", seconds / 60),
+                    _ => format!("{} h ago", seconds / 3600),
+                };
+                let preview = fragment
+                    .content
+                    .line(*line)
+                    .map(|line| line.trim().to_owned())
+                    .unwrap_or_default();
+
+                button(row![
+                    text(format!("{}:{}", line + 1, column + 1)).width(80),
+                    text(ago).width(100),
+                    text(preview),
+                ])
+                .width(Length::Fill)
+                .style(theme::Button::Text)
+                .on_press(Message::JumpToEdit(index))
+                .into()
+            },
+        );
+
+        let list: Element<'_, Message> = if fragment.edits.is_empty() {
+            text("Nothing was edited yet").into()
+        } else {
+            scrollable(Column::with_children(entries).spacing(5)).into()
+        };
+
+        container(
+            column![
+                text("Recent edits").size(20),
+                list,
+                text("Click an edit to go back to it").size(14),
+            ]
+            .spacing(15),
+        )
+        .width(560)
+        .max_height(600)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+
+    /// Offers to reload the current fragment when its file changed on disk
+    /// while it had unsaved changes.
+    fn disk_change_banner(&self) -> Option<Element<'_, Message>> {
+        let index = self.fragment_index;
+        let fragment = &self.fragments[index];
+
+        fragment.changed_on_disk.then(|| {
+            container(
+                row![
+                    text(format!(
+                        "{} changed on disk since it was opened",
+                        fragment.name()
+                    )),
+                    horizontal_space(),
+                    button("Reload")
+                        .on_press(Message::ReloadFromDisk(index))
+                        .style(theme::Button::Destructive),
+                    button("Keep mine")
+                        .on_press(Message::KeepMine(index))
+                        .style(theme::Button::Secondary),
+                ]
+                .spacing(10)
+                .align_items(Alignment::Center),
+            )
+            .padding(5)
+            .style(theme::Container::Box)
+            .into()
+        })
+    }
+
+    /// Lays the tabs out as buttons flowing onto as many rows as they need.
+    fn wrapped_tabs(&self) -> Element<'_, Message> {
+        let tabs = self.fragments.iter().enumerate().map(|(index, fragment)| {
+            row![
+                button(text(fragment.tab_label()))
+                    .style(if index == self.fragment_index {
+                        theme::Button::Primary
+                    } else {
+                        theme::Button::Secondary
+                    })
+                    .on_press(Message::TabSelected(index)),
+                button(text("×"))
+                    .style(theme::Button::Text)
+                    .on_press(Message::ConfirmClose(index)),
+            ]
+            .align_items(Alignment::Center)
+            .into()
+        });
+
+        Wrap::with_elements(tabs.collect())
+            .spacing(5.0)
+            .line_spacing(5.0)
+            .into()
+    }
+
+    /// Shows the fragment at the index in an editor, or in its hex dump.
+    fn pane(&self, index: usize) -> Element<'_, Message> {
+        let fragment = &self.fragments[index];
+
+        if fragment.view_mode == ViewMode::Hex {
+            return scrollable(text(&fragment.hex_dump))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into();
+        }
+
+        let show_line_numbers = self.preferences.show_line_numbers;
+        let editor = text_editor(&fragment.content)
+            .height(if show_line_numbers {
+                Length::Shrink
+            } else {
+                Length::Fill
+            })
+            .on_action(move |action| Message::ActionPerformedIn(index, action))
+            .style(match self.preferences.highlight_background {
+                _ if fragment.read_only => {
+                    theme::TextEditor::Custom(Box::new(ReadOnly))
+                }
+                HighlightBackground::App => theme::TextEditor::Default,
+                HighlightBackground::Theme => theme::TextEditor::Custom(
+                    Box::new(SyntaxBackground(self.theme)),
+                ),
+            });
+
+        // Large files are shown plain, as highlighting them stalls typing
+        let editor: Element<_> = if fragment.is_highlighted(&self.preferences) {
+            editor
+                .highlight::<SearchHighlighter>(
+                    search_highlight::Settings {
+                        syntax: highlighter::Settings {
+                            theme: self.theme,
+                            extension: fragment
+                                .extension(&self.preferences.new_file_language),
+                        },
+                        search: self
+                            .find
+                            .clone()
+                            .or_else(|| self.pinned_search.clone()),
+                    },
+                    |highlight, theme| highlight.to_format(theme),
+                )
+                .into()
+        } else {
+            editor.into()
+        };
+
+        if show_line_numbers {
+            let numbers = text(line_numbers::numbers(
+                fragment.content.line_count(),
+            ))
+            .horizontal_alignment(alignment::Horizontal::Right)
+            .style(theme::Text::Color(Color {
+                a: 0.5,
+                ..self.theme().palette().text
+            }));
+
+            // Clicking the numbers selects lines, and dragging more of them
+            let numbers = mouse_area(
+                container(numbers).padding(line_numbers::PADDING),
+            )
+            .on_move(move |position| Message::GutterMoved(index, position.y))
+            .on_press(Message::GutterPressed(index))
+            .on_release(Message::GutterReleased);
+
+            scrollable(row![numbers, editor])
+            .id(line_numbers::id(index))
+            .height(Length::Fill)
+            .into()
+        } else {
+            editor
+        }
+    }
+
+    /// Selects the whole lines of the current tab from the anchor line to
+    /// the given one.
+    fn select_lines(&mut self, anchor: usize, line: usize) -> Command<Message> {
+        let content = &mut self.fragments[self.fragment_index].content;
+        let (anchor, cursor) = editing::lines_selection(content, anchor, line);
+
+        editing::select(content, anchor, cursor);
+
+        Command::none()
+    }
+
+    /// Whether the current tab holds Markdown, so it can be previewed.
+    fn is_markdown(&self) -> bool {
+        self.fragments
+            .get(self.fragment_index)
+            .and_then(|fragment| {
+                fragment.language(&self.preferences.new_file_language)
+            })
+            .is_some_and(|language| language.name == "Markdown")
+    }
+
+    /// Renders the current Markdown file as it is typed.
+    fn preview(&self) -> Element<'_, Message> {
+        let text_color = self.theme().palette().text;
+        let muted = theme::Text::Color(Color {
+            a: 0.6,
+            ..text_color
+        });
+        let bold = Font {
+            weight: font::Weight::Bold,
+            ..Font::DEFAULT
+        };
+
+        let source =
+            editing::text(&self.fragments[self.fragment_index].content);
+
+        let blocks = markdown::parse(&source).into_iter().map(
+            |block| -> Element<'_, Message> {
+                match block {
+                    Block::Heading(level, heading) => text(heading)
+                        .size(match level {
+                            1 => 30,
+                            2 => 24,
+                            3 => 20,
+                            _ => 16,
+                        })
+                        .font(bold)
+                        .into(),
+                    Block::Paragraph(paragraph) => text(paragraph).into(),
+                    Block::Item {
+                        depth,
+                        marker,
+                        text: item,
+                    } => row![
+                        text(marker).width(Length::Fixed(24.0)),
+                        text(item).width(Length::Fill)
+                    ]
+                    .padding([0, 0, 0, 20 * depth as u16])
+                    .into(),
+                    Block::Quote(quote) => container(text(quote).style(muted))
+                        .padding([0, 0, 0, 15])
+                        .into(),
+                    Block::Code(code) => {
+                        container(text(code).font(Font::MONOSPACE).size(14))
+                            .width(Length::Fill)
+                            .padding(10)
+                            .style(theme::Container::Box)
+                            .into()
+                    }
+                    Block::Table(rows) => container(
+                        text(rows.join("\n")).font(Font::MONOSPACE).size(14),
+                    )
+                    .padding([0, 10])
+                    .into(),
+                    Block::Rule => horizontal_rule(1).into(),
+                }
+            },
+        );
+
+        scrollable(
+            Column::with_children(blocks)
+                .spacing(12)
+                .padding(10)
+                .width(Length::Fill),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    /// Lists the files offered for the path being typed, below the editor.
+    fn completion_list(&self) -> Option<Element<'_, Message>> {
+        let completion = self.completion.as_ref()?;
+
+        let items = completion.items.iter().enumerate().map(|(index, item)| {
+            button(text(item).size(14))
+                .width(Length::Fill)
+                .style(if index == completion.selected {
+                    theme::Button::Primary
+                } else {
+                    theme::Button::Text
+                })
+                .on_press(Message::CompletePath(index))
+                .into()
+        });
+
+        Some(
+            container(
+                column![
+                    Column::with_children(items),
+                    text("Tab or Enter to complete, Escape to dismiss")
+                        .size(12),
+                ]
+                .spacing(5),
+            )
+            .width(Length::Fill)
+            .padding(5)
+            .style(theme::Container::Box)
+            .into(),
+        )
+    }
+
+    /// Shows the search of the current fragment above the editor, with how
+    /// many times it occurs.
+    fn find_bar(&self) -> Option<Element<'_, Message>> {
+        let search = self.find.as_ref()?;
+        let count = self.fragments[self.fragment_index].match_count(search);
+
+        Some(
+            row![
+                text_input("Find", &search.query)
+                    .id(find_id())
+                    .on_input(Message::FindChanged)
+                    .on_submit(Message::FindNext)
+                    .width(200)
+                    .size(14),
+                text_input("Replace with", &search.replacement)
+                    .on_input(Message::ReplaceChanged)
+                    .on_submit(Message::ReplaceAll)
+                    .width(200)
+                    .size(14),
+                checkbox("Match case", search.case_sensitive)
+                    .on_toggle(Message::CaseSensitiveToggled)
+                    .text_size(14),
+                tooltip(
+                    checkbox("In selection", self.replace_scope.is_some())
+                        .on_toggle(Message::InSelectionToggled)
+                        .text_size(14),
+                    "Only replace within the text selected when checked",
+                    tooltip::Position::Bottom,
+                )
+                .style(theme::Container::Box),
+                tooltip(
+                    checkbox("Keep highlighted", self.pin_search)
+                        .on_toggle(Message::PinSearchToggled)
+                        .text_size(14),
+                    "Keep the matches highlighted once the bar is closed, \
+                     until Escape",
+                    tooltip::Position::Bottom,
+                )
+                .style(theme::Container::Box),
+                text(match count {
+                    1 => String::from("1 match"),
+                    count => format!("{count} matches"),
+                })
+                .size(14),
+                horizontal_space(),
+                button(text("Next").size(14)).on_press(Message::FindNext),
+                button(text("All Tabs").size(14))
+                    .on_press(Message::SearchAll(search.query.clone())),
+                button(
+                    text(if self.replace_scope.is_some() {
+                        "Replace in Selection"
+                    } else {
+                        "Replace All"
+                    })
+                    .size(14),
+                )
+                .on_press(Message::ReplaceAll),
+                button(text("Close").size(14))
+                    .style(theme::Button::Text)
+                    .on_press(Message::CloseFind),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .into(),
+        )
+    }
+
+    /// Lists the lines of every fragment the last search of all tabs found.
+    fn search_results(&self) -> Option<Element<'_, Message>> {
+        let results = self.search_results.as_ref()?;
+
+        let hits = results.hits.iter().map(|hit| {
+            let name = self
+                .fragments
+                .get(hit.fragment)
+                .map(FragmentContent::name)
+                .unwrap_or_default();
+
+            button(
+                row![
+                    text(format!("{name}:{}", hit.line + 1))
+                        .size(14)
+                        .width(200),
+                    text(&hit.preview).size(14),
+                ]
+                .spacing(10),
+            )
+            .width(Length::Fill)
+            .style(theme::Button::Text)
+            .on_press(Message::JumpToResult {
+                fragment: hit.fragment,
+                line: hit.line,
+            })
+            .into()
+        });
+
+        let summary = match (results.total, results.fragments) {
+            (0, _) => format!("Could not find {}", results.search.query),
+            (1, _) => format!("1 line matches {}", results.search.query),
+            (total, 1) => {
+                format!("{total} lines in 1 tab match {}", results.search.query)
+            }
+            (total, fragments) => format!(
+                "{total} lines in {fragments} tabs match {}",
+                results.search.query
+            ),
+        };
+        let hidden = results.total.saturating_sub(results.hits.len());
+
+        Some(
+            container(
+                column![
+                    row![
+                        text(summary).size(14),
+                        horizontal_space(),
+                        button(text("Close").size(14))
+                            .style(theme::Button::Text)
+                            .on_press(Message::CloseSearchResults),
+                    ]
+                    .align_items(Alignment::Center),
+                    scrollable(Column::with_children(hits)),
+                ]
+                .push_maybe((hidden > 0).then(|| {
+                    text(format!(
+                        "{hidden} more lines are not listed, search for \
+                         something longer to narrow them down"
+                    ))
+                    .size(12)
+                }))
+                .spacing(5),
+            )
+            .width(Length::Fill)
+            .max_height(240)
+            .padding(5)
+            .style(theme::Container::Box)
+            .into(),
+        )
+    }
+
+    /// Asks before Replace All makes more replacements than the preferences
+    /// allow without asking.
+    fn replace_prompt(&self, count: usize) -> Element<'_, Message> {
+        let query = self
+            .find
+            .as_ref()
+            .map_or("", |search| search.query.as_str());
+
+        container(
+            column![
+                text(format!(
+                    "Replace {count} occurrences of {query}{}?",
+                    if self.replace_scope.is_some() {
+                        " in the selection"
+                    } else {
+                        ""
+                    }
+                ))
+                .size(20),
+                row![
+                    button("Replace All")
+                        .on_press(Message::ConfirmReplaceAll)
+                        .style(theme::Button::Destructive),
+                    horizontal_space(),
+                    button("Cancel")
+                        .on_press(Message::CancelReplaceAll)
+                        .style(theme::Button::Secondary),
+                ]
+                .spacing(10),
+            ]
+            .spacing(15),
+        )
+        .width(400)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+
+    /// Asks before Close All closes more tabs than the preferences allow
+    /// without asking.
+    fn close_all_prompt(&self, count: usize) -> Element<'_, Message> {
+        container(
+            column![
+                text(format!("Close all {count} tabs?")).size(20),
+                row![
+                    button("Close All")
+                        .on_press(Message::ConfirmCloseAll)
+                        .style(theme::Button::Destructive),
+                    horizontal_space(),
+                    button("Cancel")
+                        .on_press(Message::CancelCloseAll)
+                        .style(theme::Button::Secondary),
+                ]
+                .spacing(10),
+            ]
+            .spacing(15),
+        )
+        .width(400)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+
+    /// Asks whether Save All really saves so many files.
+    fn save_all_prompt(&self, count: usize) -> Element<'_, Message> {
+        container(
+            column![
+                text(format!("Save all {count} files?")).size(20),
+                row![
+                    button("Save All").on_press(Message::ConfirmSaveAll),
+                    horizontal_space(),
+                    button("Cancel")
+                        .on_press(Message::CancelSaveAll)
+                        .style(theme::Button::Secondary),
+                ]
+                .spacing(10),
+            ]
+            .spacing(15),
+        )
+        .width(400)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+
+    /// Asks what to do with the changes of the dirty tabs before exiting.
+    fn exit_prompt(&self, count: usize) -> Element<'_, Message> {
+        container(
+            column![
+                text(if count == 1 {
+                    String::from("You have unsaved changes in 1 file")
+                } else {
+                    format!("You have unsaved changes in {count} files")
+                })
+                .size(20),
+                row![
+                    button("Save All").on_press(Message::SaveAllAndExit),
+                    button("Discard")
+                        .on_press(Message::DiscardAndExit)
+                        .style(theme::Button::Destructive),
+                    horizontal_space(),
+                    button("Cancel")
+                        .on_press(Message::CancelExit)
+                        .style(theme::Button::Secondary),
+                ]
+                .spacing(10),
+            ]
+            .spacing(15),
+        )
+        .width(400)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+
+    /// Asks what to do with the changes of a tab that is being closed.
+    fn close_prompt(&self, index: usize) -> Element<'_, Message> {
+        let name = self.fragments[index].name();
+
+        container(
+            column![
+                text(format!("{name} has unsaved changes")).size(20),
+                row![
+                    button("Save").on_press(Message::SaveAndClose(index)),
+                    button("Discard")
+                        .on_press(Message::TabClosed(index))
+                        .style(theme::Button::Destructive),
+                    horizontal_space(),
+                    button("Cancel")
+                        .on_press(Message::CancelClose)
+                        .style(theme::Button::Secondary),
+                ]
+                .spacing(10),
+            ]
+            .spacing(15),
+        )
+        .width(400)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+
+    /// Asks whether to delete the whole text of the current file.
+    fn empty_prompt(&self) -> Element<'_, Message> {
+        let name = self.fragments[self.fragment_index].name();
+
+        container(
+            column![
+                text(format!("Delete all of {name}?")).size(20),
+                row![
+                    button("Delete")
+                        .on_press(Message::ConfirmEmptying)
+                        .style(theme::Button::Destructive),
+                    horizontal_space(),
+                    button("Cancel")
+                        .on_press(Message::CancelEmptying)
+                        .style(theme::Button::Secondary),
+                ]
+                .spacing(10),
+            ]
+            .spacing(15),
+        )
+        .width(400)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+
+    /// Asks whether to throw away the changes of a tab to reload its file.
+    fn reload_prompt(&self, index: usize) -> Element<'_, Message> {
+        let name = self.fragments[index].name();
+
+        container(
+            column![
+                text(format!("{name} has unsaved changes")).size(20),
+                text("Reloading it from disk discards them."),
+                row![
+                    button("Reload")
+                        .on_press(Message::ReloadFromDisk(index))
+                        .style(theme::Button::Destructive),
+                    horizontal_space(),
+                    button("Cancel")
+                        .on_press(Message::CancelReload)
+                        .style(theme::Button::Secondary),
+                ]
+                .spacing(10),
+            ]
+            .spacing(15),
+        )
+        .width(400)
+        .padding(20)
+        .style(theme::Container::Box)
+        .into()
+    }
+
+    /// Tells whether the current file has unsaved changes, is being saved,
+    /// or is saved along with every other tab.
+    fn save_status(&self) -> Option<Element<'_, Message>> {
+        let fragment = &self.fragments[self.fragment_index];
+
+        let status = if fragment.is_saving {
+            String::from("Saving...")
+        } else if fragment.is_dirty {
+            String::from("● Unsaved changes")
+        } else if fragment.file.is_none() {
+            return None;
+        } else {
+            match self.fragments.iter().filter(|f| f.is_dirty).count() {
+                0 => String::from("All saved"),
+                1 => String::from("Saved, 1 other tab has changes"),
+                others => format!("Saved, {others} other tabs have changes"),
+            }
+        };
+
+        Some(text(status).into())
+    }
+
+    /// Changes the remembered settings of the current file, if it has one,
+    /// and writes them out.
+    fn remember(
+        &mut self,
+        f: impl FnOnce(&mut file_settings::FileSettings),
+    ) -> Command<Message> {
+        let (Some(file), Some(path)) = (
+            &self.fragments[self.fragment_index].file,
+            file_settings::Store::path(),
+        ) else {
+            return Command::none();
+        };
+
+        self.file_settings.update(file, f);
+
+        Command::perform(
+            write_config_file(path, self.file_settings.to_text()),
+            Message::FileSettingsWritten,
+        )
+    }
+
+    /// Writes the files of the tabs out as the session to reopen, leaving
+    /// out the tabs without one, in the background once the last write is
+    /// done.
+    fn remember_session(&mut self) -> Command<Message> {
+        let Some(path) = Session::path() else {
+            return Command::none();
+        };
+
+        if self.session_write != Rewrite::Done {
+            self.session_write = Rewrite::Outdated;
+
+            return Command::none();
+        }
+
+        let session = Session {
+            files: self
+                .fragments
+                .iter()
+                .filter_map(|fragment| fragment.file.clone())
+                .collect(),
+            active: self.fragments[self.fragment_index].file.clone(),
+        };
+
+        self.session_write = Rewrite::Writing;
+
+        Command::perform(
+            write_config_file(path, session.to_text()),
+            Message::SessionWritten,
+        )
+    }
+
+    /// Writes the undo history of the files saved lately in the
+    /// background, once the last write is done if there is one.
+    fn write_undo_store(&mut self) -> Command<Message> {
+        let Some(path) = undo_store::Store::path() else {
+            return Command::none();
+        };
+
+        if self.undo_store_write != Rewrite::Done {
+            self.undo_store_write = Rewrite::Outdated;
+
+            return Command::none();
+        }
+
+        self.undo_store_write = Rewrite::Writing;
+
+        Command::perform(
+            write_config_file(path, self.undo_store.to_text()),
+            Message::UndoStoreWritten,
+        )
+    }
+
+    /// Puts the file of the current tab first in the recent files.
+    fn remember_recent(&mut self, index: usize) -> Command<Message> {
+        let Some(file) = &self.fragments[index].file else {
+            return Command::none();
+        };
+
+        self.recent.add(file);
+
+        self.write_recent()
+    }
+
+    /// Writes the recent files in the background, once the last write is
+    /// done if there is one.
+    fn write_recent(&mut self) -> Command<Message> {
+        let Some(path) = Recent::path() else {
+            return Command::none();
+        };
+
+        if self.recent_write != Rewrite::Done {
+            self.recent_write = Rewrite::Outdated;
+
+            return Command::none();
+        }
+
+        self.recent_write = Rewrite::Writing;
+
+        Command::perform(
+            write_config_file(path, self.recent.to_text()),
+            Message::RecentWritten,
+        )
+    }
+
+    /// Saves the fragment at the index to its file, or asks where to if it
+    /// has none or `save_as` is set.
+    fn save(&mut self, index: usize, save_as: bool) -> Command<Message> {
+        let fragment = &mut self.fragments[index];
+
+        if fragment.is_loading {
+            return Command::none();
+        }
+
+        fragment.is_loading = true;
+        fragment.is_saving = true;
+
+        let buffer = editing::text(&fragment.content);
+
+        // A file saved as-is keeps the final newline it was loaded with
+        let (text, trailing_newline) = if fragment.save_as_is {
+            (buffer.clone(), TrailingNewline::Preserve)
+        } else {
+            (
+                self.preferences.saved_text(&buffer),
+                self.preferences.trailing_newline,
+            )
+        };
+        let file = fragment
+            .line_ending
+            .apply(trailing_newline.apply(&text, fragment.final_newline));
+        let contents = fragment.encoding.encode(&file);
+        let path = if save_as { None } else { fragment.file.clone() };
+        let id = fragment.id;
+        let saved = Arc::new(SavedText {
+            buffer,
+            written: text,
+            file,
+        });
+
+        self.in_flight(
+            1,
+            save_file(path, contents, self.preferences.create_directories),
+            move |result| Message::FileSaved(id, saved, result),
+        )
+    }
+
+    /// Asks where to save the next untitled tab left by Save All, passing
+    /// over the ones that no longer need it.
+    fn save_next_untitled(&mut self) -> Command<Message> {
+        while let Some(&id) = self.save_all_queue.front() {
+            let index = self.position(id).filter(|index| {
+                let f = &self.fragments[*index];
+
+                f.is_dirty && f.file.is_none() && !f.is_loading
+            });
+
+            if let Some(index) = index {
+                self.activate(index);
+
+                return self.save(index, false);
+            }
+
+            self.save_all_queue.pop_front();
+        }
+
+        Command::none()
+    }
+
+    /// Saves the first tab with changes, or closes the window once there
+    /// are none left.
+    fn save_next_or_exit(&mut self) -> Command<Message> {
+        match self.fragments.iter().position(|f| f.is_dirty) {
+            Some(index) => {
+                self.activate(index);
+
+                self.update(Message::SaveFile)
+            }
+            None => window::close(window::Id::MAIN),
+        }
+    }
+
+    /// Closes the tabs in order, stopping at the first dirty one to ask
+    /// about its changes, until a single blank tab is left.
+    fn close_next_or_finish(&mut self) -> Command<Message> {
+        while let Some(first) = self.fragments.first() {
+            if first.is_dirty {
+                self.activate(0);
+                self.closing = Some(0);
+
+                return Command::none();
+            }
+
+            self.fragments.remove(0);
+            self.forget_tab(0);
+            self.fragment_index = self.fragment_index.saturating_sub(1);
+        }
+
+        self.closing_all = false;
+        self.fragments.push(FragmentContent::default());
+        self.fragment_index = 0;
+
+        Command::batch([self.remember_session(), self.blame_current_line()])
+    }
+
+    /// Closes the find bar, keeping its search highlighted if asked to.
+    fn close_find(&mut self) {
+        self.replace_scope = None;
+
+        let Some(search) = self.find.take() else {
+            return;
+        };
+
+        if self.pin_search && !search.query.is_empty() {
+            self.pinned_search = Some(search);
+        }
+    }
+
+    /// Switches to the tab of the file, or opens it in the current tab if
+    /// that is blank and in a new one otherwise.
+    fn open_path(&mut self, path: PathBuf) -> Command<Message> {
+        if let Some(index) = self
+            .fragments
+            .iter()
+            .position(|fragment| fragment.file.as_ref() == Some(&path))
+        {
+            self.activate(index);
+
+            return self.blame_current_line();
+        }
+
+        if self.fragments[self.fragment_index].is_blank() {
+            self.fragments[self.fragment_index].is_loading = true;
+        } else {
+            self.open_tab(FragmentContent {
+                is_loading: true,
+                ..FragmentContent::default()
+            });
+        }
+
+        self.load(path)
+    }
+
+    /// Loads the file into the current fragment, which the result goes to
+    /// even if another one is current by then.
+    fn load(&mut self, path: PathBuf) -> Command<Message> {
+        let id = self.fragments[self.fragment_index].id;
+
+        self.in_flight(1, load_file(path), move |result| {
+            Message::FileOpened(id, result)
+        })
+    }
+
+    /// Returns the index of the fragment, if it is still open.
+    fn position(&self, id: FragmentId) -> Option<usize> {
+        self.fragments.iter().position(|fragment| fragment.id == id)
+    }
+
+    /// Whether a prompt, palette or list is shown over the editor.
+    fn has_overlay(&self) -> bool {
+        self.confirming_exit.is_some()
+            || self.closing.is_some()
+            || self.reloading.is_some()
+            || self.confirming_close_all.is_some()
+            || self.confirming_save_all.is_some()
+            || self.emptying.is_some()
+            || self.replacing.is_some()
+            || self.command_palette.is_some()
+            || self.theme_palette.is_some()
+            || self.show_edit_timeline
+            || self.show_shortcuts
+    }
+
+    /// Switches to the given tab.
+    fn activate(&mut self, index: usize) {
+        let previous = self.fragment_index;
+
+        if let Some(fragment) = self.fragments.get_mut(self.fragment_index) {
+            fragment.last_active = Some(Instant::now());
+        }
+
+        // A tab picked while split takes the place of the current one
+        if let Some((left, right)) = &mut self.split {
+            if index != *left && index != *right {
+                if *left == self.fragment_index {
+                    *left = index;
+                } else {
+                    *right = index;
+                }
+            }
+        }
+
+        self.fragment_index = index;
+        self.renaming = None;
+        self.completion = None;
+
+        // The selection Replace All was limited to is in the tab left
+        if index != previous {
+            self.replace_scope = None;
+        }
+    }
+
+    /// Lets the split and the search results forget a closed tab, shifting
+    /// the ones after it.
+    fn forget_tab(&mut self, index: usize) {
+        let shifted = |tab: usize| if tab > index { tab - 1 } else { tab };
+
+        self.split = self.split.and_then(|(left, right)| {
+            (left != index && right != index)
+                .then(|| (shifted(left), shifted(right)))
+        });
+
+        if let Some(results) = &mut self.search_results {
+            results.forget(index);
+        }
+    }
+
+    /// Adds a tab and switches to it, first closing the least recently used
+    /// clean tab if that would go over the limit.
+    fn open_tab(&mut self, fragment: FragmentContent) {
+        let current = self.fragment_index;
+
+        let oldest = self
+            .preferences
+            .max_tabs
+            .filter(|max_tabs| self.fragments.len() >= *max_tabs)
+            .and_then(|_| {
+                self.fragments
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, fragment)| {
+                        *index != current
+                            && !fragment.is_dirty
+                            && !fragment.is_loading
+                            && !fragment.follows_stdin
+                    })
+                    .min_by_key(|(_, fragment)| fragment.last_active)
+                    .map(|(index, _)| index)
+            });
+
+        if let Some(index) = oldest {
+            self.fragments.remove(index);
+            self.forget_tab(index);
+
+            if index < current {
+                self.fragment_index -= 1;
+            }
+        }
+
+        self.fragments.push(fragment);
+        self.activate(self.fragments.len() - 1);
+    }
+
+    /// Returns what pressing the toolbar button does, unless it is disabled.
+    fn toolbar_press(&self, toolbar_action: ToolbarAction) -> Option<Message> {
+        let fragment = &self.fragments[self.fragment_index];
+        let read_only = fragment.refuses_edits();
+        let selected = fragment.content.selection().is_some();
+
+        match toolbar_action {
+            ToolbarAction::New => Some(Message::NewFile),
+            ToolbarAction::Open => {
+                (!fragment.is_loading).then_some(Message::OpenFile)
+            }
+            ToolbarAction::Save => (fragment.is_dirty && !fragment.read_only)
+                .then_some(Message::SaveFile),
+            ToolbarAction::SaveAs => {
+                (!fragment.is_loading).then_some(Message::SaveAsFile)
+            }
+            ToolbarAction::SaveAll => self
+                .fragments
+                .iter()
+                .any(|fragment| fragment.is_dirty && !fragment.read_only)
+                .then_some(Message::SaveAll),
+            ToolbarAction::Undo => {
+                fragment.history.can_undo().then_some(Message::Undo)
+            }
+            ToolbarAction::Redo => {
+                fragment.history.can_redo().then_some(Message::Redo)
+            }
+            ToolbarAction::SelectAll => Some(Message::SelectAll),
+            ToolbarAction::Cut => {
+                (selected && !read_only).then_some(Message::Cut)
+            }
+            ToolbarAction::Copy => selected.then_some(Message::Copy),
+            ToolbarAction::Paste => (!read_only).then_some(Message::Paste),
+            ToolbarAction::Reload => (fragment.file.is_some()
+                && !fragment.is_loading)
+                .then_some(Message::ReloadFile),
+            ToolbarAction::NewTab => Some(Message::TabNew),
+            ToolbarAction::DuplicateTab => {
+                (!fragment.is_loading).then_some(Message::DuplicateTab)
+            }
+            ToolbarAction::CloseAll => Some(Message::CloseAllTabs),
+            ToolbarAction::Record if self.recording.is_some() => {
+                Some(Message::StopRecording)
+            }
+            ToolbarAction::Record => Some(Message::StartRecording),
+            ToolbarAction::Play => (self.recording.is_none()
+                && self.playback.is_empty()
+                && !self.recorded_macro.is_empty())
+            .then_some(Message::PlayMacro),
+        }
+    }
+
+    fn toolbar_action(
+        &self,
+        toolbar_action: ToolbarAction,
+    ) -> Element<'_, Message> {
+        let press = self.toolbar_press(toolbar_action);
+
+        match toolbar_action {
+            ToolbarAction::New => {
+                action(self.icon(new_icon, "New"), "New file", press)
+            }
+            ToolbarAction::Open => {
+                action(self.icon(open_icon, "Open"), "Open file", press)
+            }
+            ToolbarAction::Save => {
+                action(self.icon(save_icon, "Save"), "Save file", press)
+            }
+            ToolbarAction::SaveAs => action(
+                self.icon(save_as_icon, "Save As"),
+                "Save as a new file",
+                press,
+            ),
+            ToolbarAction::SaveAll => action(
+                self.icon(save_all_icon, "Save All"),
+                "Save every tab with changes",
+                press,
+            ),
+            ToolbarAction::Undo => {
+                action(self.icon(undo_icon, "Undo"), "Undo (Ctrl+Z)", press)
+            }
+            ToolbarAction::Redo => action(
+                self.icon(redo_icon, "Redo"),
+                "Redo (Ctrl+Shift+Z)",
+                press,
+            ),
+            ToolbarAction::SelectAll => action(
+                self.icon(select_all_icon, "All"),
+                "Select all (Ctrl+A)",
+                press,
+            ),
+            ToolbarAction::Cut => {
+                action(self.icon(cut_icon, "Cut"), "Cut (Ctrl+X)", press)
+            }
+            ToolbarAction::Copy => {
+                action(self.icon(copy_icon, "Copy"), "Copy (Ctrl+C)", press)
+            }
+            ToolbarAction::Paste => {
+                action(self.icon(paste_icon, "Paste"), "Paste (Ctrl+V)", press)
+            }
+            ToolbarAction::Reload => action(
+                self.icon(reload_icon, "Reload"),
+                "Reload from disk",
+                press,
+            ),
+            ToolbarAction::NewTab => {
+                action(self.icon(new_tab_icon, "Tab"), "New Tab", press)
+            }
+            ToolbarAction::DuplicateTab => action(
+                self.icon(duplicate_tab_icon, "Copy"),
+                "Duplicate the tab into an unsaved copy",
+                press,
+            ),
+            ToolbarAction::CloseAll => action(
+                self.icon(close_all_icon, "Close"),
+                "Close all tabs (Ctrl+Alt+W)",
+                press,
+            ),
+            ToolbarAction::Record if self.recording.is_some() => action(
+                self.icon(stop_icon, "Stop"),
+                "Stop recording (Ctrl+Shift+R)",
+                press,
+            ),
+            ToolbarAction::Record => action(
+                self.icon(record_icon, "Rec"),
+                "Record macro (Ctrl+Shift+R)",
+                press,
+            ),
+            ToolbarAction::Play => row![
+                action(
+                    self.icon(play_icon, "Play"),
+                    "Play macro (Ctrl+Shift+E)",
+                    press
+                ),
+                text_input("x1", &self.macro_repeat)
+                    .on_input(Message::MacroRepeatChanged)
+                    .width(40)
+                    .size(14),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .into(),
+        }
+    }
+
+    /// Loads or saves the given number of files in the background, counting
+    /// them as in flight until the result comes back.
+    fn in_flight<T: Send + 'static>(
+        &mut self,
+        count: usize,
+        future: impl Future<Output = T> + Send + 'static,
+        message: impl FnOnce(T) -> Message + Send + 'static,
+    ) -> Command<Message> {
+        self.operations += count;
+
+        finishing(count, future, message)
+    }
+
+    /// Offers the files for the path being typed before the caret, from the
+    /// folder of the current file.
+    fn complete_path(&self) -> Option<Completion> {
+        let fragment = &self.fragments[self.fragment_index];
+        let folder = fragment.file.as_deref()?.parent()?;
+        let (line, column) = fragment.content.cursor_position();
+        let text = fragment.content.line(line)?;
+
+        Completion::new(folder, text.get(..column)?)
+    }
+
+    /// Keeps the caret in sight while the lines are numbered, as the editor
+    /// then leaves scrolling to the scrollable around it.
+    fn reveal_cursor(&self) -> Command<Message> {
+        if !self.preferences.show_line_numbers {
+            return Command::none();
+        }
+
+        let (line, _) =
+            self.fragments[self.fragment_index].content.cursor_position();
+
+        Command::widget(line_numbers::reveal(self.fragment_index, line))
+    }
+
+    /// Blames the line under the caret, unless it is already blamed.
+    fn blame_current_line(&mut self) -> Command<Message> {
+        let fragment = &mut self.fragments[self.fragment_index];
+        let line = fragment.content.cursor_position().0;
+
+        if !self.preferences.show_blame || fragment.blamed_line == Some(line) {
+            return Command::none();
+        }
+
+        let Some(path) = fragment.file.clone() else {
+            return Command::none();
+        };
+
+        fragment.blamed_line = Some(line);
+        fragment.blame = None;
+
+        Command::perform(
+            git::blame(path.clone(), line, editing::text(&fragment.content)),
+            move |blame| Message::BlameLoaded(path, line, blame),
+        )
+    }
+
+    /// Falls back to a text label when the icon font could not be loaded,
+    /// since its codepoints would otherwise render as boxes.
+    fn icon<'a>(
+        &self,
+        icon: fn() -> Element<'a, Message>,
+        fallback: &'a str,
+    ) -> Element<'a, Message> {
+        if self.has_icon_font {
+            icon()
+        } else {
+            text(fallback).into()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadedFile {
+    path: PathBuf,
+    contents: Arc<String>,
+    link_target: Option<PathBuf>,
+    /// The indentation the `.editorconfig` files set for the file, if any.
+    editorconfig: Option<Indent>,
+    encoding: Encoding,
+    decode_errors: usize,
+    likely_encoding: Option<Encoding>,
+    modified: Option<SystemTime>,
+    size: Option<u64>,
+    /// Whether the file system refuses writes to the file.
+    read_only: bool,
+}
+
+/// The text of a tab a save started from, and the text written from it,
+/// which trailing whitespace may have been trimmed off.
+#[derive(Debug)]
+pub struct SavedText {
+    buffer: String,
+    written: String,
+    /// The text of the file, with its final newline and line endings.
+    file: String,
+}
+
+/// A file picked to be opened, and how loading it went.
+type PickedFile = (PathBuf, Result<LoadedFile, Error>);
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    DialogClosed,
+    IoError(io::ErrorKind),
+}
+
+fn default_file() -> PathBuf {
+    PathBuf::from(format!("{}/src/main.rs", env!("CARGO_MANIFEST_DIR")))
+}
+
+/// Returns the path of the file the last picked syntax theme is kept in.
+fn theme_path() -> Option<PathBuf> {
+    Some(Preferences::path()?.with_file_name("theme"))
+}
+
+/// Reads the last picked syntax theme, if one was kept and is known.
+fn load_theme() -> Option<highlighter::Theme> {
+    let name = fs::read_to_string(theme_path()?).ok()?;
+
+    highlighter::Theme::ALL
+        .iter()
+        .find(|theme| theme.to_string() == name.trim())
+        .copied()
+}
+
+async fn open_file() -> Result<LoadedFile, Error> {
+    let picked_file = rfd::AsyncFileDialog::new()
+        .set_title("Open a text file...")
+        .pick_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+
+    load_file(picked_file.path().to_owned()).await
+}
+
+/// Asks for any number of files and loads each of them, so that one that
+/// cannot be read does not keep the others from opening.
+async fn open_files() -> Result<Vec<PickedFile>, Error> {
+    let picked_files = rfd::AsyncFileDialog::new()
+        .set_title("Open text files...")
+        .pick_files()
+        .await
+        .ok_or(Error::DialogClosed)?;
+
+    load_files(
+        picked_files
+            .iter()
+            .map(|picked_file| picked_file.path().to_owned())
+            .collect(),
+    )
+    .await
+}
+
+async fn load_files(paths: Vec<PathBuf>) -> Result<Vec<PickedFile>, Error> {
+    let mut files = Vec::with_capacity(paths.len());
+
+    for path in paths {
+        files.push((path.clone(), load_file(path).await));
+    }
+
+    Ok(files)
+}
+
+async fn load_file(path: PathBuf) -> Result<LoadedFile, Error> {
+    load_file_as(path, None).await
+}
+
+/// Loads a file with the given encoding, or with the one its byte order mark
+/// stands for, or the one picked for it before, or as UTF-8 unless it is not
+/// valid as such.
+async fn load_file_as(
+    path: PathBuf,
+    encoding: Option<Encoding>,
+) -> Result<LoadedFile, Error> {
+    let metadata = tokio::fs::symlink_metadata(&path)
+        .await
+        .map_err(|error| Error::IoError(error.kind()))?;
+
+    let link_target = if metadata.is_symlink() {
+        tokio::fs::canonicalize(&path).await.ok()
+    } else {
+        None
+    };
+
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|error| Error::IoError(error.kind()))?;
+
+    let encoding = encoding
+        .or_else(|| Encoding::from_bom(&bytes))
+        .or_else(|| file_settings::Store::encoding_of(&path));
+
+    // Invalid UTF-8 is best read in whatever encoding it likely is instead
+    let encoding = encoding.unwrap_or_else(|| {
+        if std::str::from_utf8(&bytes).is_ok() {
+            Encoding::Utf8
+        } else {
+            Encoding::guess(&bytes)
+        }
+    });
+    let (contents, decode_errors) = encoding.decode(&bytes);
+    let likely_encoding = (decode_errors > 0)
+        .then(|| Encoding::guess(&bytes))
+        .filter(|guess| *guess != encoding);
+
+    let editorconfig = Indent::from_editorconfig(&path);
+    let metadata = tokio::fs::metadata(&path).await.ok();
+    let modified = metadata
+        .as_ref()
+        .and_then(|metadata| metadata.modified().ok());
+    let size = metadata.as_ref().map(|metadata| metadata.len());
+    let read_only = metadata
+        .is_some_and(|metadata| metadata.permissions().readonly());
+
+    Ok(LoadedFile {
+        path,
+        contents: Arc::new(contents),
+        link_target,
+        editorconfig,
+        encoding,
+        decode_errors,
+        likely_encoding,
+        modified,
+        size,
+        read_only,
+    })
+}
+
+/// Reads when each of the files was last modified, if it still exists.
+async fn modification_times(
+    paths: Vec<PathBuf>,
+) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let mut times = Vec::with_capacity(paths.len());
+
+    for path in paths {
+        let modified = tokio::fs::metadata(&path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .ok();
+
+        times.push((path, modified));
+    }
+
+    times
+}
+
+/// Opens the preferences file, creating it from the template first if needed.
+async fn open_preferences(path: PathBuf) -> Result<LoadedFile, Error> {
+    if tokio::fs::metadata(&path).await.is_err() {
+        if let Some(directory) = path.parent() {
+            tokio::fs::create_dir_all(directory)
+                .await
+                .map_err(|error| Error::IoError(error.kind()))?;
+        }
+
+        tokio::fs::write(&path, Preferences::TEMPLATE)
+            .await
+            .map_err(|error| Error::IoError(error.kind()))?;
+    }
+
+    load_file(path).await
+}
+
+/// Sets a key of the preferences file, keeping the rest of the file as the
+/// user wrote it.
+fn write_preference(key: &str, value: &str) -> Command<Message> {
+    let Some(path) = Preferences::path() else {
+        return Command::none();
+    };
+
+    let text = fs::read_to_string(&path)
+        .unwrap_or_else(|_| Preferences::TEMPLATE.to_owned());
+
+    Command::perform(
+        write_config_file(path, Preferences::with_value(&text, key, value)),
+        Message::PreferencesWritten,
+    )
+}
+
+/// Writes a file next to the preferences, creating their folder first.
+async fn write_config_file(
+    path: PathBuf,
     contents: String,
+) -> Result<(), Error> {
+    if let Some(directory) = path.parent() {
+        tokio::fs::create_dir_all(directory)
+            .await
+            .map_err(|error| Error::IoError(error.kind()))?;
+    }
+
+    tokio::fs::write(&path, contents)
+        .await
+        .map_err(|error| Error::IoError(error.kind()))
+}
+
+async fn confirm_apply_preferences() -> bool {
+    rfd::AsyncMessageDialog::new()
+        .set_title("Preferences saved")
+        .set_description("Apply the saved preferences now?")
+        .set_buttons(rfd::MessageButtons::YesNo)
+        .show()
+        .await
+        == rfd::MessageDialogResult::Yes
+}
+
+/// Saves to the given path, or to one picked in a dialog.
+///
+/// With `create_directories`, the user is offered to create the folders of
+/// the path that do not exist yet.
+async fn save_file(
+    path: Option<PathBuf>,
+    contents: Vec<u8>,
+    create_directories: bool,
 ) -> Result<PathBuf, Error> {
     let path = if let Some(path) = path {
         path
@@ -345,6 +6842,31 @@ async fn save_file(
             .ok_or(Error::DialogClosed)?
     };
 
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty() && !parent.exists());
+
+    if let Some(parent) = parent.filter(|_| create_directories) {
+        let confirmed = rfd::AsyncMessageDialog::new()
+            .set_title("Create folder?")
+            .set_description(format!(
+                "{} does not exist yet. Create it to save the file?",
+                parent.display()
+            ))
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .show()
+            .await
+            == rfd::MessageDialogResult::Yes;
+
+        if !confirmed {
+            return Err(Error::DialogClosed);
+        }
+
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|error| Error::IoError(error.kind()))?;
+    }
+
     tokio::fs::write(&path, contents)
         .await
         .map_err(|error| Error::IoError(error.kind()))?;
@@ -352,12 +6874,261 @@ async fn save_file(
     Ok(path)
 }
 
+/// Runs an external program until it exits.
+async fn run_tool(
+    program: String,
+    args: Vec<String>,
+) -> Result<ExitStatus, Error> {
+    tokio::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .status()
+        .await
+        .map_err(|error| Error::IoError(error.kind()))
+}
+
+/// Streams stdin line by line, like `tail -f`.
+fn follow_stdin() -> Subscription<Message> {
+    struct Stdin;
+
+    subscription::channel(
+        std::any::TypeId::of::<Stdin>(),
+        100,
+        |mut output| async move {
+            let mut lines = BufReader::new(tokio::io::stdin()).lines();
+
+            while let Ok(Some(line)) = lines.next_line().await {
+                let chunk = format!("{line}\n");
+
+                let _ = output.send(Message::StdinChunk(chunk)).await;
+            }
+
+            std::future::pending().await
+        },
+    )
+}
+
+fn welcome<'a>() -> Element<'a, Message> {
+    let hint = Preferences::path().map(|path| {
+        text(format!(
+            "Set startup = welcome, empty, default or open <path> in {} \
+             to choose what opens on launch",
+            path.display()
+        ))
+        .size(14)
+    });
+
+    container(
+        column![
+            text("Welcome").size(32),
+            row![
+                button("New file").on_press(Message::NewFile),
+                button("Open file...").on_press(Message::OpenFile),
+            ]
+            .spacing(10),
+        ]
+        .push_maybe(hint)
+        .spacing(20)
+        .align_items(Alignment::Center),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .center_x()
+    .center_y()
+    .into()
+}
+
+fn rename_id() -> text_input::Id {
+    text_input::Id::new("rename")
+}
+
+fn go_to_id() -> text_input::Id {
+    text_input::Id::new("go-to")
+}
+
+fn find_id() -> text_input::Id {
+    text_input::Id::new("find")
+}
+
+fn surround_id() -> text_input::Id {
+    text_input::Id::new("surround")
+}
+
+fn snippet_name_id() -> text_input::Id {
+    text_input::Id::new("snippet-name")
+}
+
+fn command_palette_id() -> text_input::Id {
+    text_input::Id::new("command-palette")
+}
+
+fn command_list_id() -> scrollable::Id {
+    scrollable::Id::new("command-list")
+}
+
+/// Produces the message once the editor has been laid out again.
+///
+/// Edited lines can only be navigated after they are laid out, which happens
+/// right after the current update.
+fn after_layout(message: Message) -> Command<Message> {
+    Command::perform(async {}, move |()| message)
+}
+
+/// Runs a load or save whose result is handled once the given number of
+/// files is counted as done.
+fn finishing<T: Send + 'static>(
+    count: usize,
+    future: impl Future<Output = T> + Send + 'static,
+    message: impl FnOnce(T) -> Message + Send + 'static,
+) -> Command<Message> {
+    Command::perform(future, move |output| {
+        Message::OperationsFinished(count, Box::new(message(output)))
+    })
+}
+
+/// Selects between the positions once every line up to the end of the
+/// document is laid out.
+///
+/// Lines below anything shown so far have never been laid out either, so the
+/// caret is sent to the end first, which makes the next layout reach it.
+fn select_after_layout(
+    content: &mut text_editor::Content,
+    anchor: Position,
+    cursor: Position,
+) -> Command<Message> {
+    content.perform(text_editor::Action::Move(
+        text_editor::Motion::DocumentEnd,
+    ));
+
+    after_layout(Message::Select(anchor, cursor))
+}
+
+/// Paints the editor with the background of a syntax theme instead of the
+/// one of the app theme.
+struct SyntaxBackground(highlighter::Theme);
+
+impl SyntaxBackground {
+    fn color(&self) -> Color {
+        match self.0 {
+            highlighter::Theme::SolarizedDark => color!(0x002b36),
+            highlighter::Theme::Base16Mocha => color!(0x3b3228),
+            highlighter::Theme::Base16Ocean => color!(0x2b303b),
+            highlighter::Theme::Base16Eighties => color!(0x2d2d2d),
+            highlighter::Theme::InspiredGitHub => color!(0xffffff),
+        }
+    }
+
+    fn paint(
+        &self,
+        appearance: text_editor::Appearance,
+    ) -> text_editor::Appearance {
+        text_editor::Appearance {
+            background: self.color().into(),
+            ..appearance
+        }
+    }
+}
+
+impl text_editor::StyleSheet for SyntaxBackground {
+    type Style = Theme;
+
+    fn active(&self, theme: &Theme) -> text_editor::Appearance {
+        self.paint(theme.active(&theme::TextEditor::Default))
+    }
+
+    fn hovered(&self, theme: &Theme) -> text_editor::Appearance {
+        self.paint(theme.hovered(&theme::TextEditor::Default))
+    }
+
+    fn focused(&self, theme: &Theme) -> text_editor::Appearance {
+        self.paint(theme.focused(&theme::TextEditor::Default))
+    }
+
+    fn disabled(&self, theme: &Theme) -> text_editor::Appearance {
+        self.paint(theme.disabled(&theme::TextEditor::Default))
+    }
+
+    fn placeholder_color(&self, theme: &Theme) -> Color {
+        theme.placeholder_color(&theme::TextEditor::Default)
+    }
+
+    fn value_color(&self, theme: &Theme) -> Color {
+        theme.value_color(&theme::TextEditor::Default)
+    }
+
+    fn disabled_color(&self, theme: &Theme) -> Color {
+        theme.disabled_color(&theme::TextEditor::Default)
+    }
+
+    fn selection_color(&self, theme: &Theme) -> Color {
+        theme.selection_color(&theme::TextEditor::Default)
+    }
+}
+
+/// Shows a read-only editor as disabled, whether it has the focus or not.
+struct ReadOnly;
+
+impl text_editor::StyleSheet for ReadOnly {
+    type Style = Theme;
+
+    fn active(&self, theme: &Theme) -> text_editor::Appearance {
+        theme.disabled(&theme::TextEditor::Default)
+    }
+
+    fn hovered(&self, theme: &Theme) -> text_editor::Appearance {
+        theme.disabled(&theme::TextEditor::Default)
+    }
+
+    fn focused(&self, theme: &Theme) -> text_editor::Appearance {
+        theme.disabled(&theme::TextEditor::Default)
+    }
+
+    fn disabled(&self, theme: &Theme) -> text_editor::Appearance {
+        theme.disabled(&theme::TextEditor::Default)
+    }
+
+    fn placeholder_color(&self, theme: &Theme) -> Color {
+        theme.placeholder_color(&theme::TextEditor::Default)
+    }
+
+    fn value_color(&self, theme: &Theme) -> Color {
+        theme.value_color(&theme::TextEditor::Default)
+    }
+
+    fn disabled_color(&self, theme: &Theme) -> Color {
+        theme.disabled_color(&theme::TextEditor::Default)
+    }
+
+    fn selection_color(&self, theme: &Theme) -> Color {
+        theme.selection_color(&theme::TextEditor::Default)
+    }
+}
+
+/// Writes a number of bytes the way file managers do, like `12.4 KB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{size:.1} {}", UNITS[unit])
+}
+
 fn action<'a, Message: Clone + 'a>(
     content: impl Into<Element<'a, Message>>,
     label: &'a str,
     on_press: Option<Message>,
 ) -> Element<'a, Message> {
-    let action = button(container(content).width(30).center_x());
+    let action = button(container(content).padding([0, 7]).center_x());
 
     if let Some(on_press) = on_press {
         tooltip(
@@ -373,21 +7144,474 @@ fn action<'a, Message: Clone + 'a>(
 }
 
 fn new_icon<'a, Message>() -> Element<'a, Message> {
-    icon('\u{0e800}')
+    icon('\u{0f392}')
 }
 
 fn save_icon<'a, Message>() -> Element<'a, Message> {
-    icon('\u{0e801}')
+    icon('\u{0f525}')
+}
+
+fn save_as_icon<'a, Message>() -> Element<'a, Message> {
+    icon('\u{0f7e4}')
+}
+
+fn save_all_icon<'a, Message>() -> Element<'a, Message> {
+    icon('\u{0f527}')
+}
+
+fn undo_icon<'a, Message>() -> Element<'a, Message> {
+    icon('\u{0f117}')
+}
+
+fn redo_icon<'a, Message>() -> Element<'a, Message> {
+    icon('\u{0f116}')
+}
+
+fn reload_icon<'a, Message>() -> Element<'a, Message> {
+    icon('\u{0f130}')
 }
 
 fn new_tab_icon<'a, Message>() -> Element<'a, Message> {
-    icon('\u{0e801}')
+    icon('\u{0f4fd}')
+}
+
+fn duplicate_tab_icon<'a, Message>() -> Element<'a, Message> {
+    icon('\u{0f3c2}')
+}
+
+fn close_all_icon<'a, Message>() -> Element<'a, Message> {
+    icon('\u{0f629}')
+}
+
+fn select_all_icon<'a, Message>() -> Element<'a, Message> {
+    icon('\u{0f2e2}')
+}
+
+fn cut_icon<'a, Message>() -> Element<'a, Message> {
+    icon('\u{0f528}')
+}
+
+fn copy_icon<'a, Message>() -> Element<'a, Message> {
+    icon('\u{0f759}')
+}
+
+fn paste_icon<'a, Message>() -> Element<'a, Message> {
+    icon('\u{0f290}')
 }
 
 fn open_icon<'a, Message>() -> Element<'a, Message> {
-    icon('\u{0f115}')
+    icon('\u{0f3d8}')
+}
+
+fn record_icon<'a, Message>() -> Element<'a, Message> {
+    icon('\u{0f518}')
+}
+
+fn stop_icon<'a, Message>() -> Element<'a, Message> {
+    icon('\u{0f591}')
+}
+
+fn play_icon<'a, Message>() -> Element<'a, Message> {
+    icon('\u{0f4f3}')
 }
 
 fn icon<'a, Message>(codepoint: char) -> Element<'a, Message> {
-    text(codepoint).into()
+    text(codepoint).font(ICON_FONT).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor() -> Editor {
+        let preferences = Preferences {
+            startup: Startup::Empty,
+            ..Preferences::default()
+        };
+
+        Editor::new((Args::default(), preferences, None)).0
+    }
+
+    fn loaded(path: &str, contents: &str) -> LoadedFile {
+        LoadedFile {
+            path: PathBuf::from(path),
+            contents: Arc::new(contents.to_owned()),
+            link_target: None,
+            editorconfig: None,
+            encoding: Encoding::Utf8,
+            decode_errors: 0,
+            likely_encoding: None,
+            modified: None,
+            size: None,
+            read_only: false,
+        }
+    }
+
+    #[test]
+    fn load_lands_in_its_tab_after_the_tabs_change() {
+        let mut editor = editor();
+
+        editor.fragments = vec![
+            FragmentContent {
+                is_loading: true,
+                ..FragmentContent::default()
+            },
+            FragmentContent {
+                content: text_editor::Content::with_text("mine"),
+                is_dirty: true,
+                ..FragmentContent::default()
+            },
+        ];
+        let loading = editor.fragments[0].id;
+
+        // The dirty tab takes the place of the loading one, then another
+        // tab is switched to while the file is still loading
+        editor.fragment_index = 1;
+        let _ = editor.update(Message::MoveTabLeft);
+        editor.open_tab(FragmentContent::default());
+
+        let _ = editor.update(Message::FileOpened(
+            loading,
+            Ok(loaded("loaded.txt", "from disk")),
+        ));
+
+        let dirty = &editor.fragments[0];
+        let opened = &editor.fragments[1];
+
+        assert_eq!(editing::text(&dirty.content), "mine");
+        assert!(dirty.is_dirty);
+        assert_eq!(editing::text(&opened.content), "from disk");
+        assert_eq!(opened.file, Some(PathBuf::from("loaded.txt")));
+        assert!(!opened.is_loading);
+        assert_eq!(editor.fragment_index, 2);
+    }
+
+    #[test]
+    fn counts_update_after_an_edit() {
+        let mut editor = editor();
+
+        editor.fragments[0].content =
+            text_editor::Content::with_text("one two");
+        editor.fragments[0].disk_lines = Some(vec![String::from("one two")]);
+
+        let search = Search {
+            query: String::from("o"),
+            ..Search::default()
+        };
+        let fragment = &editor.fragments[0];
+
+        assert_eq!(fragment.stats().words, 2);
+        assert_eq!(fragment.match_count(&search), 2);
+        assert!(fragment.line_changes().is_empty());
+
+        // Moving the caret keeps what was counted
+        let _ = editor.update(Message::ActionPerformed(
+            text_editor::Action::Move(text_editor::Motion::DocumentEnd),
+        ));
+
+        assert!(editor.fragments[0].stats.get().is_some());
+
+        let paste = text_editor::Edit::Paste(Arc::new(String::from(" four")));
+        let _ = editor.update(Message::ActionPerformed(
+            text_editor::Action::Edit(paste),
+        ));
+
+        let fragment = &editor.fragments[0];
+
+        assert_eq!(editing::text(&fragment.content), "one two four");
+        assert_eq!(fragment.stats().words, 3);
+        assert_eq!(fragment.stats().chars, 12);
+        assert_eq!(fragment.match_count(&search), 3);
+        assert_eq!(fragment.line_changes().len(), 1);
+    }
+
+    #[test]
+    fn load_for_a_closed_tab_is_dropped() {
+        let mut editor = editor();
+
+        editor.fragments = vec![
+            FragmentContent::default(),
+            FragmentContent {
+                content: text_editor::Content::with_text("mine"),
+                is_dirty: true,
+                ..FragmentContent::default()
+            },
+        ];
+        let closed = editor.fragments[0].id;
+
+        let _ = editor.update(Message::TabClosed(0));
+        let _ = editor.update(Message::FileOpened(
+            closed,
+            Ok(loaded("loaded.txt", "from disk")),
+        ));
+
+        assert_eq!(editor.fragments.len(), 1);
+        assert_eq!(editing::text(&editor.fragments[0].content), "mine");
+        assert!(editor.fragments[0].is_dirty);
+    }
+
+    #[test]
+    fn results_forget_a_closed_tab() {
+        let mut editor = editor();
+
+        editor.fragments = vec![
+            FragmentContent {
+                content: text_editor::Content::with_text("one\none"),
+                ..FragmentContent::default()
+            },
+            FragmentContent {
+                content: text_editor::Content::with_text("none"),
+                ..FragmentContent::default()
+            },
+        ];
+        let search = Search {
+            query: String::from("one"),
+            ..Search::default()
+        };
+        editor.search_results =
+            Some(Results::new(search, ["one\none", "none"].into_iter()));
+
+        let _ = editor.update(Message::TabClosed(0));
+
+        let results = editor.search_results.as_ref().unwrap();
+
+        assert_eq!(results.total, 1);
+        assert_eq!(results.fragments, 1);
+        assert_eq!(results.hits.len(), 1);
+        assert_eq!(results.hits[0].fragment, 0);
+    }
+
+    #[test]
+    fn macro_plays_back_in_the_tab_it_started_in() {
+        let mut editor = editor();
+
+        editor.fragments =
+            vec![FragmentContent::default(), FragmentContent::default()];
+        editor.recorded_macro =
+            vec![text_editor::Action::Edit(text_editor::Edit::Insert('x'))];
+        editor.macro_repeat = String::from("3");
+
+        let _ = editor.update(Message::PlayMacro);
+        editor.activate(1);
+
+        while !editor.playback.is_empty() {
+            let _ = editor.update(Message::PlaybackStep);
+        }
+
+        assert_eq!(editing::text(&editor.fragments[0].content), "xxx");
+        assert_eq!(editing::text(&editor.fragments[1].content), "");
+    }
+
+    #[test]
+    fn macro_repeat_is_capped() {
+        let mut editor = editor();
+
+        let _ =
+            editor.update(Message::MacroRepeatChanged(String::from("1000000")));
+
+        assert_eq!(editor.macro_repeat, MAX_MACRO_REPEAT.to_string());
+    }
+
+    #[test]
+    fn typing_during_a_save_leaves_the_tab_dirty() {
+        let mut editor = editor();
+
+        editor.fragments[0].content = text_editor::Content::with_text("one");
+        editor.fragments[0].file = Some(PathBuf::from("saved.txt"));
+        editor.fragments[0].is_dirty = true;
+        let id = editor.fragments[0].id;
+
+        let _ = editor.save(0, false);
+        let _ = editor.update(Message::ActionPerformed(
+            text_editor::Action::Edit(text_editor::Edit::Insert('x')),
+        ));
+
+        let saved = Arc::new(SavedText {
+            buffer: String::from("one"),
+            written: String::from("one"),
+            file: String::from("one\n"),
+        });
+        let _ = editor.update(Message::FileSaved(
+            id,
+            saved,
+            Ok(PathBuf::from("saved.txt")),
+        ));
+
+        let fragment = &editor.fragments[0];
+
+        assert_eq!(editing::text(&fragment.content), "xone");
+        assert!(fragment.is_dirty);
+        assert_eq!(fragment.disk_lines, Some(vec![String::from("one")]));
+    }
+
+    #[test]
+    fn save_lands_in_its_tab_after_the_tabs_change() {
+        let mut editor = editor();
+
+        editor.fragments[0].content = text_editor::Content::with_text("one");
+        editor.fragments[0].file = Some(PathBuf::from("saved.txt"));
+        editor.fragments[0].is_dirty = true;
+        let id = editor.fragments[0].id;
+
+        let _ = editor.save(0, false);
+
+        // Another tab is opened before the saved one, then switched to
+        editor.open_tab(FragmentContent::default());
+        let _ = editor.update(Message::MoveTabLeft);
+
+        let saved = Arc::new(SavedText {
+            buffer: String::from("one"),
+            written: String::from("one"),
+            file: String::from("one\n"),
+        });
+        let _ = editor.update(Message::FileSaved(
+            id,
+            saved,
+            Ok(PathBuf::from("saved.txt")),
+        ));
+
+        let other = &editor.fragments[0];
+        let saved = &editor.fragments[1];
+
+        assert!(!saved.is_dirty);
+        assert!(!saved.is_saving);
+        assert_eq!(saved.disk_lines, Some(vec![String::from("one")]));
+        assert_eq!(other.file, None);
+        assert!(other.disk_lines.is_none());
+        assert_eq!(editor.fragment_index, 0);
+    }
+
+    #[test]
+    fn diff_and_stdin_tabs_refuse_edits() {
+        let mut editor = editor();
+
+        editor.fragments = vec![
+            FragmentContent {
+                content: text_editor::Content::with_text("diff"),
+                diff_of: Some(PathBuf::from("changed.txt")),
+                ..FragmentContent::default()
+            },
+            FragmentContent {
+                content: text_editor::Content::with_text("stdin"),
+                follows_stdin: true,
+                ..FragmentContent::default()
+            },
+        ];
+
+        for index in 0..2 {
+            editor.activate(index);
+            let _ = editor.update(Message::ChangeCase(editing::Case::Upper));
+            let _ = editor.update(Message::ActionPerformed(
+                text_editor::Action::Edit(text_editor::Edit::Insert('x')),
+            ));
+        }
+
+        assert_eq!(editing::text(&editor.fragments[0].content), "diff");
+        assert_eq!(editing::text(&editor.fragments[1].content), "stdin");
+        assert!(editor.fragments.iter().all(|fragment| !fragment.is_dirty));
+    }
+
+    #[test]
+    fn macro_stops_once_its_tab_is_read_only() {
+        let mut editor = editor();
+
+        editor.recorded_macro =
+            vec![text_editor::Action::Edit(text_editor::Edit::Insert('x'))];
+        editor.macro_repeat = String::from("3");
+
+        let _ = editor.update(Message::PlayMacro);
+        let _ = editor.update(Message::PlaybackStep);
+        let _ = editor.update(Message::ToggleReadOnly);
+        let _ = editor.update(Message::PlaybackStep);
+
+        let fragment = &editor.fragments[0];
+
+        assert_eq!(editing::text(&fragment.content), "x");
+        assert_eq!(fragment.edits.len(), 1);
+        assert!(editor.playback.is_empty());
+    }
+
+    #[test]
+    fn new_tab_can_open_a_file_straight_away() {
+        let mut editor = editor();
+
+        let _ = editor.update(Message::TabNew);
+
+        let last = editor.fragments.len() - 1;
+
+        assert_eq!(editor.fragment_index, last);
+        assert!(!editor.fragments[last].is_loading);
+        assert!(matches!(
+            editor.toolbar_press(ToolbarAction::Open),
+            Some(Message::OpenFile)
+        ));
+    }
+
+    #[test]
+    fn undo_goes_on_once_a_saved_file_reopens() {
+        let mut editor = editor();
+
+        editor.fragments[0].content = text_editor::Content::with_text("one");
+        editor.fragments[0].file = Some(PathBuf::from("undo.txt"));
+        editor.fragments[0].remember_undo(history::Kind::Other);
+        editor.fragments[0].content = text_editor::Content::with_text("two");
+        editor.fragments[0].is_dirty = true;
+        let id = editor.fragments[0].id;
+
+        let saved = Arc::new(SavedText {
+            buffer: String::from("two"),
+            written: String::from("two"),
+            file: String::from("two\n"),
+        });
+        let _ = editor.update(Message::FileSaved(
+            id,
+            saved,
+            Ok(PathBuf::from("undo.txt")),
+        ));
+
+        let reopened = FragmentContent {
+            is_loading: true,
+            ..FragmentContent::default()
+        };
+        let changed = FragmentContent {
+            is_loading: true,
+            ..FragmentContent::default()
+        };
+        let (reopened_id, changed_id) = (reopened.id, changed.id);
+        editor.fragments = vec![reopened, changed];
+
+        let _ = editor.update(Message::FileOpened(
+            reopened_id,
+            Ok(loaded("undo.txt", "two\n")),
+        ));
+        let _ = editor.update(Message::FileOpened(
+            changed_id,
+            Ok(loaded("undo.txt", "three\n")),
+        ));
+
+        assert!(editor.fragments[0].history.can_undo());
+        assert!(!editor.fragments[1].history.can_undo());
+
+        editor.activate(0);
+        let _ = editor.update(Message::Undo);
+
+        assert_eq!(editing::text(&editor.fragments[0].content), "one");
+    }
+
+    #[test]
+    fn line_numbers_select_whole_lines() {
+        let content = text_editor::Content::with_text("one\ntwo\nthree");
+
+        assert_eq!(line_numbers::line_at(0.0), 0);
+        assert_eq!(
+            line_numbers::line_at(
+                f32::from(line_numbers::PADDING)
+                    + 1.5 * line_numbers::LINE_HEIGHT
+            ),
+            1
+        );
+        assert_eq!(editing::lines_selection(&content, 1, 2), ((1, 0), (2, 5)));
+        assert_eq!(editing::lines_selection(&content, 1, 0), ((1, 3), (0, 0)));
+        assert_eq!(editing::lines_selection(&content, 0, 10), ((0, 0), (2, 5)));
+    }
 }
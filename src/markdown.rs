@@ -0,0 +1,258 @@
+//! Reading Markdown into the blocks its preview shows.
+//!
+//! Only the common syntax is understood: headings, paragraphs, lists,
+//! quotes, fenced code, tables and rules. Spans like emphasis and links
+//! cannot be styled within a line of text, so they are shown as their plain
+//! text.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// A heading, from 1 to 6.
+    Heading(u8, String),
+    Paragraph(String),
+    /// An item of a list, nested so many levels deep, with its bullet or
+    /// number.
+    Item {
+        depth: usize,
+        marker: String,
+        text: String,
+    },
+    Quote(String),
+    /// Fenced code, as it is written.
+    Code(String),
+    /// The rows of a table, as they are written.
+    Table(Vec<String>),
+    Rule,
+}
+
+/// Splits the text into blocks.
+pub fn parse(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut fence: Option<(String, Vec<&str>)> = None;
+    let mut last_blank = true;
+
+    for line in text.lines() {
+        if let Some((marker, code)) = &mut fence {
+            if line.trim_start().starts_with(marker.as_str()) {
+                blocks.push(Block::Code(code.join("\n")));
+                fence = None;
+            } else {
+                code.push(line);
+            }
+
+            continue;
+        }
+
+        let trimmed = line.trim();
+        let continues = !last_blank;
+        last_blank = trimmed.is_empty();
+
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some((trimmed[..3].to_owned(), Vec::new()));
+            continue;
+        }
+
+        // A paragraph underlined with `=` or `-` is a heading
+        if let (true, Some(Block::Paragraph(paragraph))) =
+            (continues, blocks.last())
+        {
+            let level = match trimmed {
+                _ if trimmed.chars().all(|c| c == '=') => Some(1),
+                _ if trimmed.chars().all(|c| c == '-') => Some(2),
+                _ => None,
+            };
+
+            if let Some(level) = level {
+                let heading = Block::Heading(level, paragraph.clone());
+                *blocks.last_mut().expect("a paragraph") = heading;
+                continue;
+            }
+        }
+
+        if is_rule(trimmed) {
+            blocks.push(Block::Rule);
+        } else if let Some((level, heading)) = heading(trimmed) {
+            blocks.push(Block::Heading(level, inline(heading)));
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            let quote = inline(quote.trim());
+
+            match blocks.last_mut() {
+                Some(Block::Quote(text)) if continues => {
+                    text.push(' ');
+                    text.push_str(&quote);
+                }
+                _ => blocks.push(Block::Quote(quote)),
+            }
+        } else if trimmed.starts_with('|') {
+            // The line under the header only separates it
+            if trimmed.chars().all(|c| matches!(c, '|' | '-' | ':' | ' ')) {
+                continue;
+            }
+
+            match blocks.last_mut() {
+                Some(Block::Table(rows)) if continues => {
+                    rows.push(trimmed.to_owned());
+                }
+                _ => blocks.push(Block::Table(vec![trimmed.to_owned()])),
+            }
+        } else if let Some((marker, text)) = item(trimmed) {
+            let indentation = line.len() - line.trim_start().len();
+
+            blocks.push(Block::Item {
+                depth: indentation / 2,
+                marker,
+                text: inline(text),
+            });
+        } else {
+            // Lines go on with the block above unless a blank line split
+            // them
+            match blocks.last_mut() {
+                Some(
+                    Block::Paragraph(text)
+                    | Block::Quote(text)
+                    | Block::Item { text, .. },
+                ) if continues => {
+                    text.push(' ');
+                    text.push_str(&inline(trimmed));
+                }
+                _ => blocks.push(Block::Paragraph(inline(trimmed))),
+            }
+        }
+    }
+
+    // A fence left open runs to the end
+    if let Some((_, code)) = fence {
+        blocks.push(Block::Code(code.join("\n")));
+    }
+
+    blocks
+}
+
+fn is_rule(line: &str) -> bool {
+    let marks: String = line.chars().filter(|c| *c != ' ').collect();
+
+    marks.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|mark| marks.chars().all(|c| c == *mark))
+}
+
+fn heading(line: &str) -> Option<(u8, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' '))
+    {
+        return None;
+    }
+
+    // Closing hashes are only decoration
+    let text = rest.trim().trim_end_matches('#').trim_end();
+
+    Some((level as u8, text))
+}
+
+/// Returns the bullet or number of a list item and its text.
+fn item(line: &str) -> Option<(String, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(text) = line.strip_prefix(bullet) {
+            // Task lists show their boxes
+            let marker = match text.get(..4) {
+                Some("[ ] ") => "☐",
+                Some("[x] " | "[X] ") => "☑",
+                _ => return Some((String::from("•"), text)),
+            };
+
+            return Some((marker.to_owned(), &text[4..]));
+        }
+    }
+
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let rest = &line[digits..];
+
+    if digits == 0 || digits > 9 {
+        return None;
+    }
+
+    rest.strip_prefix(". ")
+        .or_else(|| rest.strip_prefix(") "))
+        .map(|text| (format!("{}.", &line[..digits]), text))
+}
+
+/// Returns the plain text of a line, without the marks of its spans.
+fn inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut plain = String::with_capacity(text.len());
+    let mut index = 0;
+
+    let is_word = |index: Option<usize>| {
+        index
+            .and_then(|index| chars.get(index))
+            .is_some_and(|c| c.is_alphanumeric())
+    };
+
+    while index < chars.len() {
+        match chars[index] {
+            // Escaped characters are kept as they are
+            '\\' if index + 1 < chars.len() => {
+                plain.push(chars[index + 1]);
+                index += 2;
+                continue;
+            }
+            // Links and images show their text
+            '!' if chars.get(index + 1) == Some(&'[') => {}
+            '[' => {
+                if let Some((label, end)) = link(&chars, index) {
+                    plain.push_str(&inline(&label));
+                    index = end;
+                    continue;
+                }
+
+                plain.push('[');
+            }
+            '`' => {}
+            '~' if chars.get(index + 1) == Some(&'~') => {
+                index += 2;
+                continue;
+            }
+            // Emphasis marks stand at the edge of a word, not inside it
+            '*' | '_'
+                if !is_word(index.checked_sub(1))
+                    || !is_word(Some(index + 1)) =>
+            {
+                // One standing alone is not a mark
+                if index
+                    .checked_sub(1)
+                    .and_then(|previous| chars.get(previous))
+                    .is_none_or(|c| c.is_whitespace())
+                    && chars.get(index + 1).is_some_and(|c| c.is_whitespace())
+                {
+                    plain.push(chars[index]);
+                }
+            }
+            c => plain.push(c),
+        }
+
+        index += 1;
+    }
+
+    plain
+}
+
+/// Returns the label of the link starting with the bracket at the index and
+/// the index after the link, if it is one.
+fn link(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let close = (start + 1..chars.len()).find(|index| chars[*index] == ']')?;
+
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+
+    let end = (close + 2..chars.len()).find(|index| chars[*index] == ')')?;
+
+    Some((chars[start + 1..close].iter().collect(), end + 1))
+}
@@ -0,0 +1,73 @@
+//! The command palette, which runs an action found by typing part of its
+//! name.
+//!
+//! The letters typed have to appear in the name in the same order, but not
+//! next to each other, so `svall` finds "Save All" as well as "Save All and
+//! Exit". Names where they follow each other or start words come first.
+use std::cmp::Reverse;
+
+/// What is typed in the palette and which of its matches is picked.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Palette {
+    pub query: String,
+    /// The position of the picked one among the matches.
+    pub selected: usize,
+}
+
+impl Palette {
+    /// Picks the match that many places down, wrapping around the ends.
+    pub fn select(&mut self, offset: isize, count: usize) {
+        if count == 0 {
+            self.selected = 0;
+            return;
+        }
+
+        let count = count as isize;
+
+        self.selected =
+            (self.selected as isize + offset).rem_euclid(count) as usize;
+    }
+}
+
+/// Returns the indices of the names the query matches, best first.
+pub fn matches<'a>(
+    query: &str,
+    names: impl Iterator<Item = &'a str>,
+) -> Vec<usize> {
+    let mut matches: Vec<(usize, usize)> = names
+        .enumerate()
+        .filter_map(|(index, name)| Some((index, score(query, name)?)))
+        .collect();
+
+    // Equal matches keep the order they are listed in
+    matches.sort_by_key(|(_, score)| Reverse(*score));
+
+    matches.into_iter().map(|(index, _)| index).collect()
+}
+
+/// Scores how well the query matches the text, if it does at all.
+fn score(query: &str, text: &str) -> Option<usize> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (next..text.len()).find(|index| text[*index] == c)?;
+
+        score += 1;
+
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 2;
+        }
+
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+
+        previous = Some(found);
+        next = found + 1;
+    }
+
+    Some(score)
+}
@@ -0,0 +1,102 @@
+//! Completing the file paths typed in quotes, from the files on disk.
+//!
+//! A path is the text after an opening quote that is not closed yet, as long
+//! as it goes through a folder or starts with a dot, like `"src/ma` or
+//! `'../`. It is completed from the folder of the current file.
+use std::fs;
+use std::path::Path;
+
+/// How many files are offered at most.
+const LIMIT: usize = 10;
+
+/// The files a path being typed may go on to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// The names of the files, with a `/` after the folders.
+    pub items: Vec<String>,
+    pub selected: usize,
+    /// How many bytes of the name being completed are typed already.
+    typed: usize,
+}
+
+impl Completion {
+    /// Offers the files the path ending the text may go on to, from the
+    /// given folder.
+    pub fn new(folder: &Path, text: &str) -> Option<Self> {
+        let path = typed_path(text)?;
+        let (parent, name) = match path.rfind('/') {
+            Some(slash) => path.split_at(slash + 1),
+            None => ("", path),
+        };
+
+        let mut items: Vec<String> = fs::read_dir(folder.join(parent))
+            .ok()?
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let mut file_name = entry.file_name().into_string().ok()?;
+
+                // Hidden files only show once their dot is typed
+                if !file_name.starts_with(name)
+                    || (file_name.starts_with('.') && !name.starts_with('.'))
+                {
+                    return None;
+                }
+
+                if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                    file_name.push('/');
+                }
+
+                (file_name != name).then_some(file_name)
+            })
+            .collect();
+
+        if items.is_empty() {
+            return None;
+        }
+
+        items.sort();
+        items.truncate(LIMIT);
+
+        Some(Self {
+            items,
+            selected: 0,
+            typed: name.len(),
+        })
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.items.len();
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected =
+            (self.selected + self.items.len() - 1) % self.items.len();
+    }
+
+    /// Returns what is left to type of the item at the index.
+    pub fn rest(&self, index: usize) -> Option<&str> {
+        self.items.get(index).map(|item| &item[self.typed..])
+    }
+}
+
+/// Returns the path being typed at the end of the text, if it is in quotes
+/// and looks like one.
+fn typed_path(text: &str) -> Option<&str> {
+    let mut open = None;
+
+    for (index, c) in text.char_indices() {
+        match open {
+            None if matches!(c, '"' | '\'' | '`') => {
+                open = Some((index + c.len_utf8(), c));
+            }
+            Some((_, quote)) if c == quote => open = None,
+            _ => {}
+        }
+    }
+
+    let path = &text[open?.0..];
+    let looks_like_path = (path.contains('/') || path.starts_with('.'))
+        && !path.contains(char::is_whitespace);
+
+    looks_like_path.then_some(path)
+}
@@ -0,0 +1,864 @@
+//! User preferences that change how the editor behaves.
+//!
+//! They are read on startup from a `key = value` file in the user's config
+//! directory, one preference per line, with `#` starting a comment.
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::auto_indent::Rules;
+use crate::editing;
+use crate::glob;
+use crate::indent::Indent;
+use crate::language::Language;
+use crate::related::Group;
+
+#[derive(Debug, Clone)]
+pub struct Preferences {
+    pub trailing_newline: TrailingNewline,
+    /// Whether saving removes the spaces and tabs at the end of lines.
+    pub trim_trailing_whitespace: bool,
+    /// Whether multi-line pastes in brace languages follow the brace depth
+    /// of the paste site.
+    pub reindent_paste: bool,
+    /// The brace languages pastes are reindented in, by lowercase name, or
+    /// all of them when empty.
+    pub reindent_paste_languages: Vec<String>,
+    /// Whether the status bar shows who last changed the current line.
+    pub show_blame: bool,
+    pub startup: Startup,
+    /// The toolbar buttons, in order.
+    pub toolbar: Vec<ToolbarAction>,
+    /// Whether files opened from the command line go to a running editor.
+    pub single_instance: bool,
+    pub tab_bar_position: TabBarPosition,
+    pub tab_overflow: TabOverflow,
+    pub highlight_background: HighlightBackground,
+    /// How many tabs can be open before the least recently used clean one
+    /// is closed for a new one, if there is a limit.
+    pub max_tabs: Option<usize>,
+    /// Whether the line opening the scope around the cursor stays pinned
+    /// above the editor.
+    pub sticky_scroll: bool,
+    /// Whether the lines are numbered in a column left of the editor.
+    pub show_line_numbers: bool,
+    /// Whether saving offers to create the missing folders of a path.
+    pub create_directories: bool,
+    /// Whether files with a path are saved once edits pause.
+    pub auto_save: bool,
+    /// How long edits have to pause for before auto-save saves.
+    pub auto_save_delay: Duration,
+    /// The paths auto-save leaves for saving by hand, as globs.
+    pub auto_save_exclude: Vec<String>,
+    /// The external programs the current file can be opened with.
+    pub tools: Vec<Tool>,
+    /// The groups of related files tried before the built-in ones.
+    pub related_files: Vec<Group>,
+    /// The indentation of languages, by lowercase name, where it differs
+    /// from their defaults.
+    pub indents: Vec<(String, Indent)>,
+    /// How many columns a level of indentation spans, over the defaults of
+    /// the languages.
+    pub indent_width: Option<usize>,
+    /// Whether levels of indentation are spaces rather than tabs, over the
+    /// defaults of the languages.
+    pub use_spaces: Option<bool>,
+    /// Whether new lines and closings are indented as they are typed.
+    pub auto_indent: bool,
+    /// Whether Enter between brackets, like `{}`, puts the closing one on a
+    /// line of its own below an indented one.
+    pub expand_brackets: bool,
+    /// The auto-indent rules of languages, by lowercase name, where they
+    /// differ from the built-in ones.
+    pub indent_rules: Vec<(String, Rules)>,
+    /// The column long lines are broken at by hard wrapping.
+    pub wrap_column: usize,
+    /// The extension whose syntax files without one are highlighted with.
+    pub new_file_language: String,
+    /// How many replacements Replace All makes before asking first, if it
+    /// asks at all.
+    pub confirm_replace_all: Option<usize>,
+    /// How many tabs Close All closes before asking first, if it asks at
+    /// all.
+    pub confirm_close_all: Option<usize>,
+    /// How many files Save All saves before asking first, if it asks at
+    /// all.
+    pub confirm_save_all: Option<usize>,
+    /// Whether deleting the whole text of a file asks first.
+    pub confirm_empty_buffer: bool,
+    /// Whether the status bar counts the characters that cannot be seen,
+    /// like zero-width and non-breaking spaces.
+    pub show_invisibles: bool,
+    /// How long a file changed on disk has to stay unchanged before it is
+    /// reloaded, so files being written to are not reloaded on every write.
+    pub reload_debounce: Duration,
+    /// How many hexadecimal digits or base64 characters the inserted random
+    /// tokens have.
+    pub token_length: usize,
+    /// How many blank lines in a row collapsing blank lines leaves.
+    pub max_blank_lines: usize,
+    /// Whether collapsing blank lines removes the ones at the start and end
+    /// of the text, rather than shortening them like the others.
+    pub trim_blank_edges: bool,
+    /// The folder exported snippets are written to, if not the one next to
+    /// the preferences.
+    pub snippets_directory: Option<PathBuf>,
+    /// How many bytes a file can have and still be highlighted, if there
+    /// is a limit.
+    pub max_highlight_size: Option<usize>,
+    /// How many lines a file can have and still be highlighted, if there
+    /// is a limit.
+    pub max_highlight_lines: Option<usize>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            trailing_newline: TrailingNewline::default(),
+            trim_trailing_whitespace: false,
+            reindent_paste: false,
+            reindent_paste_languages: Vec::new(),
+            show_blame: false,
+            startup: Startup::default(),
+            toolbar: ToolbarAction::ALL.to_vec(),
+            single_instance: false,
+            tab_bar_position: TabBarPosition::default(),
+            tab_overflow: TabOverflow::default(),
+            highlight_background: HighlightBackground::default(),
+            max_tabs: None,
+            sticky_scroll: false,
+            show_line_numbers: false,
+            create_directories: true,
+            auto_save: false,
+            auto_save_delay: Duration::from_secs(3),
+            auto_save_exclude: Vec::new(),
+            tools: Vec::new(),
+            related_files: Vec::new(),
+            indents: Vec::new(),
+            indent_width: None,
+            use_spaces: None,
+            auto_indent: true,
+            expand_brackets: true,
+            indent_rules: Vec::new(),
+            wrap_column: 80,
+            new_file_language: String::from("rs"),
+            confirm_replace_all: Some(100),
+            confirm_close_all: Some(10),
+            confirm_save_all: Some(10),
+            confirm_empty_buffer: false,
+            show_invisibles: true,
+            reload_debounce: Duration::from_millis(500),
+            token_length: 32,
+            max_blank_lines: 1,
+            trim_blank_edges: false,
+            snippets_directory: None,
+            max_highlight_size: Some(2_000_000),
+            max_highlight_lines: Some(50_000),
+        }
+    }
+}
+
+impl Preferences {
+    /// Written to a new preferences file to list what can be set.
+    pub const TEMPLATE: &'static str = "\
+# trailing_newline = single | keep | none
+# trim_trailing_whitespace = false
+# reindent_paste = false
+# reindent_paste_languages = <language>, like rust, c, go
+# show_blame = false
+# startup = default | empty | welcome | open <path>
+# toolbar = new, open, save, save_as, save_all, undo, redo, select_all, cut,
+#     copy, paste, reload, new_tab, duplicate_tab, close_all, record, play
+# single_instance = false
+# tab_bar_position = top | bottom
+# tab_overflow = scroll | wrap
+# highlight_background = app | theme
+# max_tabs = off | <count>
+# sticky_scroll = false
+# show_line_numbers = false
+# create_directories = true
+# auto_save = false
+# auto_save_delay = <milliseconds>
+# auto_save_exclude = <glob>, like secrets/** or *.env
+# open_with = <name>: <command> {file}
+# related_files = {name}.h | {name}.c
+# indent = <language>: spaces <width> | tabs <width>
+# indent_width = <columns>
+# use_spaces = true
+# auto_indent = true
+# expand_brackets = true
+# indent_rules = <language>: after <tokens> dedent <tokens>
+# wrap_column = 80
+# default_new_file_language = <language or extension>
+# confirm_replace_all = off | <count>
+# confirm_close_all = off | <count>
+# confirm_save_all = off | <count>
+# confirm_empty_buffer = false
+# show_invisibles = true
+# reload_debounce = <milliseconds>
+# token_length = 32
+# max_blank_lines = 1
+# trim_blank_edges = false
+# snippets_directory = <path>
+# max_highlight_size = off | <bytes>
+# max_highlight_lines = off | <count>
+";
+
+    /// Returns the path of the preferences file, if there is a config
+    /// directory to put it in.
+    pub fn path() -> Option<PathBuf> {
+        let config = env::var_os("XDG_CONFIG_HOME")
+            .or_else(|| env::var_os("APPDATA"))
+            .map(PathBuf::from)
+            .or_else(|| {
+                env::var_os("HOME")
+                    .map(|home| PathBuf::from(home).join(".config"))
+            })?;
+
+        Some(config.join("iced_proto").join("preferences"))
+    }
+
+    /// Reads the preferences file, keeping the defaults for anything that is
+    /// missing or malformed.
+    pub fn load() -> Self {
+        let text = Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+
+        Self::parse(&text)
+    }
+
+    /// Returns the preferences file text with the key set to the value,
+    /// replacing its lines or else adding one at the end.
+    pub fn with_value(text: &str, key: &str, value: &str) -> String {
+        let setting = format!("{key} = {value}");
+        let mut found = false;
+
+        let mut lines: Vec<&str> = text
+            .lines()
+            .map(|line| {
+                let is_key = !line.trim_start().starts_with('#')
+                    && line
+                        .split_once('=')
+                        .is_some_and(|(name, _)| name.trim() == key);
+
+                if is_key {
+                    found = true;
+                    setting.as_str()
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        if !found {
+            lines.push(&setting);
+        }
+
+        let mut text = lines.join("\n");
+        text.push('\n');
+        text
+    }
+
+    /// Returns the folder exported snippets are written to, if there is
+    /// one.
+    pub fn snippets_directory(&self) -> Option<PathBuf> {
+        self.snippets_directory
+            .clone()
+            .or_else(|| Some(Self::path()?.with_file_name("snippets")))
+    }
+
+    /// Whether auto-save may save the file at the path.
+    pub fn auto_saves(&self, path: &Path) -> bool {
+        self.auto_save
+            && !self
+                .auto_save_exclude
+                .iter()
+                .any(|pattern| glob::matches(pattern, path))
+    }
+
+    /// Whether multi-line pastes are reindented in the given language.
+    pub fn reindents_paste(&self, language: &Language) -> bool {
+        let name = language.name.to_lowercase();
+
+        self.reindent_paste
+            && language.braces
+            && (self.reindent_paste_languages.is_empty()
+                || self.reindent_paste_languages.contains(&name))
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut preferences = Self::default();
+
+        for line in text.lines().map(str::trim) {
+            if line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key.trim() {
+                "trailing_newline" => {
+                    if let Some(trailing_newline) =
+                        TrailingNewline::from_key(value)
+                    {
+                        preferences.trailing_newline = trailing_newline;
+                    }
+                }
+                "trim_trailing_whitespace" => {
+                    if let Ok(trim) = value.parse() {
+                        preferences.trim_trailing_whitespace = trim;
+                    }
+                }
+                "reindent_paste" => {
+                    if let Ok(reindent_paste) = value.parse() {
+                        preferences.reindent_paste = reindent_paste;
+                    }
+                }
+                "reindent_paste_languages" => {
+                    preferences.reindent_paste_languages = value
+                        .split(',')
+                        .map(|language| language.trim().to_lowercase())
+                        .filter(|language| !language.is_empty())
+                        .collect();
+                }
+                "show_blame" => {
+                    if let Ok(show_blame) = value.parse() {
+                        preferences.show_blame = show_blame;
+                    }
+                }
+                "startup" => {
+                    if let Some(startup) = Startup::from_key(value) {
+                        preferences.startup = startup;
+                    }
+                }
+                "single_instance" => {
+                    if let Ok(single_instance) = value.parse() {
+                        preferences.single_instance = single_instance;
+                    }
+                }
+                "tab_bar_position" => {
+                    if let Some(position) = TabBarPosition::from_key(value) {
+                        preferences.tab_bar_position = position;
+                    }
+                }
+                "tab_overflow" => {
+                    if let Some(overflow) = TabOverflow::from_key(value) {
+                        preferences.tab_overflow = overflow;
+                    }
+                }
+                "highlight_background" => {
+                    if let Some(background) =
+                        HighlightBackground::from_key(value)
+                    {
+                        preferences.highlight_background = background;
+                    }
+                }
+                "max_tabs" => {
+                    if value == "off" {
+                        preferences.max_tabs = None;
+                    } else if let Ok(max_tabs) = value.parse() {
+                        preferences.max_tabs =
+                            Some(max_tabs).filter(|max_tabs| *max_tabs > 0);
+                    }
+                }
+                "confirm_replace_all" => {
+                    if value == "off" {
+                        preferences.confirm_replace_all = None;
+                    } else if let Ok(count) = value.parse() {
+                        preferences.confirm_replace_all = Some(count);
+                    }
+                }
+                "confirm_close_all" => {
+                    if value == "off" {
+                        preferences.confirm_close_all = None;
+                    } else if let Ok(count) = value.parse() {
+                        preferences.confirm_close_all = Some(count);
+                    }
+                }
+                "max_highlight_size" => {
+                    if value == "off" {
+                        preferences.max_highlight_size = None;
+                    } else if let Ok(size) = value.parse() {
+                        preferences.max_highlight_size = Some(size);
+                    }
+                }
+                "max_highlight_lines" => {
+                    if value == "off" {
+                        preferences.max_highlight_lines = None;
+                    } else if let Ok(count) = value.parse() {
+                        preferences.max_highlight_lines = Some(count);
+                    }
+                }
+                "confirm_save_all" => {
+                    if value == "off" {
+                        preferences.confirm_save_all = None;
+                    } else if let Ok(count) = value.parse() {
+                        preferences.confirm_save_all = Some(count);
+                    }
+                }
+                "confirm_empty_buffer" => {
+                    if let Ok(confirm) = value.parse() {
+                        preferences.confirm_empty_buffer = confirm;
+                    }
+                }
+                "show_invisibles" => {
+                    if let Ok(show_invisibles) = value.parse() {
+                        preferences.show_invisibles = show_invisibles;
+                    }
+                }
+                "reload_debounce" => {
+                    if let Ok(milliseconds) = value.parse() {
+                        preferences.reload_debounce =
+                            Duration::from_millis(milliseconds);
+                    }
+                }
+                "token_length" => {
+                    if let Ok(length) = value.parse::<usize>() {
+                        if length > 0 {
+                            preferences.token_length = length;
+                        }
+                    }
+                }
+                "max_blank_lines" => {
+                    if let Ok(max) = value.parse() {
+                        preferences.max_blank_lines = max;
+                    }
+                }
+                "trim_blank_edges" => {
+                    if let Ok(trim) = value.parse() {
+                        preferences.trim_blank_edges = trim;
+                    }
+                }
+                "snippets_directory" if !value.is_empty() => {
+                    preferences.snippets_directory = Some(PathBuf::from(value));
+                }
+                "sticky_scroll" => {
+                    if let Ok(sticky_scroll) = value.parse() {
+                        preferences.sticky_scroll = sticky_scroll;
+                    }
+                }
+                "show_line_numbers" => {
+                    if let Ok(show_line_numbers) = value.parse() {
+                        preferences.show_line_numbers = show_line_numbers;
+                    }
+                }
+                "create_directories" => {
+                    if let Ok(create_directories) = value.parse() {
+                        preferences.create_directories = create_directories;
+                    }
+                }
+                "auto_save" => {
+                    if let Ok(auto_save) = value.parse() {
+                        preferences.auto_save = auto_save;
+                    }
+                }
+                "auto_save_delay" => {
+                    if let Ok(milliseconds) = value.parse() {
+                        preferences.auto_save_delay =
+                            Duration::from_millis(milliseconds);
+                    }
+                }
+                "auto_save_exclude" if !value.is_empty() => {
+                    preferences.auto_save_exclude.push(value.to_owned());
+                }
+                "open_with" => {
+                    if let Some(tool) = Tool::from_key(value) {
+                        preferences.tools.push(tool);
+                    }
+                }
+                "related_files" => {
+                    if let Some(group) = Group::from_key(value) {
+                        preferences.related_files.push(group);
+                    }
+                }
+                "indent" => {
+                    let Some((language, indent)) = value.split_once(':') else {
+                        continue;
+                    };
+
+                    if let Some(indent) = Indent::from_key(indent.trim()) {
+                        preferences
+                            .indents
+                            .push((language.trim().to_lowercase(), indent));
+                    }
+                }
+                "indent_width" => {
+                    if let Ok(width) = value.parse::<usize>() {
+                        preferences.indent_width =
+                            Some(width).filter(|width| *width > 0);
+                    }
+                }
+                "use_spaces" => {
+                    if let Ok(use_spaces) = value.parse() {
+                        preferences.use_spaces = Some(use_spaces);
+                    }
+                }
+                "auto_indent" => {
+                    if let Ok(auto_indent) = value.parse() {
+                        preferences.auto_indent = auto_indent;
+                    }
+                }
+                "expand_brackets" => {
+                    if let Ok(expand_brackets) = value.parse() {
+                        preferences.expand_brackets = expand_brackets;
+                    }
+                }
+                "indent_rules" => {
+                    let Some((language, rules)) = value.split_once(':') else {
+                        continue;
+                    };
+
+                    if let Some(rules) = Rules::from_key(rules) {
+                        preferences
+                            .indent_rules
+                            .push((language.trim().to_lowercase(), rules));
+                    }
+                }
+                "wrap_column" => {
+                    if let Ok(wrap_column) = value.parse::<usize>() {
+                        if wrap_column > 0 {
+                            preferences.wrap_column = wrap_column;
+                        }
+                    }
+                }
+                "default_new_file_language" if !value.is_empty() => {
+                    // A language is known by its name or any extension
+                    preferences.new_file_language = Language::from_name(value)
+                        .map_or(value, |language| language.extensions[0])
+                        .to_owned();
+                }
+                "toolbar" => {
+                    preferences.toolbar = value
+                        .split(',')
+                        .filter_map(|key| ToolbarAction::from_key(key.trim()))
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+
+        preferences
+    }
+
+    /// Returns the text as saving leaves it in the editor: without trailing
+    /// whitespace if that is trimmed, and without the blank lines at the end
+    /// the newline policy collapses. The final newline itself is not shown.
+    pub fn saved_text(&self, text: &str) -> String {
+        let text = if self.trim_trailing_whitespace {
+            editing::trim_trailing_whitespace(text)
+        } else {
+            text.to_owned()
+        };
+
+        match self.trailing_newline {
+            TrailingNewline::Preserve => text,
+            TrailingNewline::EnsureSingle | TrailingNewline::Remove => {
+                text.trim_end_matches('\n').to_owned()
+            }
+        }
+    }
+
+    /// Whether a text of the given size is small enough to highlight.
+    pub fn highlights(&self, size: usize, line_count: usize) -> bool {
+        self.max_highlight_size.is_none_or(|max| size <= max)
+            && self.max_highlight_lines.is_none_or(|max| line_count <= max)
+    }
+
+    /// Returns how files of the given language are indented by default.
+    ///
+    /// The indentation set for the language wins over `indent_width` and
+    /// `use_spaces`, which win over the defaults of the language.
+    pub fn indent(&self, language: Option<&Language>) -> Indent {
+        let default = language.map_or(Indent::default(), |language| {
+            language.indent
+        });
+        let default = Indent {
+            insert_spaces: self.use_spaces.unwrap_or(default.insert_spaces),
+            width: self.indent_width.unwrap_or(default.width),
+        };
+
+        let Some(language) = language else {
+            return default;
+        };
+
+        self.indents
+            .iter()
+            .rev()
+            .find(|(name, _)| *name == language.name.to_lowercase())
+            .map_or(default, |(_, indent)| *indent)
+    }
+
+    /// Returns the auto-indent rules of the given language, which only copy
+    /// the indentation of the line above when there is no language.
+    pub fn indent_rules(&self, language: Option<&Language>) -> Rules {
+        let Some(language) = language else {
+            return Rules::default();
+        };
+
+        self.indent_rules
+            .iter()
+            .rev()
+            .find(|(name, _)| *name == language.name.to_lowercase())
+            .map_or_else(|| Rules::of(language), |(_, rules)| rules.clone())
+    }
+}
+
+/// An external program to open files with:
+/// `open_with = <name>: <command> {file}`, once per program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tool {
+    pub name: String,
+    /// The program and its arguments, where `{file}` stands for the path.
+    pub command: String,
+}
+
+impl Tool {
+    fn from_key(value: &str) -> Option<Self> {
+        let (name, command) = value.split_once(':')?;
+        let (name, command) = (name.trim(), command.trim());
+
+        if name.is_empty() || command.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            name: name.to_owned(),
+            command: command.to_owned(),
+        })
+    }
+
+    /// Returns the program to run on the given file, and its arguments.
+    pub fn command_line(&self, file: &Path) -> (String, Vec<String>) {
+        let file = file.display().to_string();
+        let mut words = self
+            .command
+            .split_whitespace()
+            .map(|word| word.replace("{file}", &file));
+
+        let program = words.next().unwrap_or_default();
+
+        (program, words.collect())
+    }
+}
+
+/// What the editor is painted with behind the highlighted text: the
+/// background of the app theme, or the one of the syntax theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HighlightBackground {
+    #[default]
+    App,
+    Theme,
+}
+
+impl HighlightBackground {
+    fn from_key(value: &str) -> Option<Self> {
+        match value {
+            "app" => Some(Self::App),
+            "theme" => Some(Self::Theme),
+            _ => None,
+        }
+    }
+}
+
+/// Where the tab bar sits: above the editor, or below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TabBarPosition {
+    #[default]
+    Top,
+    Bottom,
+}
+
+impl TabBarPosition {
+    fn from_key(value: &str) -> Option<Self> {
+        match value {
+            "top" => Some(Self::Top),
+            "bottom" => Some(Self::Bottom),
+            _ => None,
+        }
+    }
+}
+
+/// How the tabs that do not fit in the width of the window are shown: on
+/// a single row, or wrapped onto more rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TabOverflow {
+    #[default]
+    Scroll,
+    Wrap,
+}
+
+impl TabOverflow {
+    fn from_key(value: &str) -> Option<Self> {
+        match value {
+            "scroll" => Some(Self::Scroll),
+            "wrap" => Some(Self::Wrap),
+            _ => None,
+        }
+    }
+}
+
+/// What the editor opens on launch.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Startup {
+    /// The source of the editor itself: `startup = default`.
+    #[default]
+    DefaultFile,
+    /// A single empty buffer: `startup = empty`.
+    Empty,
+    /// A given file: `startup = open <path>`.
+    File(PathBuf),
+    /// The welcome screen: `startup = welcome`.
+    Welcome,
+}
+
+impl Startup {
+    fn from_key(value: &str) -> Option<Self> {
+        match value {
+            "default" => Some(Self::DefaultFile),
+            "empty" => Some(Self::Empty),
+            "welcome" => Some(Self::Welcome),
+            _ => value
+                .strip_prefix("open ")
+                .map(|path| Self::File(PathBuf::from(path.trim()))),
+        }
+    }
+}
+
+/// What happens to the newlines at the end of a file when it is saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingNewline {
+    #[default]
+    EnsureSingle,
+    Preserve,
+    Remove,
+}
+
+impl TrailingNewline {
+    pub const ALL: [Self; 3] =
+        [Self::EnsureSingle, Self::Preserve, Self::Remove];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::EnsureSingle => "single",
+            Self::Preserve => "keep",
+            Self::Remove => "none",
+        }
+    }
+
+    fn from_key(value: &str) -> Option<Self> {
+        match value {
+            "single" => Some(Self::EnsureSingle),
+            "keep" => Some(Self::Preserve),
+            "none" => Some(Self::Remove),
+            _ => None,
+        }
+    }
+
+    /// Applies the policy to the given text.
+    ///
+    /// `final_newline` tells whether the file ended with a newline when it
+    /// was loaded, which is what [`TrailingNewline::Preserve`] keeps.
+    pub fn apply(self, text: &str, final_newline: bool) -> String {
+        match self {
+            Self::EnsureSingle => {
+                let mut text = text.trim_end_matches('\n').to_owned();
+                text.push('\n');
+                text
+            }
+            Self::Preserve if final_newline => format!("{text}\n"),
+            Self::Preserve => text.to_owned(),
+            Self::Remove => text.trim_end_matches('\n').to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for TrailingNewline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::EnsureSingle => "Single final newline",
+            Self::Preserve => "Keep final newlines",
+            Self::Remove => "No final newline",
+        })
+    }
+}
+
+/// A button of the toolbar:
+/// `toolbar = new, open, save, save_as, save_all, undo, redo, select_all, cut,
+/// copy, paste, reload, new_tab, duplicate_tab, close_all, record, play`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolbarAction {
+    New,
+    Open,
+    Save,
+    SaveAs,
+    /// Saves every tab with changes.
+    SaveAll,
+    Undo,
+    Redo,
+    SelectAll,
+    Cut,
+    Copy,
+    Paste,
+    /// Reads the file again, discarding the changes.
+    Reload,
+    NewTab,
+    /// Opens an unsaved copy of the current tab.
+    DuplicateTab,
+    /// Closes every tab, asking about the unsaved changes.
+    CloseAll,
+    /// Starts or stops recording a macro.
+    Record,
+    /// Plays the macro, with its repeat count.
+    Play,
+}
+
+impl ToolbarAction {
+    pub const ALL: [Self; 17] = [
+        Self::New,
+        Self::Open,
+        Self::Save,
+        Self::SaveAs,
+        Self::SaveAll,
+        Self::Undo,
+        Self::Redo,
+        Self::SelectAll,
+        Self::Cut,
+        Self::Copy,
+        Self::Paste,
+        Self::Reload,
+        Self::NewTab,
+        Self::DuplicateTab,
+        Self::CloseAll,
+        Self::Record,
+        Self::Play,
+    ];
+
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "new" => Some(Self::New),
+            "open" => Some(Self::Open),
+            "save" => Some(Self::Save),
+            "save_as" => Some(Self::SaveAs),
+            "save_all" => Some(Self::SaveAll),
+            "undo" => Some(Self::Undo),
+            "redo" => Some(Self::Redo),
+            "select_all" => Some(Self::SelectAll),
+            "cut" => Some(Self::Cut),
+            "copy" => Some(Self::Copy),
+            "paste" => Some(Self::Paste),
+            "reload" => Some(Self::Reload),
+            "new_tab" => Some(Self::NewTab),
+            "duplicate_tab" => Some(Self::DuplicateTab),
+            "close_all" => Some(Self::CloseAll),
+            "record" => Some(Self::Record),
+            "play" => Some(Self::Play),
+            _ => None,
+        }
+    }
+}
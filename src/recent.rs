@@ -0,0 +1,65 @@
+//! The files opened or saved lately, offered again in the toolbar.
+//!
+//! They are kept next to the preferences as `file = <path>` lines, the most
+//! recent first.
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::preferences::Preferences;
+
+/// How many files are remembered.
+const LIMIT: usize = 10;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recent {
+    pub files: Vec<PathBuf>,
+}
+
+impl Recent {
+    /// Returns the path of the file the list is kept in.
+    pub fn path() -> Option<PathBuf> {
+        Some(Preferences::path()?.with_file_name("recent"))
+    }
+
+    /// Reads the list, which is empty if it was never written.
+    pub fn load() -> Self {
+        let text = Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+
+        let files = text
+            .lines()
+            .filter_map(|line| {
+                let (key, value) = line.split_once('=')?;
+
+                (key.trim() == "file").then(|| PathBuf::from(value.trim()))
+            })
+            .take(LIMIT)
+            .collect();
+
+        Self { files }
+    }
+
+    /// Puts the file first, moving it up if it was already listed.
+    pub fn add(&mut self, path: &Path) {
+        self.files.retain(|file| file != path);
+        self.files.insert(0, path.to_owned());
+        self.files.truncate(LIMIT);
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.files.retain(|file| file != path);
+    }
+
+    /// Writes the list out in the format [`Recent::load`] reads.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for file in &self.files {
+            let _ = writeln!(text, "file = {}", file.display());
+        }
+
+        text
+    }
+}
@@ -0,0 +1,126 @@
+//! The files that go together by their names, like a header and its source
+//! or a module and its tests.
+//!
+//! A group of related files is a list of paths where `{name}` stands for
+//! what they share, like `{name}.h | {name}.c`. A path may go through
+//! folders, like `{name}/mod.rs`, and is taken from the folder the group
+//! starts in.
+use std::path::{Path, PathBuf};
+
+/// The groups tried after the ones of the preferences.
+const BUILT_IN: &[&str] = &[
+    "{name}.h | {name}.c | {name}.cpp | {name}.cc",
+    "{name}.hpp | {name}.cpp",
+    "{name}.rs | {name}_test.rs",
+    "{name}/mod.rs | {name}.rs",
+    "{name}.go | {name}_test.go",
+    "{name}.py | test_{name}.py",
+    "{name}.js | {name}.test.js",
+    "{name}.ts | {name}.test.ts",
+];
+
+/// Files related by their names:
+/// `related_files = <path> | <path> ...`, once per group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub patterns: Vec<String>,
+}
+
+impl Group {
+    /// Parses the paths of a group, which needs two of them at least, each
+    /// holding `{name}` once.
+    pub fn from_key(value: &str) -> Option<Self> {
+        let patterns: Vec<String> = value
+            .split('|')
+            .map(|pattern| pattern.trim().to_owned())
+            .collect();
+
+        let valid = patterns.len() > 1
+            && patterns.iter().all(|pattern| {
+                pattern.matches("{name}").count() == 1
+                    && pattern.split('/').all(|part| !part.is_empty())
+            });
+
+        valid.then_some(Self { patterns })
+    }
+}
+
+/// Returns the files related to the given one, the likeliest first, whether
+/// they exist or not.
+///
+/// The paths that match more of the file's name are tried first, so that
+/// `foo_test.rs` goes with `foo.rs` rather than `foo_test_test.rs`.
+pub fn candidates(groups: &[Group], file: &Path) -> Vec<PathBuf> {
+    let built_in = BUILT_IN.iter().filter_map(|group| Group::from_key(group));
+    let groups: Vec<Group> = groups.iter().cloned().chain(built_in).collect();
+
+    let mut matches = Vec::new();
+
+    for group in &groups {
+        for (index, pattern) in group.patterns.iter().enumerate() {
+            if let Some((folder, name)) = capture(pattern, file) {
+                matches.push((pattern.len(), group, index, folder, name));
+            }
+        }
+    }
+
+    // The sort is stable, so groups keep their order among equals
+    matches.sort_by_key(|(length, ..)| std::cmp::Reverse(*length));
+
+    let mut candidates = Vec::new();
+
+    for (_, group, index, folder, name) in matches {
+        // The next path in the group comes first, so repeating the command
+        // goes around all of them
+        let count = group.patterns.len();
+        let others =
+            (1..count).map(|step| &group.patterns[(index + step) % count]);
+
+        for pattern in others {
+            let path = folder.join(pattern.replace("{name}", &name));
+
+            if path != file && !candidates.contains(&path) {
+                candidates.push(path);
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Returns the folder the pattern starts in and what `{name}` stands for,
+/// if the end of the path matches the pattern.
+fn capture(pattern: &str, file: &Path) -> Option<(PathBuf, String)> {
+    let parts: Vec<&str> = pattern.split('/').collect();
+    let components: Vec<&str> = file
+        .iter()
+        .rev()
+        .take(parts.len())
+        .map(|component| component.to_str())
+        .collect::<Option<_>>()?;
+
+    if components.len() < parts.len() {
+        return None;
+    }
+
+    let mut name = None;
+
+    for (part, component) in parts.iter().rev().zip(components) {
+        match part.split_once("{name}") {
+            Some((prefix, suffix)) => {
+                let captured = component
+                    .strip_prefix(prefix)?
+                    .strip_suffix(suffix)
+                    .filter(|captured| !captured.is_empty())?;
+
+                name = Some(captured.to_owned());
+            }
+            None if *part == component => {}
+            None => return None,
+        }
+    }
+
+    let folder = file.ancestors().nth(parts.len())?.to_owned();
+
+    Some((folder, name?))
+}
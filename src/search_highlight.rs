@@ -0,0 +1,91 @@
+//! Syntax highlighting with the occurrences of a search standing out.
+//!
+//! The syntax highlighter of iced colors the lines, and the occurrences are
+//! laid over it in bold, in the primary color of the theme.
+use std::ops::Range;
+
+use iced::advanced::text::highlighter::{self, Format};
+use iced::font::{self, Font};
+use iced::highlighter::Highlighter;
+use iced::Theme;
+
+use crate::find::Search;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub syntax: iced::highlighter::Settings,
+    /// The search whose occurrences are highlighted, if any.
+    pub search: Option<Search>,
+}
+
+pub struct SearchHighlighter {
+    syntax: Highlighter,
+    search: Option<Search>,
+}
+
+pub enum Highlight {
+    Syntax(iced::highlighter::Highlight),
+    Match,
+}
+
+impl Highlight {
+    pub fn to_format(&self, theme: &Theme) -> Format<Font> {
+        match self {
+            Self::Syntax(highlight) => highlight.to_format(),
+            Self::Match => Format {
+                color: Some(theme.palette().primary),
+                font: Some(Font {
+                    weight: font::Weight::Bold,
+                    ..Font::MONOSPACE
+                }),
+            },
+        }
+    }
+}
+
+impl highlighter::Highlighter for SearchHighlighter {
+    type Settings = Settings;
+    type Highlight = Highlight;
+
+    type Iterator<'a> =
+        Box<dyn Iterator<Item = (Range<usize>, Self::Highlight)> + 'a>;
+
+    fn new(settings: &Self::Settings) -> Self {
+        Self {
+            syntax: Highlighter::new(&settings.syntax),
+            search: settings.search.clone(),
+        }
+    }
+
+    fn update(&mut self, new_settings: &Self::Settings) {
+        // This highlights every line again, as the occurrences may differ
+        self.syntax.update(&new_settings.syntax);
+        self.search = new_settings.search.clone();
+    }
+
+    fn change_line(&mut self, line: usize) {
+        self.syntax.change_line(line);
+    }
+
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        let matches = self
+            .search
+            .as_ref()
+            .map(|search| search.matches(line))
+            .unwrap_or_default();
+
+        // Later spans win where they overlap, so the occurrences go last
+        Box::new(
+            self.syntax
+                .highlight_line(line)
+                .map(|(range, highlight)| (range, Highlight::Syntax(highlight)))
+                .chain(
+                    matches.into_iter().map(|range| (range, Highlight::Match)),
+                ),
+        )
+    }
+
+    fn current_line(&self) -> usize {
+        self.syntax.current_line()
+    }
+}
@@ -0,0 +1,66 @@
+//! The files open in the editor, reopened on the next launch.
+//!
+//! They are kept next to the preferences as `file = <path>` lines in tab
+//! order, with an `active = <path>` line for the current tab.
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::preferences::Preferences;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Session {
+    pub files: Vec<PathBuf>,
+    /// The file of the current tab, if it has one.
+    pub active: Option<PathBuf>,
+}
+
+impl Session {
+    /// Returns the path of the file the session is kept in.
+    pub fn path() -> Option<PathBuf> {
+        Some(Preferences::path()?.with_file_name("session"))
+    }
+
+    /// Reads the last session, if there is one, leaving out the files that
+    /// no longer exist.
+    pub fn load() -> Option<Self> {
+        let text = fs::read_to_string(Self::path()?).ok()?;
+
+        let mut session = Self::parse(&text);
+        session.files.retain(|path| path.exists());
+        Some(session)
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut session = Self::default();
+
+        for line in text.lines().map(str::trim) {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = PathBuf::from(value.trim());
+
+            match key.trim() {
+                "file" => session.files.push(value),
+                "active" => session.active = Some(value),
+                _ => {}
+            }
+        }
+
+        session
+    }
+
+    /// Writes the session out in the format [`Session::load`] reads.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for path in &self.files {
+            let _ = writeln!(text, "file = {}", path.display());
+        }
+        if let Some(active) = &self.active {
+            let _ = writeln!(text, "active = {}", active.display());
+        }
+
+        text
+    }
+}
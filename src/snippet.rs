@@ -0,0 +1,91 @@
+//! Snippet files, holding text taken from a file to insert again later.
+//!
+//! A snippet file starts with `key = value` lines telling what the text is,
+//! like its language and when it was taken, followed by a `---` line and the
+//! text itself.
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The extension of snippet files.
+pub const EXTENSION: &str = "snippet";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    pub name: String,
+    /// The name of the language of the text, or else the extension of the
+    /// file it was taken from.
+    pub language: String,
+    pub created: SystemTime,
+    pub text: String,
+}
+
+impl Snippet {
+    /// Returns the contents of the snippet file.
+    pub fn to_file(&self) -> String {
+        format!(
+            "name = {}\nlanguage = {}\ncreated = {}\n---\n{}",
+            self.name,
+            self.language,
+            timestamp(self.created),
+            self.text
+        )
+    }
+
+    /// Returns the path of the snippet file in the given folder.
+    pub fn path(&self, directory: &Path) -> PathBuf {
+        directory.join(format!("{}.{EXTENSION}", self.name))
+    }
+}
+
+/// Returns the name trimmed, if it can name a file in the snippets folder.
+pub fn valid_name(name: &str) -> Option<&str> {
+    let name = name.trim();
+
+    let valid = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\', ':'])
+        && !name.chars().any(char::is_control);
+
+    valid.then_some(name)
+}
+
+/// Writes the time in UTC, like `2024-03-01T12:30:00Z`.
+fn timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+
+    let (days, seconds) = (seconds / 86_400, seconds % 86_400);
+    let (year, month, day) = civil_date(days);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Returns the year, month and day of the days since 1970-01-01.
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    // Years are counted from March, so leap days come last
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
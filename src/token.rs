@@ -0,0 +1,61 @@
+//! Random identifiers to insert into the text, like UUIDs.
+//!
+//! The bytes come from the random number generator of the operating system,
+//! so the tokens are fit to be used as secrets.
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::rngs::OsRng;
+use rand::RngCore;
+
+use std::fmt::Write;
+
+/// Returns a random version 4 UUID, like
+/// `0b7f5a3e-9c1d-4e2a-8f6b-3d0c9e1a7b52`.
+pub fn uuid_v4() -> String {
+    let mut bytes = random_bytes(16);
+
+    // The version and variant bits
+    bytes[6] = bytes[6] & 0x0f | 0x40;
+    bytes[8] = bytes[8] & 0x3f | 0x80;
+
+    let mut uuid = String::with_capacity(36);
+
+    for (i, byte) in bytes.iter().enumerate() {
+        if matches!(i, 4 | 6 | 8 | 10) {
+            uuid.push('-');
+        }
+
+        let _ = write!(uuid, "{byte:02x}");
+    }
+
+    uuid
+}
+
+/// Returns a random token of the given number of hexadecimal digits.
+pub fn hex_token(length: usize) -> String {
+    let mut token = String::with_capacity(length + 1);
+
+    for byte in random_bytes(length.div_ceil(2)) {
+        let _ = write!(token, "{byte:02x}");
+    }
+
+    token.truncate(length);
+    token
+}
+
+/// Returns a random token of the given number of URL-safe base64
+/// characters.
+pub fn base64_token(length: usize) -> String {
+    let mut token =
+        URL_SAFE_NO_PAD.encode(random_bytes(length.div_ceil(4) * 3));
+
+    token.truncate(length);
+    token
+}
+
+fn random_bytes(count: usize) -> Vec<u8> {
+    let mut bytes = vec![0; count];
+
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
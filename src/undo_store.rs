@@ -0,0 +1,182 @@
+//! The undo history of the files saved lately, restored when they are
+//! opened again, as the session reopens them after a restart or a crash.
+//!
+//! It is kept next to the preferences in `[path]` sections, each with the
+//! text the file was saved with as a `text = ` line and the texts it can be
+//! undone to as `undo = <line> <column> <text>` lines, oldest first. The
+//! texts are base64, to keep them on one line.
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::history::{History, Snapshot};
+use crate::preferences::Preferences;
+
+/// How many files have their history kept.
+const FILES: usize = 10;
+
+/// How many groups of edits of a file can be undone after a restart.
+const SNAPSHOTS: usize = 20;
+
+/// How many bytes of text the kept history of a file holds at most.
+const SIZE: usize = 1 << 20;
+
+/// The remembered history of a file, which only applies while the file is
+/// still as it was saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Saved {
+    path: PathBuf,
+    text: String,
+    undo: Vec<Snapshot>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Store {
+    /// The files, the most recently saved first.
+    files: Vec<Saved>,
+}
+
+impl Store {
+    /// Returns the path of the file the histories are kept in.
+    pub fn path() -> Option<PathBuf> {
+        Some(Preferences::path()?.with_file_name("undo"))
+    }
+
+    /// Reads the histories, which are none if they were never written.
+    pub fn load() -> Self {
+        let text = Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+
+        Self::parse(&text)
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut files: Vec<Saved> = Vec::new();
+
+        for line in text.lines().map(str::trim) {
+            if let Some(path) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                files.push(Saved {
+                    path: PathBuf::from(path),
+                    text: String::new(),
+                    undo: Vec::new(),
+                });
+                continue;
+            }
+
+            let (Some(saved), Some((key, value))) =
+                (files.last_mut(), line.split_once('='))
+            else {
+                continue;
+            };
+
+            match key.trim() {
+                "text" => {
+                    if let Some(text) = decode(value.trim()) {
+                        saved.text = text;
+                    }
+                }
+                "undo" => {
+                    if let Some(snapshot) = snapshot(value) {
+                        saved.undo.push(snapshot);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        files.truncate(FILES);
+
+        Self { files }
+    }
+
+    /// Writes the histories out in the format [`Store::load`] reads.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for saved in &self.files {
+            let _ = writeln!(text, "[{}]", saved.path.display());
+            let _ = writeln!(text, "text = {}", STANDARD.encode(&saved.text));
+
+            for snapshot in &saved.undo {
+                let (line, column) = snapshot.cursor;
+
+                let _ = writeln!(
+                    text,
+                    "undo = {line} {column} {}",
+                    STANDARD.encode(&snapshot.text)
+                );
+            }
+        }
+
+        text
+    }
+
+    /// Remembers the history of a file just saved with the given text,
+    /// keeping as much of its end as fits.
+    pub fn update(&mut self, path: &Path, text: &str, history: &History) {
+        self.files.retain(|saved| saved.path != path);
+
+        let mut size = text.len();
+        let mut undo: Vec<Snapshot> = history
+            .undo_snapshots()
+            .iter()
+            .rev()
+            .take(SNAPSHOTS)
+            .take_while(|snapshot| {
+                size += snapshot.text.len();
+                size <= SIZE
+            })
+            .cloned()
+            .collect();
+        undo.reverse();
+
+        if undo.is_empty() {
+            return;
+        }
+
+        self.files.insert(
+            0,
+            Saved {
+                path: path.to_owned(),
+                text: text.to_owned(),
+                undo,
+            },
+        );
+        self.files.truncate(FILES);
+    }
+
+    /// Returns the history remembered for a file opened with the given
+    /// text, unless the file changed since it was saved.
+    pub fn history(&self, path: &Path, text: &str) -> Option<History> {
+        let saved = self
+            .files
+            .iter()
+            .find(|saved| saved.path == path && saved.text == text)?;
+
+        Some(History::with_undo(saved.undo.clone()))
+    }
+}
+
+fn decode(value: &str) -> Option<String> {
+    String::from_utf8(STANDARD.decode(value).ok()?).ok()
+}
+
+/// Reads an `undo` line, as its line, column and text.
+fn snapshot(value: &str) -> Option<Snapshot> {
+    let mut parts = value.split_whitespace();
+    let line = parts.next()?.parse().ok()?;
+    let column = parts.next()?.parse().ok()?;
+    let text = decode(parts.next().unwrap_or_default())?;
+
+    Some(Snapshot {
+        text,
+        cursor: (line, column),
+    })
+}
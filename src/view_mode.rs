@@ -0,0 +1,87 @@
+//! The ways a fragment can be shown besides the text editor.
+use std::fmt;
+use std::fmt::Write;
+
+/// How many bytes a line of the hex view shows.
+const BYTES_PER_LINE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ViewMode {
+    #[default]
+    Text,
+    /// A read-only dump of the bytes of the text.
+    Hex,
+}
+
+impl ViewMode {
+    pub const ALL: [Self; 2] = [Self::Text, Self::Hex];
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "text" => Some(Self::Text),
+            "hex" => Some(Self::Hex),
+            _ => None,
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Hex => "hex",
+        }
+    }
+
+    /// Guesses the mode a file is best shown in from its contents.
+    pub fn detect(contents: &str) -> Self {
+        if contents.contains('\0') {
+            Self::Hex
+        } else {
+            Self::Text
+        }
+    }
+}
+
+impl fmt::Display for ViewMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Text => "Text",
+            Self::Hex => "Hex",
+        })
+    }
+}
+
+/// Lays the bytes out like `hexdump -C`: offset, bytes in hex, then the
+/// printable ones as characters.
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+
+    for (i, line) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+        let _ = write!(dump, "{:08x} ", i * BYTES_PER_LINE);
+
+        for column in 0..BYTES_PER_LINE {
+            if column % 8 == 0 {
+                dump.push(' ');
+            }
+
+            match line.get(column) {
+                Some(byte) => {
+                    let _ = write!(dump, "{byte:02x} ");
+                }
+                None => dump.push_str("   "),
+            }
+        }
+
+        dump.push(' ');
+        dump.push('|');
+        dump.extend(line.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                char::from(byte)
+            } else {
+                '.'
+            }
+        }));
+        dump.push_str("|\n");
+    }
+
+    dump
+}